@@ -9,7 +9,7 @@ use crate::logout::{Logout, LogoutError};
 use crate::{AuthEditor, Config};
 
 /// TODO: docs.
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct Auth {
     pub(crate) config: Shared<Config>,
     pub(crate) credential_store: CredentialStore,
@@ -49,6 +49,7 @@ impl Auth {
         this.state.set_logged_in(auth_types::JsonWebToken::mock(
             auth_types::PeerHandle::GitHub(github_handle),
         ));
+        this.state.set_credentials_retrieved();
 
         this
     }
@@ -93,6 +94,7 @@ impl<Ed: AuthEditor> Module<Ed> for Auth {
                     tracing::error!("couldn't retrieve credentials: {err}")
                 },
             }
+            auth_state.set_credentials_retrieved();
         });
     }
 
@@ -1,13 +1,32 @@
+use std::sync::Arc;
+
 use auth_types::JsonWebToken;
 use editor::{Access, Shared};
 
+use crate::async_once_lock::AsyncOnceLock;
+
 /// TODO: docs.
 #[derive(Clone, Default)]
 pub struct AuthState {
     inner: Shared<Option<JsonWebToken>>,
+    retrieved: Arc<AsyncOnceLock<()>>,
 }
 
 impl AuthState {
+    /// Returns once the [`Auth`](crate::Auth) module has finished looking for
+    /// credentials persisted by a previous login, whether it found any or
+    /// not.
+    ///
+    /// Callers that need to know if the user is logged in right after the
+    /// editor has started should await this before checking the state.
+    pub async fn wait_credentials_retrieved(&self) {
+        self.retrieved.wait().await;
+    }
+
+    pub(crate) fn set_credentials_retrieved(&self) {
+        let _ = self.retrieved.set(());
+    }
+
     pub(crate) fn set_logged_in(&self, jwt: JsonWebToken) {
         self.inner.set(Some(jwt));
     }
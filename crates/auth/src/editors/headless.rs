@@ -0,0 +1,44 @@
+#![allow(missing_docs)]
+
+use auth_types::JsonWebToken;
+use editor::context::Borrowed;
+use editor::{Access, Context};
+use headless::Headless;
+use headless::notify::NotifyContextExt;
+
+use crate::{AuthEditor, config, github, login, logout};
+
+impl AuthEditor for Headless {
+    type LoginError = github::GitHubLoginError<Self>;
+
+    #[allow(clippy::manual_async_fn)]
+    fn credential_builder(
+        _: &mut Context<Self, Borrowed>,
+    ) -> impl Future<Output = Box<keyring::CredentialBuilder>> + Send + 'static
+    {
+        async { keyring::default_credential_builder() }
+    }
+
+    async fn login(
+        config: impl Access<config::Config>,
+        ctx: &mut Context<Self>,
+    ) -> Result<JsonWebToken, Self::LoginError> {
+        let jwt = github::login(config, ctx).await?;
+        ctx.notify_info(format_args!(
+            "Successfully logged in as '{}'",
+            jwt.claims().username.as_str()
+        ));
+        Ok(jwt)
+    }
+
+    fn on_login_error(
+        error: login::LoginError<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_error(error);
+    }
+
+    fn on_logout_error(error: logout::LogoutError, ctx: &mut Context<Self>) {
+        ctx.notify_error(error);
+    }
+}
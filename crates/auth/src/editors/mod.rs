@@ -1,5 +1,7 @@
 //! Contains the editor-specific implementations of [`AuthEditor`].
 
+#[cfg(feature = "headless")]
+pub mod headless;
 #[cfg(feature = "mock")]
 pub mod mock;
 #[cfg(feature = "neovim")]
@@ -0,0 +1,512 @@
+use core::mem;
+use core::ops::Range;
+
+use collab_types::PeerId;
+
+use crate::text::ByteOffset;
+
+/// The maximum number of children of an internal node, or of runs in a leaf.
+const MAX_CHILDREN: usize = 16;
+
+/// The minimum number of children of every node except the root.
+const MIN_CHILDREN: usize = MAX_CHILDREN / 2;
+
+/// Keeps track of which peer inserted each byte of a text file's contents.
+///
+/// cola already knows this, but it doesn't expose it, so we have to mirror
+/// every insertion and deletion applied to the text.
+///
+/// The runs are stored in the leaves of a B-tree where every node caches its
+/// byte length, so that both insertions and deletions are logarithmic in the
+/// number of runs.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "Vec<AuthorshipRun>"))]
+pub(crate) struct Authorship {
+    root: Node,
+}
+
+/// A contiguous byte range of a text file inserted by the same peer.
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct AuthorshipRun {
+    /// The length of the run, in bytes.
+    pub(crate) byte_len: usize,
+
+    /// The peer that inserted the run, or `None` if it's part of the text
+    /// the file was created with.
+    ///
+    /// We can't store the file's creator directly because that's not known
+    /// when the file's contents are first created.
+    pub(crate) inserted_by: Option<PeerId>,
+}
+
+/// An iterator over the runs of an [`Authorship`], in order.
+pub(crate) struct Runs<'a> {
+    /// The runs of the leaf we're currently in.
+    leaf: core::slice::Iter<'a, AuthorshipRun>,
+
+    /// The children left to visit at each level of the tree above the leaf.
+    stack: Vec<core::slice::Iter<'a, Node>>,
+}
+
+#[derive(Clone)]
+enum Node {
+    Internal(Children<Self>),
+    Leaf(Children<AuthorshipRun>),
+}
+
+/// The children of a node, together with the sum of their byte lengths.
+#[derive(Clone)]
+struct Children<T> {
+    byte_len: usize,
+    items: Vec<T>,
+}
+
+trait ByteLen {
+    fn byte_len(&self) -> usize;
+}
+
+impl Authorship {
+    /// Removes the given byte range from the runs.
+    #[inline]
+    pub(crate) fn delete(&mut self, byte_range: Range<ByteOffset>) {
+        if byte_range.is_empty() {
+            return;
+        }
+
+        self.root.delete(byte_range);
+
+        // Deleting could've left the root with a single child, or with no
+        // children at all if the whole text was deleted.
+        while let Node::Internal(children) = &mut self.root {
+            if children.items.len() > 1 {
+                break;
+            }
+            self.root = children.items.pop().unwrap_or_default();
+        }
+    }
+
+    /// Records that the given peer inserted `byte_len` bytes at the given
+    /// offset.
+    #[inline]
+    pub(crate) fn insert(
+        &mut self,
+        byte_offset: ByteOffset,
+        byte_len: usize,
+        inserted_by: PeerId,
+    ) {
+        if byte_len == 0 {
+            return;
+        }
+
+        let run = AuthorshipRun { byte_len, inserted_by: Some(inserted_by) };
+
+        if let Some(split) = self.root.insert(byte_offset, run) {
+            let root = mem::take(&mut self.root);
+            self.root = Node::Internal(Children::new(vec![root, split]));
+        }
+    }
+
+    #[inline]
+    pub(crate) fn new(byte_len: usize) -> Self {
+        let mut runs = Vec::new();
+        if byte_len > 0 {
+            runs.push(AuthorshipRun { byte_len, inserted_by: None });
+        }
+        Self { root: Node::Leaf(Children::new(runs)) }
+    }
+
+    /// Returns an iterator over the runs, in order.
+    ///
+    /// The runs cover the whole text. Two adjacent runs can have the same
+    /// author if they're stored in different leaves.
+    #[inline]
+    pub(crate) fn runs(&self) -> Runs<'_> {
+        Runs {
+            leaf: [].iter(),
+            stack: vec![core::slice::from_ref(&self.root).iter()],
+        }
+    }
+}
+
+impl Node {
+    /// Removes the given byte range, which must be non-empty and contained in
+    /// this node.
+    fn delete(&mut self, byte_range: Range<ByteOffset>) {
+        match self {
+            Self::Internal(children) => {
+                children.delete_in_children(byte_range)
+            },
+            Self::Leaf(runs) => runs.delete_in_runs(byte_range),
+        }
+    }
+
+    /// Inserts the given run at the given offset, returning the node split
+    /// off from this one if it overflowed.
+    fn insert(
+        &mut self,
+        byte_offset: ByteOffset,
+        run: AuthorshipRun,
+    ) -> Option<Self> {
+        match self {
+            Self::Internal(children) => children
+                .insert_in_children(byte_offset, run)
+                .map(Self::Internal),
+            Self::Leaf(runs) => {
+                runs.insert_in_runs(byte_offset, run).map(Self::Leaf)
+            },
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Internal(children) => children.items.len(),
+            Self::Leaf(runs) => runs.items.len(),
+        }
+    }
+
+    /// Appends the children of `other`, which must be at the same depth as
+    /// this node, to this node's.
+    fn merge(&mut self, other: Self) {
+        match (self, other) {
+            (Self::Internal(children), Self::Internal(other)) => {
+                children.append(other);
+            },
+            (Self::Leaf(runs), Self::Leaf(other)) => {
+                let last_idx = runs.items.len().saturating_sub(1);
+                runs.append(other);
+                runs.merge_with_next(last_idx);
+            },
+            _ => unreachable!("sibling nodes are always at the same depth"),
+        }
+    }
+
+    fn split_if_overflowing(&mut self) -> Option<Self> {
+        match self {
+            Self::Internal(children) => {
+                children.split_if_overflowing().map(Self::Internal)
+            },
+            Self::Leaf(runs) => runs.split_if_overflowing().map(Self::Leaf),
+        }
+    }
+}
+
+impl Children<Node> {
+    fn delete_in_children(&mut self, byte_range: Range<ByteOffset>) {
+        self.byte_len -= byte_range.len();
+
+        let mut child_offset = 0;
+        let mut child_idx = 0;
+
+        while child_offset < byte_range.end {
+            let child_len = self.items[child_idx].byte_len();
+            let child_end = child_offset + child_len;
+
+            if child_end > byte_range.start {
+                let start = byte_range.start.max(child_offset) - child_offset;
+                let end = byte_range.end.min(child_end) - child_offset;
+
+                if end - start == child_len {
+                    self.items.remove(child_idx);
+                } else {
+                    self.items[child_idx].delete(start..end);
+                    child_idx += 1;
+                }
+            } else {
+                child_idx += 1;
+            }
+
+            child_offset = child_end;
+        }
+
+        self.rebalance();
+    }
+
+    fn insert_in_children(
+        &mut self,
+        byte_offset: ByteOffset,
+        run: AuthorshipRun,
+    ) -> Option<Self> {
+        self.byte_len += run.byte_len;
+
+        // Offsets between two children go to the end of the first one, which
+        // is where a peer typing after its own text would be appending.
+        let mut child_offset = 0;
+        let mut child_idx = 0;
+
+        loop {
+            let child_len = self.items[child_idx].byte_len();
+            if child_offset + child_len >= byte_offset
+                || child_idx + 1 == self.items.len()
+            {
+                break;
+            }
+            child_offset += child_len;
+            child_idx += 1;
+        }
+
+        let child = &mut self.items[child_idx];
+
+        if let Some(split) = child.insert(byte_offset - child_offset, run) {
+            self.items.insert(child_idx + 1, split);
+        }
+
+        self.split_if_overflowing()
+    }
+
+    /// Merges every child with fewer than [`MIN_CHILDREN`] children with one
+    /// of its siblings.
+    fn rebalance(&mut self) {
+        let mut child_idx = 0;
+
+        while child_idx < self.items.len() && self.items.len() > 1 {
+            if self.items[child_idx].len() >= MIN_CHILDREN {
+                child_idx += 1;
+                continue;
+            }
+
+            let left_idx = child_idx.saturating_sub(1);
+            let right = self.items.remove(left_idx + 1);
+            let left = &mut self.items[left_idx];
+            left.merge(right);
+
+            if let Some(split) = left.split_if_overflowing() {
+                self.items.insert(left_idx + 1, split);
+            }
+
+            // The merged node could still be underflowing if both siblings
+            // were, so check it again.
+            child_idx = left_idx;
+        }
+    }
+}
+
+impl Children<AuthorshipRun> {
+    fn delete_in_runs(&mut self, byte_range: Range<ByteOffset>) {
+        self.byte_len -= byte_range.len();
+
+        let mut run_offset = 0;
+        let mut run_idx = 0;
+
+        while run_offset + self.items[run_idx].byte_len <= byte_range.start {
+            run_offset += self.items[run_idx].byte_len;
+            run_idx += 1;
+        }
+
+        let first_idx = run_idx;
+        let mut num_to_delete = byte_range.len();
+
+        while num_to_delete > 0 {
+            let run = &mut self.items[run_idx];
+
+            // Every run after the first one starts exactly at the start of
+            // the range, so this is zero for those.
+            let offset_in_run = byte_range.start - run_offset;
+            let num_deleted =
+                (run.byte_len - offset_in_run).min(num_to_delete);
+            run.byte_len -= num_deleted;
+            num_to_delete -= num_deleted;
+
+            if run.byte_len == 0 {
+                self.items.remove(run_idx);
+            } else {
+                run_offset += run.byte_len;
+                run_idx += 1;
+            }
+        }
+
+        // Deleting whole runs could've left two runs with the same author
+        // next to each other, but only around the start of the range.
+        self.merge_with_next(first_idx);
+        if let Some(prev_idx) = first_idx.checked_sub(1) {
+            self.merge_with_next(prev_idx);
+        }
+    }
+
+    fn insert_in_runs(
+        &mut self,
+        byte_offset: ByteOffset,
+        new_run: AuthorshipRun,
+    ) -> Option<Self> {
+        self.byte_len += new_run.byte_len;
+
+        let mut run_offset = 0;
+
+        for run_idx in 0..self.items.len() {
+            let run = self.items[run_idx];
+
+            if run_offset + run.byte_len < byte_offset {
+                run_offset += run.byte_len;
+                continue;
+            }
+
+            if run.inserted_by == new_run.inserted_by {
+                self.items[run_idx].byte_len += new_run.byte_len;
+                return None;
+            }
+
+            let offset_in_run = byte_offset - run_offset;
+
+            if offset_in_run == 0 {
+                self.items.insert(run_idx, new_run);
+            } else if offset_in_run == run.byte_len {
+                match self.items.get_mut(run_idx + 1) {
+                    Some(next) if next.inserted_by == new_run.inserted_by => {
+                        next.byte_len += new_run.byte_len;
+                    },
+                    _ => self.items.insert(run_idx + 1, new_run),
+                }
+            } else {
+                let run_end = AuthorshipRun {
+                    byte_len: run.byte_len - offset_in_run,
+                    inserted_by: run.inserted_by,
+                };
+                self.items[run_idx].byte_len = offset_in_run;
+                self.items
+                    .splice(run_idx + 1..run_idx + 1, [new_run, run_end]);
+            }
+
+            return self.split_if_overflowing();
+        }
+
+        debug_assert_eq!(run_offset, byte_offset);
+        self.items.push(new_run);
+        self.split_if_overflowing()
+    }
+
+    /// Merges the run at the given index with the next one if they have the
+    /// same author.
+    fn merge_with_next(&mut self, run_idx: usize) {
+        let (Some(run), Some(next)) =
+            (self.items.get(run_idx), self.items.get(run_idx + 1))
+        else {
+            return;
+        };
+
+        if run.inserted_by == next.inserted_by {
+            let next = self.items.remove(run_idx + 1);
+            self.items[run_idx].byte_len += next.byte_len;
+        }
+    }
+}
+
+impl<T: ByteLen> Children<T> {
+    fn append(&mut self, other: Self) {
+        self.byte_len += other.byte_len;
+        self.items.extend(other.items);
+    }
+
+    fn new(items: Vec<T>) -> Self {
+        Self { byte_len: items.iter().map(T::byte_len).sum(), items }
+    }
+
+    /// Splits off the second half of the children if there are more than
+    /// [`MAX_CHILDREN`] of them.
+    fn split_if_overflowing(&mut self) -> Option<Self> {
+        if self.items.len() <= MAX_CHILDREN {
+            return None;
+        }
+        let split = Self::new(self.items.split_off(self.items.len() / 2));
+        self.byte_len -= split.byte_len;
+        Some(split)
+    }
+}
+
+impl ByteLen for Node {
+    #[inline]
+    fn byte_len(&self) -> usize {
+        match self {
+            Self::Internal(children) => children.byte_len,
+            Self::Leaf(runs) => runs.byte_len,
+        }
+    }
+}
+
+impl ByteLen for AuthorshipRun {
+    #[inline]
+    fn byte_len(&self) -> usize {
+        self.byte_len
+    }
+}
+
+impl Default for Node {
+    #[inline]
+    fn default() -> Self {
+        Self::Leaf(Children::new(Vec::new()))
+    }
+}
+
+impl From<Vec<AuthorshipRun>> for Authorship {
+    #[inline]
+    fn from(runs: Vec<AuthorshipRun>) -> Self {
+        let mut nodes = chunks(runs)
+            .map(|runs| Node::Leaf(Children::new(runs)))
+            .collect::<Vec<_>>();
+
+        while nodes.len() > 1 {
+            nodes = chunks(nodes)
+                .map(|nodes| Node::Internal(Children::new(nodes)))
+                .collect();
+        }
+
+        Self { root: nodes.pop().unwrap_or_default() }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Authorship {
+    #[inline]
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        // Serialize the runs as a flat sequence so that the encoding doesn't
+        // depend on the shape of the tree.
+        let runs = self.runs().copied().collect::<Vec<_>>();
+        runs.serialize(serializer)
+    }
+}
+
+impl<'a> Iterator for Runs<'a> {
+    type Item = &'a AuthorshipRun;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(run) = self.leaf.next() {
+                return Some(run);
+            }
+
+            let node = loop {
+                match self.stack.last_mut()?.next() {
+                    Some(node) => break node,
+                    None => {
+                        self.stack.pop();
+                    },
+                }
+            };
+
+            match node {
+                Node::Internal(children) => {
+                    self.stack.push(children.items.iter())
+                },
+                Node::Leaf(runs) => self.leaf = runs.items.iter(),
+            }
+        }
+    }
+}
+
+/// Splits the given items into as few chunks of at most [`MAX_CHILDREN`]
+/// items as possible, with the items spread evenly between them.
+fn chunks<T>(items: Vec<T>) -> impl Iterator<Item = Vec<T>> {
+    let num_chunks = items.len().div_ceil(MAX_CHILDREN);
+    let mut num_left = items.len();
+    let mut items = items.into_iter();
+
+    (0..num_chunks).map(move |chunk_idx| {
+        let chunk_len = num_left / (num_chunks - chunk_idx);
+        num_left -= chunk_len;
+        items.by_ref().take(chunk_len).collect()
+    })
+}
@@ -1,6 +1,7 @@
 //! TODO: docs.
 
 mod annotation;
+mod authorship;
 pub mod binary;
 pub mod fs;
 mod project;
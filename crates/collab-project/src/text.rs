@@ -35,6 +35,7 @@ use crate::annotation::{
     Annotations,
     AnnotationsIter,
 };
+use crate::authorship::{self, Authorship};
 use crate::fs::{
     FileContents,
     FileMut,
@@ -48,6 +49,25 @@ use crate::project::{State, StateMut};
 /// TODO: docs.
 pub type ByteOffset = usize;
 
+/// A position in a text file that stays attached to the surrounding text as
+/// the file is edited, both locally and by remote peers.
+#[derive(Debug, Clone, Copy)]
+pub struct Anchor {
+    inner: cola::Anchor,
+}
+
+/// The side of its offset an [`Anchor`] is attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnchorBias {
+    /// The anchor is attached to the character before its offset, so text
+    /// later inserted at the offset ends up after it.
+    Left,
+
+    /// The anchor is attached to the character after its offset, so text
+    /// later inserted at the offset ends up before it.
+    Right,
+}
+
 /// TODO: docs.
 pub struct TextFile<'a, S = Visible> {
     inner: PuffFile<'a, S>,
@@ -139,6 +159,16 @@ pub struct TextFileSelections<'a, S = Visible> {
     file: TextFile<'a, S>,
 }
 
+/// An iterator over the byte ranges of a text file's contents, together with
+/// the [`PeerId`] of the peer that inserted them.
+///
+/// See [`TextFile::authorship`] for more infos.
+pub struct TextFileAuthorship<'a> {
+    created_by: PeerId,
+    offset: ByteOffset,
+    runs: core::iter::Peekable<authorship::Runs<'a>>,
+}
+
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct TextCtx {
@@ -150,6 +180,7 @@ pub(crate) struct TextCtx {
 #[derive(Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct TextContents {
+    authorship: Authorship,
     replica: LazyReplica,
     text: crop::Rope,
     text_backlog: TextBacklog,
@@ -188,12 +219,48 @@ struct TextBacklog {
 }
 
 impl<'a, S> TextFile<'a, S> {
+    /// Returns an iterator over the byte ranges of this file's contents,
+    /// together with the [`PeerId`] of the peer that inserted them.
+    ///
+    /// The ranges are yielded in order, they cover the whole file, and no
+    /// two adjacent ranges have the same author. The text the file was
+    /// created with is attributed to the peer that
+    /// [created](Self::created_by) it.
+    #[inline]
+    pub fn authorship(&self) -> TextFileAuthorship<'a> {
+        TextFileAuthorship {
+            created_by: self.created_by(),
+            offset: 0,
+            runs: self.text_contents().authorship.runs().peekable(),
+        }
+    }
+
     /// Returns a `Rope` containing the text file's contents.
     #[inline]
     pub fn contents(&self) -> &'a Rope {
         &self.text_contents().text
     }
 
+    /// Creates a new [`Anchor`] at the given offset.
+    ///
+    /// Anchors are local to this replica and are never sent to other peers.
+    #[inline]
+    pub fn create_anchor(
+        &self,
+        offset: ByteOffset,
+        bias: AnchorBias,
+    ) -> Anchor {
+        let bias = match bias {
+            AnchorBias::Left => cola::AnchorBias::Left,
+            AnchorBias::Right => cola::AnchorBias::Right,
+        };
+        let replica = self
+            .text_contents()
+            .replica
+            .get(self.state.local_id(), self.created_by());
+        Anchor { inner: replica.create_anchor(offset, bias) }
+    }
+
     /// Returns the [`PeerId`] of the peer that created this text file.
     #[inline]
     pub fn created_by(&self) -> PeerId {
@@ -221,6 +288,17 @@ impl<'a, S> TextFile<'a, S> {
         self.inner.local_id()
     }
 
+    /// Returns the current offset of the given [`Anchor`], or `None` if it
+    /// can't be resolved in this file (e.g. because it was created in a
+    /// different one).
+    #[inline]
+    pub fn resolve_anchor(&self, anchor: Anchor) -> Option<ByteOffset> {
+        self.text_contents()
+            .replica
+            .get(self.state.local_id(), self.created_by())
+            .resolve_anchor(anchor.inner)
+    }
+
     /// Returns an iterator over the selections in this text file.
     #[inline]
     pub fn selections(&self) -> TextFileSelections<'a, S> {
@@ -724,6 +802,8 @@ impl TextContents {
                 self.text_backlog.insert(insertion.text().clone(), text);
                 continue;
             };
+            let inserted_by = PeerId::new(insertion.text().inserted_by());
+            self.authorship.insert(byte_offset, text.len(), inserted_by);
             self.text.insert(byte_offset, &*text);
             replacements.push(TextReplacement {
                 deleted_range: byte_offset..byte_offset,
@@ -732,7 +812,9 @@ impl TextContents {
         }
 
         for (text, byte_offset) in replica.backlogged_insertions() {
+            let inserted_by = PeerId::new(text.inserted_by());
             let text = self.text_backlog.take(text);
+            self.authorship.insert(byte_offset, text.len(), inserted_by);
             self.text.insert(byte_offset, &*text);
             replacements.push(TextReplacement {
                 deleted_range: byte_offset..byte_offset,
@@ -744,6 +826,7 @@ impl TextContents {
             for deleted_range in
                 replica.integrate_deletion(&deletion).into_iter().rev()
             {
+                self.authorship.delete(deleted_range.clone());
                 self.text.delete(deleted_range.clone());
                 replacements.push(TextReplacement {
                     deleted_range,
@@ -754,6 +837,7 @@ impl TextContents {
 
         for byte_ranges in replica.backlogged_deletions() {
             for deleted_range in byte_ranges.into_iter().rev() {
+                self.authorship.delete(deleted_range.clone());
                 self.text.delete(deleted_range.clone());
                 replacements.push(TextReplacement {
                     deleted_range,
@@ -768,6 +852,7 @@ impl TextContents {
     #[inline]
     pub(crate) fn new(text: crop::Rope) -> Self {
         Self {
+            authorship: Authorship::new(text.byte_len()),
             replica: LazyReplica::new(text.byte_len()),
             text,
             text_backlog: TextBacklog::default(),
@@ -832,6 +917,7 @@ impl TextContents {
             let end = deleted_range.end;
             match start.cmp(&end) {
                 Ordering::Less => {
+                    self.authorship.delete(start..end);
                     self.text.delete(start..end);
                     let deletion = replica.deleted(start..end);
                     deletions.push(deletion);
@@ -841,6 +927,7 @@ impl TextContents {
             }
 
             if !inserted_text.is_empty() {
+                self.authorship.insert(start, inserted_text.len(), local_id);
                 self.text.insert(start, &*inserted_text);
                 let insertion = replica.inserted(start, inserted_text.len());
                 insertions.push((insertion, inserted_text));
@@ -1156,6 +1243,28 @@ impl<'a, S: IsVisible> Iterator for TextFileCursors<'a, S> {
     }
 }
 
+impl Iterator for TextFileAuthorship<'_> {
+    type Item = (Range<ByteOffset>, PeerId);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let run = self.runs.next()?;
+        let author = run.inserted_by.unwrap_or(self.created_by);
+        let start = self.offset;
+        self.offset += run.byte_len;
+
+        // Runs with different authors could still resolve to the same peer
+        // if one of them is the text the file was created with.
+        while let Some(next) = self.runs.next_if(|next| {
+            next.inserted_by.unwrap_or(self.created_by) == author
+        }) {
+            self.offset += next.byte_len;
+        }
+
+        Some((start..self.offset, author))
+    }
+}
+
 impl<'a> Iterator for Selections<'a> {
     type Item = SelectionRef<'a>;
 
@@ -0,0 +1,125 @@
+//! Contains the Unix domain socket that [agents](crate::agents) connect to
+//! when running inside an editor with access to the OS.
+
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::path::Path;
+use std::{fs, io};
+
+use abs_path::{AbsPath, AbsPathBuf};
+use async_net::unix::{UnixListener, UnixStream};
+use futures_util::{Stream, stream};
+
+/// The [`AgentListener`](crate::CollabEditor::AgentListener) used by the
+/// Neovim and headless editors.
+///
+/// The socket file is removed when the listener is dropped.
+pub struct AgentListener {
+    inner: Pin<Box<dyn Stream<Item = UnixStream>>>,
+    socket_path: AbsPathBuf,
+}
+
+impl AgentListener {
+    /// Starts listening on the Unix domain socket at the given path.
+    ///
+    /// If the path is taken by a socket left behind by a previous process
+    /// that's no longer listening on it, that socket is replaced.
+    ///
+    /// The socket is only readable and writable by the current user.
+    pub(crate) fn bind(socket_path: &AbsPath) -> io::Result<Self> {
+        let listener = match bind_private(socket_path.as_ref()) {
+            Ok(listener) => listener,
+            Err(err) if err.kind() == io::ErrorKind::AddrInUse => {
+                if std::os::unix::net::UnixStream::connect(socket_path).is_ok()
+                {
+                    return Err(err);
+                }
+                fs::remove_file(socket_path)?;
+                bind_private(socket_path.as_ref())?
+            },
+            Err(err) => return Err(err),
+        };
+
+        let inner = stream::unfold(listener, async |listener| {
+            let accepted = listener.accept().await;
+            match accepted {
+                Ok((stream, _)) => Some((stream, listener)),
+                Err(err) => {
+                    tracing::error!("couldn't accept agent connection: {err}");
+                    None
+                },
+            }
+        });
+
+        Ok(Self {
+            inner: Box::pin(inner),
+            socket_path: socket_path.to_owned(),
+        })
+    }
+}
+
+impl Stream for AgentListener {
+    type Item = UnixStream;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        ctx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(ctx)
+    }
+}
+
+impl Drop for AgentListener {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.socket_path);
+    }
+}
+
+/// Binds a listener to a socket at the given path which, unlike one created
+/// by [`UnixListener::bind`], is never accessible by other users, not even
+/// for the short time between creating the socket and changing its
+/// permissions.
+///
+/// Anyone who can connect to the socket can act in the session on behalf of
+/// the user, so the socket is first bound inside a fresh directory that only
+/// the user can access, and it's only linked at the given path once its
+/// permissions have been restricted.
+///
+/// Fails with [`AddrInUse`](io::ErrorKind::AddrInUse) if the path is already
+/// taken.
+fn bind_private(socket_path: &Path) -> io::Result<UnixListener> {
+    let (Some(parent), Some(file_name)) =
+        (socket_path.parent(), socket_path.file_name())
+    else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a valid socket path", socket_path.display()),
+        ));
+    };
+
+    // Socket paths can't be very long, so keep the directory's name short.
+    let private_dir = parent.join(format!(".{:08x}", fastrand::u32(..)));
+
+    fs::DirBuilder::new().mode(0o700).create(&private_dir)?;
+
+    let private_path = private_dir.join(file_name);
+
+    let bind = || -> io::Result<UnixListener> {
+        let listener = UnixListener::bind(&private_path)?;
+        fs::set_permissions(&private_path, fs::Permissions::from_mode(0o600))?;
+        fs::hard_link(&private_path, socket_path).map_err(|err| {
+            if err.kind() == io::ErrorKind::AlreadyExists {
+                io::ErrorKind::AddrInUse.into()
+            } else {
+                err
+            }
+        })?;
+        Ok(listener)
+    };
+
+    let listener = bind();
+    let _ = fs::remove_file(&private_path);
+    let _ = fs::remove_dir(&private_dir);
+    listener
+}
@@ -0,0 +1,856 @@
+//! Contains the machinery used to let local processes (e.g. code agents or
+//! scripts) take part in a session as separate peers.
+//!
+//! Agents connect to a Unix domain socket and talk to the session using
+//! newline-delimited [JSON-RPC 2.0][json-rpc] messages. Every connection
+//! joins the session as a new peer, so its edits, cursor and selection are
+//! attributed to it instead of to the local user.
+//!
+//! [json-rpc]: https://www.jsonrpc.org/specification
+
+use core::ops::Range;
+
+use abs_path::{AbsPath, AbsPathBuf};
+use collab_project::fs::{Directory, File, FileMut, Node, NodeMut};
+use collab_project::text::{CursorId, SelectionId, TextReplacement};
+use collab_server::client::MessageFragment;
+use collab_types::{Message, Peer, PeerId, crop};
+use editor::Context;
+use futures_util::io::{AsyncBufReadExt, AsyncRead, AsyncWrite};
+use futures_util::{
+    AsyncReadExt,
+    AsyncWriteExt,
+    FutureExt,
+    Sink,
+    SinkExt,
+    StreamExt,
+    select_biased,
+};
+use fxhash::FxHashMap;
+use serde::Deserialize;
+use serde_json::json;
+use smallvec::SmallVec;
+
+use crate::editors::CollabEditor;
+use crate::project::{IntegrateError, Project};
+use crate::reconnect::{Reconnected, Reconnector};
+use crate::session::ProjectAccess;
+
+/// The agents connected to a session.
+pub(crate) struct Agents {
+    /// Map from an agent's peer ID to its replica of the project.
+    replicas: FxHashMap<PeerId, AgentReplica>,
+
+    /// The receiver of the requests sent by the agents' connections.
+    request_rx: flume::Receiver<AgentRequest>,
+
+    /// The sender cloned by every agent connection.
+    request_tx: flume::Sender<AgentRequest>,
+
+    /// The path of the socket to listen for agents on, if enabled.
+    socket_path: Option<AbsPathBuf>,
+
+    /// Dropped together with the session, which stops the listener and all
+    /// the agent connections.
+    _stop_tx: flume::Sender<()>,
+
+    /// Cloned by the listener and the agent connections to know when to
+    /// stop.
+    stop_rx: flume::Receiver<()>,
+}
+
+/// An agent's replica of the project.
+///
+/// The replica is forked from the local one when the agent connects, and
+/// kept in sync by [`integrate`](Self::integrate)ing every message produced
+/// or received by the session from then on. The edits, cursor and selection
+/// created through it are owned by the agent's [`PeerId`].
+pub struct AgentReplica {
+    cursor_id: Option<CursorId>,
+    inner: collab_project::Project,
+    selection_id: Option<SelectionId>,
+}
+
+/// The type of error that can occur when an agent's request can't be
+/// fulfilled.
+#[derive(Debug, derive_more::Display, cauchy::Error, PartialEq, Eq)]
+pub enum AgentError {
+    /// The byte offset or range is out of bounds or not on a char boundary.
+    #[display("invalid byte range {}..{} in {_0}", _1.start, _1.end)]
+    InvalidRange(AbsPathBuf, Range<usize>),
+
+    /// There's no file at the given path.
+    #[display("no file at {_0}")]
+    NoFileAtPath(AbsPathBuf),
+
+    /// The file at the given path is not a text file.
+    #[display("{_0} is not a text file")]
+    NotTextFile(AbsPathBuf),
+
+    /// The agent tried to edit the project while being read-only.
+    #[display("the agent is not allowed to edit the project")]
+    ReadOnly,
+}
+
+/// A request sent by an agent's connection to the session's event loop.
+pub(crate) struct AgentRequest {
+    agent_id: PeerId,
+    kind: AgentRequestKind,
+    response_tx: flume::Sender<Result<serde_json::Value, AgentError>>,
+}
+
+enum AgentRequestKind {
+    /// The agent has joined the session as the given peer.
+    Attach(Peer),
+
+    /// The agent has called one of the methods.
+    Call(Call),
+
+    /// The agent has disconnected.
+    Detach,
+}
+
+/// The methods that agents can call.
+enum Call {
+    /// Applies the given replacements to a text file, in order.
+    Edit { path: AbsPathBuf, replacements: Vec<TextReplacement> },
+
+    /// Returns the paths of all the files in the project.
+    ListFiles,
+
+    /// Moves the agent's cursor to the given offset in a text file.
+    MoveCursor { path: AbsPathBuf, offset: usize },
+
+    /// Returns the contents of a text file.
+    ReadFile { path: AbsPathBuf },
+
+    /// Moves the agent's selection to the given range in a text file.
+    Select { path: AbsPathBuf, range: Range<usize> },
+
+    /// Removes the agent's selection, if any.
+    Unselect,
+}
+
+/// A JSON-RPC request.
+#[derive(Deserialize)]
+struct RpcRequest {
+    id: Option<serde_json::Value>,
+    method: String,
+    #[serde(default)]
+    params: serde_json::Value,
+}
+
+/// A JSON-RPC error.
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct PathParams {
+    path: String,
+}
+
+#[derive(Deserialize)]
+struct EditParams {
+    path: String,
+    replacements: Vec<ReplacementParams>,
+}
+
+#[derive(Deserialize)]
+struct ReplacementParams {
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct MoveCursorParams {
+    path: String,
+    offset: usize,
+}
+
+#[derive(Deserialize)]
+struct SelectParams {
+    path: String,
+    start: usize,
+    end: usize,
+}
+
+impl Agents {
+    /// Creates a new `Agents`, which will listen for agents on the socket at
+    /// the given path (if any) once the session starts.
+    pub(crate) fn new(socket_path: Option<AbsPathBuf>) -> Self {
+        let (request_tx, request_rx) = flume::unbounded();
+        let (stop_tx, stop_rx) = flume::bounded(0);
+        Self {
+            replicas: FxHashMap::default(),
+            request_rx,
+            request_tx,
+            socket_path,
+            _stop_tx: stop_tx,
+            stop_rx,
+        }
+    }
+
+    /// Handles a request sent by an agent's connection, returning the
+    /// messages produced by the agent and the ones produced by integrating
+    /// them into the local project, all of which should be sent to the
+    /// other peers.
+    pub(crate) async fn handle<Ed: CollabEditor>(
+        &mut self,
+        request: AgentRequest,
+        project: &mut Project<Ed>,
+        ctx: &mut Context<Ed>,
+    ) -> Result<
+        (SmallVec<[Message; 2]>, SmallVec<[Message; 2]>),
+        IntegrateError<Ed>,
+    > {
+        let AgentRequest { agent_id, kind, response_tx } = request;
+
+        let mut messages = SmallVec::new();
+        let mut derived = SmallVec::new();
+
+        let response = match kind {
+            AgentRequestKind::Attach(peer) => {
+                self.replicas.insert(
+                    agent_id,
+                    AgentReplica::new(&project.inner, agent_id),
+                );
+                // Display the agent's cursor and selection without waiting
+                // for the server to tell us it joined.
+                project.integrate(Message::PeerJoined(peer), ctx).await?;
+                Ok(serde_json::Value::Null)
+            },
+            AgentRequestKind::Call(call) => {
+                let Some(replica) = self.replicas.get_mut(&agent_id) else {
+                    return Ok((messages, derived));
+                };
+                replica.call(call, !project.is_read_only()).map(
+                    |(value, agent_messages)| {
+                        messages.extend(agent_messages);
+                        value
+                    },
+                )
+            },
+            AgentRequestKind::Detach => {
+                // The agent's cursor and selection are removed once the
+                // server tells us it left.
+                self.replicas.remove(&agent_id);
+                Ok(serde_json::Value::Null)
+            },
+        };
+
+        // Integrate the agent's messages into the local project and into
+        // the replicas of the other agents.
+        for message in &messages {
+            self.integrate(Some(agent_id), message);
+            for more in project.integrate(message.clone(), ctx).await? {
+                self.integrate(None, &more);
+                derived.push(more);
+            }
+        }
+
+        let _ = response_tx.send(response);
+
+        Ok((messages, derived))
+    }
+
+    /// Integrates the given message into the replicas of all the agents,
+    /// except the one with the given ID (if any).
+    pub(crate) fn integrate(
+        &mut self,
+        except_agent: Option<PeerId>,
+        message: &Message,
+    ) {
+        for (&agent_id, replica) in &mut self.replicas {
+            if Some(agent_id) != except_agent {
+                replica.integrate(message);
+            }
+        }
+    }
+
+    /// Returns the receiver of the requests sent by the agents' connections.
+    pub(crate) fn request_rx(&self) -> &flume::Receiver<AgentRequest> {
+        &self.request_rx
+    }
+
+    /// Starts listening for agents on the socket, if enabled.
+    pub(crate) fn listen<Ed, Tx, Rx>(
+        &self,
+        reconnector: &Reconnector<Ed, Tx, Rx>,
+        project_access: &ProjectAccess<Ed>,
+        ctx: &mut Context<Ed>,
+    ) where
+        Ed: CollabEditor,
+        Tx: Sink<Message, Error = std::io::Error> + Unpin + 'static,
+        Rx: futures_util::stream::FusedStream<
+                Item = Result<
+                    MessageFragment,
+                    collab_server::client::ReceiveError,
+                >,
+            > + Unpin
+            + 'static,
+    {
+        let Some(socket_path) = &self.socket_path else { return };
+
+        let mut listener = match Ed::listen_for_agents(socket_path, ctx) {
+            Ok(listener) => listener,
+            Err(err) => {
+                tracing::error!(
+                    "couldn't listen for agents on {socket_path}: {err:?}"
+                );
+                return;
+            },
+        };
+
+        let reconnector = reconnector.clone();
+        let project_access = project_access.clone();
+        let request_tx = self.request_tx.clone();
+        let stop_rx = self.stop_rx.clone();
+
+        ctx.spawn_and_detach(async move |ctx| {
+            loop {
+                let io = select_biased! {
+                    _ = stop_rx.recv_async().fuse() => return,
+                    maybe_io = listener.next().fuse() => match maybe_io {
+                        Some(io) => io,
+                        None => return,
+                    },
+                };
+
+                let connection = AgentConnection {
+                    project_access: project_access.clone(),
+                    reconnector: reconnector.clone(),
+                    request_tx: request_tx.clone(),
+                    stop_rx: stop_rx.clone(),
+                };
+
+                ctx.spawn_and_detach(async move |ctx| {
+                    connection.serve(io, ctx).await
+                });
+            }
+        });
+    }
+}
+
+impl AgentReplica {
+    /// Integrates a message produced or received by the session, to keep
+    /// the replica in sync with the local project.
+    pub fn integrate(&mut self, message: &Message) {
+        let inner = &mut self.inner;
+
+        match message {
+            Message::CreatedCursor(creation) => {
+                inner.integrate_cursor_creation(creation.clone());
+            },
+            Message::CreatedDirectory(creation) => {
+                drain(inner.integrate_fs_op(creation.clone()));
+            },
+            Message::CreatedFile(creation) => {
+                drain(inner.integrate_fs_op(creation.clone()));
+            },
+            Message::CreatedSelection(creation) => {
+                inner.integrate_selection_creation(creation.clone());
+            },
+            Message::DeletedDirectory(deletion) => {
+                drain(inner.integrate_fs_op(deletion.clone()));
+            },
+            Message::DeletedFile(deletion) => {
+                drain(inner.integrate_fs_op(deletion.clone()));
+            },
+            Message::EditedBinary(edit) => {
+                inner.integrate_binary_edit(edit.clone());
+            },
+            Message::EditedText(edit) => {
+                inner.integrate_text_edit(edit.clone());
+            },
+            Message::MovedCursor(movement) => {
+                inner.integrate_cursor_move(movement.clone());
+            },
+            Message::MovedDirectory(movement) => {
+                drain(inner.integrate_fs_op(movement.clone()));
+            },
+            Message::MovedFile(movement) => {
+                drain(inner.integrate_fs_op(movement.clone()));
+            },
+            Message::MovedSelection(movement) => {
+                inner.integrate_selection_move(movement.clone());
+            },
+            Message::PeerDisconnected(peer_id)
+            | Message::PeerLeft(peer_id) => {
+                inner.integrate_peer_disconnection(*peer_id);
+            },
+            Message::RemovedCursor(removal) => {
+                inner.integrate_cursor_removal(removal.clone());
+            },
+            Message::RemovedSelection(removal) => {
+                inner.integrate_selection_removal(removal.clone());
+            },
+            Message::RenamedFsNode(rename) => {
+                drain(inner.integrate_fs_op(rename.clone()));
+            },
+            Message::PeerJoined(_)
+            | Message::ProjectRequest(_)
+            | Message::ProjectResponse(_)
+            | Message::SavedTextFile(_) => {},
+        }
+    }
+
+    /// Creates a new replica owned by the agent with the given ID by forking
+    /// the given project.
+    pub fn new(project: &collab_project::Project, agent_id: PeerId) -> Self {
+        Self {
+            cursor_id: None,
+            inner: project.fork(agent_id),
+            selection_id: None,
+        }
+    }
+
+    /// Moves the agent's cursor to the given offset in the text file at the
+    /// given path, creating it if necessary.
+    pub fn move_cursor(
+        &mut self,
+        path: &AbsPath,
+        offset: usize,
+    ) -> Result<SmallVec<[Message; 2]>, AgentError> {
+        let file_id = {
+            let file = self.text_file(path)?;
+            check_range(file.contents(), path, offset..offset)?;
+            file.local_id()
+        };
+
+        let mut messages = SmallVec::new();
+
+        if let Some(cursor_id) = self.cursor_id {
+            let Ok(Some(mut cursor)) = self.inner.cursor_mut(cursor_id) else {
+                unreachable!("the agent owns its cursor");
+            };
+            let is_in_file = cursor
+                .file_mut()
+                .is_some_and(|file| file.local_id() == file_id);
+            if is_in_file {
+                messages.push(Message::MovedCursor(cursor.r#move(offset)));
+                return Ok(messages);
+            }
+            messages.push(Message::RemovedCursor(cursor.delete()));
+        }
+
+        let (cursor_id, creation) =
+            self.text_file_mut(path)?.create_cursor(offset);
+        self.cursor_id = Some(cursor_id);
+        messages.push(Message::CreatedCursor(creation));
+
+        Ok(messages)
+    }
+
+    /// Applies the given replacements to the text file at the given path.
+    ///
+    /// The replacements are applied in order, so the range of each
+    /// replacement refers to the contents produced by the previous ones.
+    pub fn edit(
+        &mut self,
+        path: &AbsPath,
+        replacements: Vec<TextReplacement>,
+    ) -> Result<Message, AgentError> {
+        // Check all the ranges before applying any of the replacements.
+        let mut contents = self.text_file(path)?.contents().clone();
+        for replacement in &replacements {
+            check_range(&contents, path, replacement.deleted_range.clone())?;
+            contents.replace(
+                replacement.deleted_range.clone(),
+                &*replacement.inserted_text,
+            );
+        }
+
+        Ok(Message::EditedText(self.text_file_mut(path)?.edit(replacements)))
+    }
+
+    /// Returns the contents of the text file at the given path.
+    pub fn read_file(&self, path: &AbsPath) -> Result<String, AgentError> {
+        self.text_file(path).map(|file| file.contents().to_string())
+    }
+
+    /// Moves the agent's selection to the given range in the text file at
+    /// the given path, creating it if necessary.
+    pub fn select(
+        &mut self,
+        path: &AbsPath,
+        range: Range<usize>,
+    ) -> Result<SmallVec<[Message; 2]>, AgentError> {
+        let file_id = {
+            let file = self.text_file(path)?;
+            check_range(file.contents(), path, range.clone())?;
+            file.local_id()
+        };
+
+        let mut messages = SmallVec::new();
+
+        if let Some(selection_id) = self.selection_id {
+            let Ok(Some(mut selection)) =
+                self.inner.selection_mut(selection_id)
+            else {
+                unreachable!("the agent owns its selection");
+            };
+            let is_in_file = selection
+                .file_mut()
+                .is_some_and(|file| file.local_id() == file_id);
+            if is_in_file {
+                messages
+                    .push(Message::MovedSelection(selection.r#move(range)));
+                return Ok(messages);
+            }
+            messages.push(Message::RemovedSelection(selection.delete()));
+        }
+
+        let (selection_id, creation) =
+            self.text_file_mut(path)?.create_selection(range);
+        self.selection_id = Some(selection_id);
+        messages.push(Message::CreatedSelection(creation));
+
+        Ok(messages)
+    }
+
+    /// Removes the agent's selection, if it has one.
+    pub fn unselect(&mut self) -> Option<Message> {
+        let selection_id = self.selection_id.take()?;
+        let selection = self.inner.selection_mut(selection_id).ok()??;
+        Some(Message::RemovedSelection(selection.delete()))
+    }
+
+    fn call(
+        &mut self,
+        call: Call,
+        can_edit: bool,
+    ) -> Result<(serde_json::Value, SmallVec<[Message; 2]>), AgentError> {
+        let null = serde_json::Value::Null;
+
+        match call {
+            Call::Edit { path, replacements } => {
+                if !can_edit {
+                    return Err(AgentError::ReadOnly);
+                }
+                let message = self.edit(&path, replacements)?;
+                Ok((null, SmallVec::from_iter([message])))
+            },
+            Call::ListFiles => {
+                let mut files = Vec::new();
+                push_files(self.inner.root(), &mut files);
+                Ok((serde_json::Value::Array(files), SmallVec::new()))
+            },
+            Call::MoveCursor { path, offset } => {
+                Ok((null, self.move_cursor(&path, offset)?))
+            },
+            Call::ReadFile { path } => {
+                Ok((self.read_file(&path)?.into(), SmallVec::new()))
+            },
+            Call::Select { path, range } => {
+                Ok((null, self.select(&path, range)?))
+            },
+            Call::Unselect => {
+                Ok((null, self.unselect().into_iter().collect()))
+            },
+        }
+    }
+
+    fn text_file(
+        &self,
+        path: &AbsPath,
+    ) -> Result<collab_project::text::TextFile<'_>, AgentError> {
+        match self.inner.node_at_path(path) {
+            Some(Node::File(File::Text(file))) => Ok(file),
+            Some(_) => Err(AgentError::NotTextFile(path.to_owned())),
+            None => Err(AgentError::NoFileAtPath(path.to_owned())),
+        }
+    }
+
+    fn text_file_mut(
+        &mut self,
+        path: &AbsPath,
+    ) -> Result<collab_project::text::TextFileMut<'_>, AgentError> {
+        match self.inner.node_at_path_mut(path) {
+            Some(NodeMut::File(FileMut::Text(file))) => Ok(file),
+            Some(_) => Err(AgentError::NotTextFile(path.to_owned())),
+            None => Err(AgentError::NoFileAtPath(path.to_owned())),
+        }
+    }
+}
+
+/// A connection to an agent.
+struct AgentConnection<Ed: CollabEditor, Tx, Rx> {
+    project_access: ProjectAccess<Ed>,
+    reconnector: Reconnector<Ed, Tx, Rx>,
+    request_tx: flume::Sender<AgentRequest>,
+    stop_rx: flume::Receiver<()>,
+}
+
+impl<Ed, Tx, Rx> AgentConnection<Ed, Tx, Rx>
+where
+    Ed: CollabEditor,
+    Tx: Sink<Message, Error = std::io::Error> + Unpin,
+    Rx: futures_util::stream::FusedStream<
+            Item = Result<
+                MessageFragment,
+                collab_server::client::ReceiveError,
+            >,
+        > + Unpin,
+{
+    /// Joins the session as a new peer, and then serves the agent's requests
+    /// until it disconnects or the session ends.
+    async fn serve(
+        self,
+        io: impl AsyncRead + AsyncWrite + Unpin,
+        ctx: &mut Context<Ed>,
+    ) {
+        let Reconnected { local_peer: agent, tx: mut message_tx, rx, .. } =
+            match self.reconnector.join_as_new_peer(ctx).await {
+                Ok(reconnected) => reconnected,
+                Err(err) => {
+                    tracing::warn!("couldn't connect agent to session: {err}");
+                    return;
+                },
+            };
+
+        let agent_id = agent.id;
+
+        if self
+            .request(agent_id, AgentRequestKind::Attach(agent))
+            .await
+            .is_none()
+        {
+            return;
+        }
+
+        let (reader, mut writer) = io.split();
+        let mut lines =
+            futures_util::io::BufReader::new(reader).lines().fuse();
+        let mut message_rx = rx;
+
+        loop {
+            select_biased! {
+                _ = self.stop_rx.recv_async().fuse() => return,
+                maybe_line = lines.next() => {
+                    let Some(Ok(line)) = maybe_line else { break };
+                    let Some(response) = self.handle_line(agent_id, &line).await
+                    else {
+                        continue;
+                    };
+                    let mut response = response.to_string();
+                    response.push('\n');
+                    if writer.write_all(response.as_bytes()).await.is_err()
+                        || writer.flush().await.is_err()
+                    {
+                        break;
+                    }
+                },
+                maybe_fragment = message_rx.next() => {
+                    let Some(Ok(fragment)) = maybe_fragment else { break };
+                    // The agent's connection doesn't integrate any message,
+                    // but it can be asked for the project by peers joining
+                    // the session (e.g. if the agent is elected as the host).
+                    let Some(Message::ProjectRequest(request)) =
+                        fragment.message
+                    else {
+                        continue;
+                    };
+                    let Some(response) = self
+                        .project_access
+                        .with(async move |proj, _| proj.project_response(request))
+                        .await
+                    else {
+                        break;
+                    };
+                    let message = Message::ProjectResponse(response);
+                    if message_tx.send(message).await.is_err() {
+                        break;
+                    }
+                },
+            }
+        }
+
+        let _ = self.request(agent_id, AgentRequestKind::Detach).await;
+    }
+
+    /// Handles a line sent by the agent, returning the JSON-RPC response to
+    /// send back (if any).
+    async fn handle_line(
+        &self,
+        agent_id: PeerId,
+        line: &str,
+    ) -> Option<serde_json::Value> {
+        let request = match serde_json::from_str::<RpcRequest>(line) {
+            Ok(request) => request,
+            Err(err) => {
+                let error = RpcError::new(RpcError::PARSE_ERROR, err);
+                return Some(error.into_response(serde_json::Value::Null));
+            },
+        };
+
+        let result = match Call::parse(&request.method, request.params) {
+            Ok(call) => {
+                match self
+                    .request(agent_id, AgentRequestKind::Call(call))
+                    .await
+                {
+                    Some(Ok(value)) => Ok(value),
+                    Some(Err(err)) => {
+                        Err(RpcError::new(RpcError::AGENT_ERROR, err))
+                    },
+                    None => return None,
+                }
+            },
+            Err(err) => Err(err),
+        };
+
+        // Requests without an ID are notifications, which don't get a
+        // response.
+        let id = request.id?;
+
+        Some(match result {
+            Ok(value) => {
+                json!({ "jsonrpc": "2.0", "id": id, "result": value })
+            },
+            Err(err) => err.into_response(id),
+        })
+    }
+
+    /// Sends a request to the session's event loop and waits for its
+    /// response, returning `None` if the session has ended.
+    async fn request(
+        &self,
+        agent_id: PeerId,
+        kind: AgentRequestKind,
+    ) -> Option<Result<serde_json::Value, AgentError>> {
+        let (response_tx, response_rx) = flume::bounded(1);
+        let request = AgentRequest { agent_id, kind, response_tx };
+        self.request_tx.send_async(request).await.ok()?;
+        response_rx.recv_async().await.ok()
+    }
+}
+
+impl Call {
+    fn parse(
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<Self, RpcError> {
+        Ok(match method {
+            "edit" => {
+                let EditParams { path, replacements } = parse_params(params)?;
+                Self::Edit {
+                    path: parse_path(&path)?,
+                    replacements: replacements
+                        .into_iter()
+                        .map(|ReplacementParams { start, end, text }| {
+                            TextReplacement {
+                                deleted_range: start..end,
+                                inserted_text: text.into(),
+                            }
+                        })
+                        .collect(),
+                }
+            },
+            "list_files" => Self::ListFiles,
+            "move_cursor" => {
+                let MoveCursorParams { path, offset } = parse_params(params)?;
+                Self::MoveCursor { path: parse_path(&path)?, offset }
+            },
+            "read_file" => {
+                let PathParams { path } = parse_params(params)?;
+                Self::ReadFile { path: parse_path(&path)? }
+            },
+            "select" => {
+                let SelectParams { path, start, end } = parse_params(params)?;
+                Self::Select { path: parse_path(&path)?, range: start..end }
+            },
+            "unselect" => Self::Unselect,
+            _ => {
+                return Err(RpcError::new(
+                    RpcError::METHOD_NOT_FOUND,
+                    format_args!("unknown method {method:?}"),
+                ));
+            },
+        })
+    }
+}
+
+impl RpcError {
+    const AGENT_ERROR: i64 = -32000;
+    const INVALID_PARAMS: i64 = -32602;
+    const METHOD_NOT_FOUND: i64 = -32601;
+    const PARSE_ERROR: i64 = -32700;
+
+    fn into_response(self, id: serde_json::Value) -> serde_json::Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": self.code, "message": self.message },
+        })
+    }
+
+    fn new(code: i64, message: impl core::fmt::Display) -> Self {
+        Self { code, message: message.to_string() }
+    }
+}
+
+/// Checks that the given range is within the given text, and that both its
+/// ends are on char boundaries.
+fn check_range(
+    text: &crop::Rope,
+    path: &AbsPath,
+    range: Range<usize>,
+) -> Result<(), AgentError> {
+    let is_valid = range.start <= range.end
+        && range.end <= text.byte_len()
+        && text.is_char_boundary(range.start)
+        && text.is_char_boundary(range.end);
+
+    is_valid
+        .then_some(())
+        .ok_or_else(|| AgentError::InvalidRange(path.to_owned(), range))
+}
+
+/// Exhausts the given sync actions without applying them, since replicas
+/// don't have a file system to keep in sync.
+fn drain(mut sync_actions: collab_project::fs::SyncActions<'_>) {
+    while sync_actions.next().is_some() {}
+}
+
+fn parse_params<T: serde::de::DeserializeOwned>(
+    params: serde_json::Value,
+) -> Result<T, RpcError> {
+    serde_json::from_value(params)
+        .map_err(|err| RpcError::new(RpcError::INVALID_PARAMS, err))
+}
+
+/// Parses a path relative to the root of the project, with or without a
+/// leading slash.
+fn parse_path(path: &str) -> Result<AbsPathBuf, RpcError> {
+    let path = if path.starts_with('/') {
+        path.parse::<AbsPathBuf>()
+    } else {
+        format!("/{path}").parse::<AbsPathBuf>()
+    };
+
+    path.map_err(|err| RpcError::new(RpcError::INVALID_PARAMS, err))
+}
+
+/// Pushes the paths and kinds of all the files under the given directory.
+fn push_files(dir: Directory<'_>, files: &mut Vec<serde_json::Value>) {
+    for node in dir.children() {
+        let file = match node {
+            Node::Directory(dir) => {
+                push_files(dir, files);
+                continue;
+            },
+            Node::File(file) => file,
+        };
+
+        let kind = match &file {
+            File::Binary(_) => "binary",
+            File::Symlink(_) => "symlink",
+            File::Text(_) => "text",
+        };
+
+        files.push(json!({ "path": file.path().to_string(), "kind": kind }));
+    }
+}
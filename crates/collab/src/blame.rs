@@ -0,0 +1,208 @@
+//! Contains the [`Blame`] action, which shows who wrote each line of the
+//! focused buffer.
+
+use abs_path::AbsPathBuf;
+use collab_project::fs::File;
+use collab_project::text::TextFile;
+use collab_types::{Peer, PeerHandle, PeerId};
+use editor::command::ToCompletionFn;
+use editor::module::AsyncAction;
+use editor::{Buffer, Context};
+use smallvec::SmallVec;
+
+use crate::collab::Collab;
+use crate::editors::CollabEditor;
+use crate::project::Project;
+use crate::session::Sessions;
+
+/// An `Action` that toggles the display of the peer that wrote each line of
+/// the focused buffer.
+///
+/// The annotations are a snapshot of the buffer's authorship at the time the
+/// action is called, so they're removed as soon as the buffer is edited.
+#[derive(cauchy::Clone)]
+pub struct Blame<Ed: CollabEditor> {
+    sessions: Sessions<Ed>,
+}
+
+/// The peer that wrote most of a line in a buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineAuthor {
+    /// The index of the line in the buffer.
+    pub line_idx: usize,
+
+    /// The ID of the peer that wrote the line.
+    pub peer_id: PeerId,
+
+    /// The handle of the peer that wrote the line, or `None` if the peer
+    /// wasn't in the session by the time we joined it, or has left it since.
+    pub peer_handle: Option<PeerHandle>,
+}
+
+impl<Ed: CollabEditor> Blame<Ed> {
+    pub(crate) async fn call_inner(
+        &self,
+        ctx: &mut Context<Ed>,
+    ) -> Result<(), BlameError> {
+        let (buffer_id, buffer_path) = ctx.with_borrowed(|ctx| {
+            ctx.current_buffer()
+                .map(|buf| (buf.id(), buf.path().into_owned()))
+                .ok_or(BlameError::NoBufferFocused)
+        })?;
+
+        let Some(sesh) = self
+            .sessions
+            .find(|sesh| buffer_path.starts_with(&sesh.project_root_path))
+        else {
+            return Err(BlameError::BufferNotInSession(buffer_path));
+        };
+
+        let was_toggled = sesh
+            .project_access
+            .with_mut(async move |proj, ctx| {
+                Self::toggle(proj, buffer_id, ctx)
+            })
+            .await
+            .unwrap_or(false);
+
+        if was_toggled {
+            Ok(())
+        } else {
+            Err(BlameError::BufferNotInSession(buffer_path))
+        }
+    }
+
+    /// Shows the authors of the lines in the buffer with the given ID if
+    /// they're not already displayed, or removes them if they are.
+    ///
+    /// Returns `false` if the buffer is not backed by a text file in the
+    /// project.
+    pub(crate) fn toggle(
+        proj: &mut Project<Ed>,
+        buffer_id: Ed::BufferId,
+        ctx: &mut Context<Ed>,
+    ) -> bool {
+        if let Some(blame) = proj.blames.remove(&buffer_id) {
+            Ed::remove_buffer_blame(blame, ctx);
+            return true;
+        }
+
+        let Some(&file_id) = proj.id_maps.buffer2file.get(&buffer_id) else {
+            return false;
+        };
+
+        let Some(File::Text(file)) = proj.inner.file(file_id) else {
+            return false;
+        };
+
+        let line_authors = line_authors(&file)
+            .into_iter()
+            .map(|(line_idx, peer_id)| LineAuthor {
+                line_idx,
+                peer_id,
+                peer_handle: peer_handle(proj, peer_id),
+            })
+            .collect::<Vec<_>>();
+
+        let blame =
+            Ed::create_buffer_blame(buffer_id.clone(), &line_authors, ctx);
+
+        proj.blames.insert(buffer_id, blame);
+
+        true
+    }
+}
+
+impl<Ed: CollabEditor> AsyncAction<Ed> for Blame<Ed> {
+    const NAME: &str = "blame";
+
+    type Args = ();
+
+    async fn call(&mut self, _: Self::Args, ctx: &mut Context<Ed>) {
+        if let Err(err) = self.call_inner(ctx).await {
+            Ed::on_blame_error(err, ctx);
+        }
+    }
+}
+
+/// The type of error that can occur when [`Blame`] fails.
+#[derive(Debug, derive_more::Display, cauchy::Error, PartialEq)]
+pub enum BlameError {
+    /// The focused buffer is not a text file in any of the projects being
+    /// collaborated on.
+    #[display("{_0} is not a text file in any of the active sessions")]
+    BufferNotInSession(AbsPathBuf),
+
+    /// No buffer is focused.
+    #[display("No buffer is focused")]
+    NoBufferFocused,
+}
+
+impl<Ed: CollabEditor> From<&Collab<Ed>> for Blame<Ed> {
+    fn from(collab: &Collab<Ed>) -> Self {
+        Self { sessions: collab.sessions.clone() }
+    }
+}
+
+impl<Ed: CollabEditor> ToCompletionFn<Ed> for Blame<Ed> {
+    fn to_completion_fn(&self) {}
+}
+
+/// Returns the `(line_idx, peer_id)` pairs of the peer that wrote the most
+/// bytes in each non-empty line of the given file.
+fn line_authors(file: &TextFile<'_>) -> Vec<(usize, PeerId)> {
+    let contents = file.contents();
+
+    let num_lines = contents.line_len();
+
+    let mut bytes_by_author =
+        vec![SmallVec::<[(PeerId, usize); 2]>::new(); num_lines];
+
+    for (byte_range, peer_id) in file.authorship() {
+        let mut offset = byte_range.start;
+
+        while offset < byte_range.end {
+            let line_idx = contents.line_of_byte(offset);
+
+            let line_end = if line_idx + 1 < num_lines {
+                contents.byte_of_line(line_idx + 1)
+            } else {
+                contents.byte_len()
+            };
+
+            let end = line_end.min(byte_range.end);
+
+            let authors = &mut bytes_by_author[line_idx];
+
+            match authors.iter_mut().find(|(id, _)| *id == peer_id) {
+                Some((_, num_bytes)) => *num_bytes += end - offset,
+                None => authors.push((peer_id, end - offset)),
+            }
+
+            offset = end;
+        }
+    }
+
+    bytes_by_author
+        .into_iter()
+        .enumerate()
+        .filter_map(|(line_idx, authors)| {
+            // In case of a tie, the author that comes first in the line wins.
+            let (peer_id, _) = authors.into_iter().reduce(|max, author| {
+                if author.1 > max.1 { author } else { max }
+            })?;
+            Some((line_idx, peer_id))
+        })
+        .collect()
+}
+
+fn peer_handle<Ed: CollabEditor>(
+    proj: &Project<Ed>,
+    peer_id: PeerId,
+) -> Option<PeerHandle> {
+    if peer_id == proj.local_peer.id {
+        Some(proj.local_peer.handle.clone())
+    } else {
+        proj.remote_peers.get(peer_id).map(|peer| Peer::from(peer).handle)
+    }
+}
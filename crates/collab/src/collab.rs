@@ -1,22 +1,45 @@
+use abs_path::AbsPathBuf;
 use auth::AuthState;
 use collab_types::PeerHandle;
 use editor::context::Borrowed;
 use editor::module::{ApiCtx, Module};
 use editor::{Context, Shared};
 
+use crate::blame::{Blame, BlameError};
 use crate::config::Config;
 use crate::copy_id::{CopyId, CopyIdError};
 use crate::editors::{CollabEditor, SessionId};
+use crate::follow::{Follow, FollowError};
+use crate::host_local::{HostLocal, HostLocalError, ListenAddress};
 use crate::join::{Join, JoinError};
 use crate::jump::{Jump, JumpError};
 use crate::leave::{Leave, LeaveError};
+use crate::list_peers::{ListPeers, ListPeersError};
 use crate::pause::{Pause, PauseError};
+use crate::peer_status::PeerStatus;
+use crate::peers::PeerActivity;
+use crate::permissions::PermissionsError;
 use crate::progress::ProgressReporter;
+use crate::query::{
+    FileSummary,
+    PeerSummary,
+    QueryFiles,
+    QueryPeers,
+    QuerySessions,
+    SessionSummary,
+};
+use crate::read_only::ReadOnly;
+use crate::read_write::ReadWrite;
+use crate::redo::Redo;
 use crate::resume::{Resume, ResumeError};
 use crate::session::{SessionInfos, Sessions};
 use crate::start::{Start, StartError};
+use crate::statusline::Statusline;
+use crate::undo::{Undo, UndoError};
+use crate::unfollow::{Unfollow, UnfollowError};
 
 /// TODO: docs.
+#[derive(cauchy::Clone)]
 pub struct Collab<Ed: CollabEditor> {
     pub(crate) auth_state: AuthState,
     pub(crate) config: Shared<Config>,
@@ -24,6 +47,14 @@ pub struct Collab<Ed: CollabEditor> {
 }
 
 impl<Ed: CollabEditor> Collab<Ed> {
+    /// Calls the [`Blame`] action.
+    pub async fn blame(
+        &self,
+        ctx: &mut Context<Ed>,
+    ) -> Result<(), BlameError> {
+        Blame::from(self).call_inner(ctx).await
+    }
+
     /// Calls the [`CopyId`] action.
     pub async fn copy_id(
         &self,
@@ -32,6 +63,25 @@ impl<Ed: CollabEditor> Collab<Ed> {
         CopyId::from(self).call_inner(ctx).await
     }
 
+    /// Calls the [`Follow`] action.
+    pub async fn follow(
+        &self,
+        peer_handle: PeerHandle,
+    ) -> Result<(), FollowError<Ed>> {
+        Follow::from(self).call_inner(peer_handle).await
+    }
+
+    /// Calls the [`HostLocal`] action with the given listen address.
+    pub async fn host_local(
+        &self,
+        listen_addr: ListenAddress,
+        ctx: &mut Context<Ed>,
+    ) -> Result<SessionInfos<Ed>, HostLocalError<Ed>> {
+        let mut reporter =
+            <Ed::ProgressReporter as ProgressReporter<_, Start<_>>>::new(ctx);
+        HostLocal::from(self).call_inner(listen_addr, &mut reporter, ctx).await
+    }
+
     /// Calls the [`Join`] action with the given session ID.
     pub async fn join(
         &self,
@@ -59,6 +109,14 @@ impl<Ed: CollabEditor> Collab<Ed> {
         Leave::from(self).call_inner(ctx).await
     }
 
+    /// Calls the [`ListPeers`] action.
+    pub async fn list_peers(
+        &self,
+        ctx: &mut Context<Ed>,
+    ) -> Result<(), ListPeersError<Ed>> {
+        ListPeers::from(self).call_inner(ctx).await
+    }
+
     /// Calls the [`Pause`] action.
     pub async fn pause(
         &self,
@@ -67,6 +125,56 @@ impl<Ed: CollabEditor> Collab<Ed> {
         Pause::from(self).call_inner(ctx).await
     }
 
+    /// Calls the [`PeerStatus`] function.
+    pub fn peer_status(
+        &self,
+        peer_handle: PeerHandle,
+    ) -> Option<PeerActivity> {
+        PeerStatus::from(self).call_inner(peer_handle)
+    }
+
+    /// Calls the [`QueryFiles`] function.
+    pub fn query_files(
+        &self,
+        session_id: SessionId<Ed>,
+    ) -> Option<Vec<FileSummary>> {
+        QueryFiles::from(self).call_inner(session_id)
+    }
+
+    /// Calls the [`QueryPeers`] function.
+    pub fn query_peers(
+        &self,
+        session_id: SessionId<Ed>,
+    ) -> Option<Vec<PeerSummary>> {
+        QueryPeers::from(self).call_inner(session_id)
+    }
+
+    /// Calls the [`QuerySessions`] function.
+    pub fn query_sessions(&self) -> Vec<SessionSummary> {
+        QuerySessions::from(self).call_inner()
+    }
+
+    /// Calls the [`ReadOnly`] action.
+    pub async fn read_only(
+        &self,
+        ctx: &mut Context<Ed>,
+    ) -> Result<(), PermissionsError<Ed>> {
+        ReadOnly::from(self).call_inner(ctx).await
+    }
+
+    /// Calls the [`ReadWrite`] action.
+    pub async fn read_write(
+        &self,
+        ctx: &mut Context<Ed>,
+    ) -> Result<(), PermissionsError<Ed>> {
+        ReadWrite::from(self).call_inner(ctx).await
+    }
+
+    /// Calls the [`Redo`] action.
+    pub async fn redo(&self, ctx: &mut Context<Ed>) -> Result<(), UndoError> {
+        Redo::from(self).call_inner(ctx).await
+    }
+
     /// Calls the [`Resume`] action.
     pub async fn resume(
         &self,
@@ -84,6 +192,40 @@ impl<Ed: CollabEditor> Collab<Ed> {
             <Ed::ProgressReporter as ProgressReporter<_, Start<_>>>::new(ctx);
         Start::from(self).call_inner(&mut reporter, ctx).await
     }
+
+    /// Same as [`start`](Self::start), but starts the session on the project
+    /// rooted at the given path instead of the one containing the focused
+    /// buffer.
+    pub async fn start_at(
+        &self,
+        project_root: AbsPathBuf,
+        ctx: &mut Context<Ed>,
+    ) -> Result<SessionInfos<Ed>, StartError<Ed>> {
+        let mut reporter =
+            <Ed::ProgressReporter as ProgressReporter<_, Start<_>>>::new(ctx);
+        let server_addr = self.config.with(|c| c.server_address.clone());
+        Start::from(self)
+            .call_at_root(project_root, server_addr, None, &mut reporter, ctx)
+            .await
+    }
+
+    /// Calls the [`Statusline`] function.
+    pub fn statusline(&self) -> String {
+        Statusline::from(self).call_inner()
+    }
+
+    /// Calls the [`Undo`] action.
+    pub async fn undo(&self, ctx: &mut Context<Ed>) -> Result<(), UndoError> {
+        Undo::from(self).call_inner(ctx).await
+    }
+
+    /// Calls the [`Unfollow`] action.
+    pub fn unfollow(
+        &self,
+        ctx: &mut Context<Ed>,
+    ) -> Result<(), UnfollowError> {
+        Unfollow::from(self).call_inner(ctx)
+    }
 }
 
 impl<Ed: CollabEditor> Module<Ed> for Collab<Ed> {
@@ -92,20 +234,46 @@ impl<Ed: CollabEditor> Module<Ed> for Collab<Ed> {
     type Config = Config;
 
     fn api(&self, ctx: &mut ApiCtx<Ed>) {
-        ctx.with_command(CopyId::from(self))
+        ctx.with_command(Blame::from(self))
+            .with_command(CopyId::from(self))
+            .with_command(Follow::from(self))
             .with_command(Join::from(self))
             .with_command(Jump::from(self))
             .with_command(Leave::from(self))
+            .with_command(ListPeers::from(self))
             .with_command(Pause::from(self))
+            .with_command(ReadOnly::from(self))
+            .with_command(ReadWrite::from(self))
+            .with_command(Redo::from(self))
             .with_command(Resume::from(self))
             .with_command(Start::from(self))
+            .with_command(Undo::from(self))
+            .with_command(Unfollow::from(self))
+            .with_function(Blame::from(self))
             .with_function(CopyId::from(self))
+            .with_function(Follow::from(self))
             .with_function(Jump::from(self))
             .with_function(Join::from(self))
             .with_function(Leave::from(self))
             .with_function(Resume::from(self))
             .with_function(Pause::from(self))
-            .with_function(Start::from(self));
+            .with_function(PeerStatus::from(self))
+            .with_function(QueryFiles::from(self))
+            .with_function(QueryPeers::from(self))
+            .with_function(QuerySessions::from(self))
+            .with_function(ReadOnly::from(self))
+            .with_function(ReadWrite::from(self))
+            .with_function(Redo::from(self))
+            .with_function(Start::from(self))
+            .with_function(Statusline::from(self))
+            .with_function(Undo::from(self))
+            .with_function(Unfollow::from(self));
+
+        // The local server can't run without the `host-local` feature, so
+        // there's no point in exposing the action.
+        #[cfg(feature = "host-local")]
+        ctx.with_command(HostLocal::from(self))
+            .with_function(HostLocal::from(self));
     }
 
     fn on_init(&self, ctx: &mut Context<Ed, Borrowed<'_>>) {
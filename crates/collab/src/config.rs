@@ -13,11 +13,37 @@ use serde::de::{Deserialize, Deserializer};
 const DEFAULT_DOMAIN: &str = "collab.nomad.foo";
 const DEFAULT_PORT: u16 = 3000;
 
+/// The prefix of the server addresses that should be connected to without
+/// TLS.
+const PLAINTEXT_PREFIX: &str = "tcp://";
+
 /// TODO: docs.
 #[derive(Debug, Default, serde::Deserialize)]
 #[serde(default)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
+    /// The path of the Unix domain socket that local agents can connect to
+    /// to take part in the editing sessions as separate peers, or `None` to
+    /// disable them.
+    ///
+    /// See the [`agents`](crate::agents) module for more infos.
+    pub(crate) agent_socket: Option<AbsPathBuf>,
+
+    /// Patterns in `.gitignore` syntax matching the files and directories
+    /// that should never be shared, in addition to the ones in the project's
+    /// `.nomadignore` file.
+    ///
+    /// See [`NomadIgnore`](crate::nomadignore::NomadIgnore) for more infos.
+    pub(crate) exclude: Vec<String>,
+
+    /// How to find the root of the project containing the focused buffer
+    /// when starting a session.
+    pub(crate) project_root: ProjectRoot,
+
+    /// Whether to record the messages exchanged during every session, so
+    /// that they can be [replayed](crate::recording::replay) later.
+    pub(crate) record_sessions: bool,
+
     /// The address of the server to connect to when starting or joining an
     /// editing session.
     pub(crate) server_address: ServerAddress<'static>,
@@ -26,11 +52,51 @@ pub struct Config {
     pub(crate) store_remote_projects_under: Option<AbsPathBuf>,
 }
 
+/// How to find the root of the project containing a buffer, if the editor
+/// doesn't already know it (e.g. from a language server).
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectRoot {
+    /// The names of the files or directories whose presence marks a
+    /// directory as the root of a project, e.g. `.git` or `Cargo.toml`.
+    pub markers: Vec<String>,
+
+    /// Which directory is picked when several of the buffer's ancestors
+    /// contain a marker.
+    pub precedence: RootPrecedence,
+
+    /// Whether to stop searching for markers at the home directory, instead
+    /// of continuing up to the root of the filesystem.
+    pub stop_at_home: bool,
+}
+
+/// Which directory is picked as the project root when several of a buffer's
+/// ancestors contain a [root marker](ProjectRoot::markers).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RootPrecedence {
+    /// The one closest to the buffer, e.g. a crate inside a monorepo.
+    #[default]
+    Nearest,
+
+    /// The one furthest from the buffer, e.g. the monorepo itself.
+    Outermost,
+}
+
 /// TODO: docs.
 #[derive(Clone)]
 pub struct ServerAddress<'dns_name> {
     pub(crate) host: Host<'dns_name>,
     pub(crate) port: u16,
+
+    /// Whether to connect to the server without TLS, even if it's not on
+    /// the local machine.
+    ///
+    /// This is opted into by prefixing the address with `tcp://`, e.g. to
+    /// join a session hosted with
+    /// [`HostLocal`](crate::host_local::HostLocal) from another machine.
+    pub(crate) plaintext: bool,
 }
 
 /// The type of error that can occur when parsing a `ServerAddress` from a
@@ -58,11 +124,19 @@ pub(crate) enum Host<'dns_name> {
 
 impl<'a> ServerAddress<'a> {
     pub(crate) fn borrow(&'a self) -> Self {
-        ServerAddress { host: self.host.borrow(), port: self.port }
+        ServerAddress {
+            host: self.host.borrow(),
+            port: self.port,
+            plaintext: self.plaintext,
+        }
     }
 
     pub(crate) fn to_owned(&self) -> ServerAddress<'static> {
-        ServerAddress { host: self.host.to_owned(), port: self.port }
+        ServerAddress {
+            host: self.host.to_owned(),
+            port: self.port,
+            plaintext: self.plaintext,
+        }
     }
 }
 
@@ -82,12 +156,26 @@ impl<'a> Host<'a> {
     }
 }
 
+impl Default for ProjectRoot {
+    fn default() -> Self {
+        Self {
+            markers: vec![".git".to_owned()],
+            precedence: RootPrecedence::default(),
+            stop_at_home: true,
+        }
+    }
+}
+
 impl Default for ServerAddress<'static> {
     fn default() -> Self {
         let Ok(dns_name) = DnsName::try_from(DEFAULT_DOMAIN) else {
             unreachable!("{DEFAULT_DOMAIN:?} is a valid DNS name")
         };
-        Self { host: Host::Domain(dns_name), port: DEFAULT_PORT }
+        Self {
+            host: Host::Domain(dns_name),
+            port: DEFAULT_PORT,
+            plaintext: false,
+        }
     }
 }
 
@@ -99,6 +187,9 @@ impl fmt::Debug for ServerAddress<'_> {
 
 impl fmt::Display for ServerAddress<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.plaintext {
+            f.write_str(PLAINTEXT_PREFIX)?;
+        }
         write!(f, "{}:{}", self.host, self.port)
     }
 }
@@ -107,6 +198,11 @@ impl str::FromStr for ServerAddress<'static> {
     type Err = ServerAddressParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (s, plaintext) = match s.strip_prefix(PLAINTEXT_PREFIX) {
+            Some(rest) => (rest, true),
+            None => (s, false),
+        };
+
         let (host, port) = s
             .rsplit_once(':')
             .ok_or(ServerAddressParseError::InvalidFormat)?;
@@ -124,7 +220,7 @@ impl str::FromStr for ServerAddress<'static> {
             return Err(ServerAddressParseError::InvalidHost);
         };
 
-        Ok(Self { host, port })
+        Ok(Self { host, port, plaintext })
     }
 }
 
@@ -172,3 +268,22 @@ impl<'dns_name> From<Host<'dns_name>> for ServerName<'dns_name> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plaintext_prefix_opts_out_of_tls() {
+        let addr = "tcp://192.168.1.10:3000".parse::<ServerAddress>().unwrap();
+        assert!(addr.plaintext);
+        assert_eq!(addr.port, 3000);
+        assert_eq!(addr.to_string(), "tcp://192.168.1.10:3000");
+    }
+
+    #[test]
+    fn local_network_addresses_use_tls_by_default() {
+        let addr = "192.168.1.10:3000".parse::<ServerAddress>().unwrap();
+        assert!(!addr.plaintext);
+    }
+}
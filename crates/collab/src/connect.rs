@@ -0,0 +1,90 @@
+//! Contains the logic used to connect to a collab server over TCP, shared by
+//! the editors that talk to the server over the network.
+
+use std::io;
+use std::sync::{Arc, OnceLock};
+
+use async_net::TcpStream;
+use editor::{Context, Editor};
+use futures_rustls::client::TlsStream;
+use futures_rustls::{TlsConnector, rustls};
+use futures_util::future::Either;
+
+use crate::config;
+use crate::tcp_stream_ext::TcpStreamExt;
+
+/// The type of I/O stream returned by [`connect_to_server`].
+pub type TcpIo = Either<TlsStream<TcpStream>, TcpStream>;
+
+/// The type of error returned by [`connect_to_server`].
+#[derive(Debug, derive_more::Display, cauchy::Error)]
+pub enum ConnectToServerError {
+    /// It wasn't possible to establish a TCP connection with the server.
+    #[display("couldn't establish TCP connection with server: {_0}")]
+    ConnectTcp(io::Error),
+
+    /// It wasn't possible to establish a TLS connection with the server.
+    #[display("couldn't establish TLS connection with server: {_0}")]
+    ConnectTls(io::Error),
+
+    /// It wasn't possible to obtain the TLS certificates from the OS.
+    #[display("couldn't obtain TLS certificates from OS: {_0}")]
+    Certificates(futures_rustls::rustls::Error),
+}
+
+/// Connects to the server at the given address, using TLS unless the server
+/// is on the local machine or the address explicitly
+/// [opted out](config::ServerAddress::plaintext) of it.
+pub(crate) async fn connect_to_server(
+    server_addr: config::ServerAddress<'static>,
+    ctx: &mut Context<impl Editor>,
+) -> Result<TcpIo, ConnectToServerError> {
+    let tcp_stream =
+        <TcpStream as TcpStreamExt>::connect(server_addr.clone(), ctx)
+            .await
+            .map_err(ConnectToServerError::ConnectTcp)?;
+
+    // If we're connecting to a loopback address we're probably testing
+    // against a local server without TLS, or connecting to one started with
+    // `:Mad collab host-local`, so use plain TCP.
+    let is_loopback = match &server_addr.host {
+        config::Host::Ip(ip) => ip.is_loopback(),
+        config::Host::Domain(_) => false,
+    };
+
+    if is_loopback || server_addr.plaintext {
+        return Ok(Either::Right(tcp_stream));
+    }
+
+    let tls_connector = tls_connector(ctx)
+        .await
+        .map_err(ConnectToServerError::Certificates)?;
+
+    tls_connector
+        .connect(server_addr.host.into(), tcp_stream)
+        .await
+        .map(Either::Left)
+        .map_err(ConnectToServerError::ConnectTls)
+}
+
+async fn tls_connector(
+    ctx: &mut Context<impl Editor>,
+) -> Result<&TlsConnector, rustls::Error> {
+    static TLS_CONNECTOR: OnceLock<TlsConnector> = OnceLock::new();
+
+    if let Some(connector) = TLS_CONNECTOR.get() {
+        return Ok(connector);
+    }
+
+    // Getting the certificates from the OS blocks, so we do it in a
+    // background thread.
+    let client_config = ctx
+        .spawn_background(async {
+            use rustls_platform_verifier::ConfigVerifierExt;
+            rustls::ClientConfig::with_platform_verifier()
+        })
+        .await?;
+
+    Ok(TLS_CONNECTOR
+        .get_or_init(|| TlsConnector::from(Arc::new(client_config))))
+}
@@ -0,0 +1,620 @@
+//! Contains the [`CollabEditor`] implementation for the [`Headless`] editor
+//! used by the `nomad` CLI.
+
+use core::convert::Infallible;
+use core::net::SocketAddr;
+use core::ops::Range;
+use core::time::Duration;
+use std::path::Path;
+use std::process::ExitCode;
+use std::{env, io};
+
+use abs_path::{AbsPath, AbsPathBuf, node};
+use collab_types::Peer;
+use editor::context::Borrowed;
+use editor::{AgentId, ByteOffset, Context, Editor};
+use executor::Executor;
+use fs::{Directory, Fs};
+use futures_util::StreamExt;
+use headless::Headless;
+use headless::notify::NotifyContextExt;
+
+use crate::editors::{ActionForSelectedSession, CollabEditor};
+use crate::peers::PeerActivity;
+use crate::progress::{JoinState, Pipeline, ProgressReporter, StartState};
+use crate::project::Project;
+use crate::session::{NoActiveSessionError, SessionError, SessionInfos};
+use crate::{
+    SessionId,
+    agent_socket,
+    blame,
+    config,
+    connect,
+    copy_id,
+    follow,
+    host_local,
+    join,
+    jump,
+    leave,
+    list_peers,
+    local_server,
+    pause,
+    permissions,
+    resume,
+    start,
+    statusline,
+    undo,
+    unfollow,
+};
+
+/// The type of error returned when the [`Headless`] editor can't figure out
+/// the directory where Nomad should store its data.
+#[derive(Debug, derive_more::Display)]
+pub enum HeadlessDataDirError {
+    /// It wasn't possible to get the user's home directory.
+    #[display("couldn't get the home directory: {_0:?}")]
+    Home(<real_fs::RealFs as Fs>::HomeError),
+
+    /// The user doesn't have a home directory.
+    #[display("couldn't find the home directory")]
+    NoHome,
+}
+
+/// The [`ProgressReporter`] used by the [`Headless`] editor, which prints
+/// every progress update on its own line.
+///
+/// Errors are not reported, as they're returned to the caller of the
+/// action.
+pub struct HeadlessProgressReporter;
+
+impl CollabEditor for Headless {
+    type AgentListener = agent_socket::AgentListener;
+    type BufferBlame = ();
+    type Io = connect::TcpIo;
+    type PeerSelection = ();
+    type PeerTooltip = ();
+    type ProgressReporter = HeadlessProgressReporter;
+    type ProjectFilter = Option<gitignore::GitIgnore>;
+    type ServerParams = nomad_collab_params::NomadParams;
+
+    type ConnectToServerError = connect::ConnectToServerError;
+    type DefaultDirForRemoteProjectsError = HeadlessDataDirError;
+    type ListenForAgentsError = io::Error;
+    type LspRootError = Infallible;
+    type ProjectFilterError = gitignore::CreateError;
+    type RecordingsDirError = HeadlessDataDirError;
+    type ServeLocallyError = io::Error;
+    type SnapshotsDirError = HeadlessDataDirError;
+
+    async fn confirm_start(_: &AbsPath, _: &mut Context<Self>) -> bool {
+        // The user already confirmed by running the command.
+        true
+    }
+
+    async fn connect_to_server(
+        server_addr: config::ServerAddress<'static>,
+        ctx: &mut Context<Self>,
+    ) -> Result<Self::Io, Self::ConnectToServerError> {
+        connect::connect_to_server(server_addr, ctx).await
+    }
+
+    fn create_buffer_blame(
+        buffer_id: Self::BufferId,
+        _: &[blame::LineAuthor],
+        _: &mut Context<Self>,
+    ) -> Self::BufferBlame {
+        match buffer_id {}
+    }
+
+    fn create_peer_selection(
+        _: Peer,
+        _: Range<ByteOffset>,
+        buffer_id: Self::BufferId,
+        _: &mut Context<Self>,
+    ) -> Self::PeerSelection {
+        match buffer_id {}
+    }
+
+    fn create_peer_tooltip(
+        _: Peer,
+        _: ByteOffset,
+        buffer_id: Self::BufferId,
+        _: &mut Context<Self>,
+    ) -> Self::PeerTooltip {
+        match buffer_id {}
+    }
+
+    async fn default_dir_for_remote_projects(
+        ctx: &mut Context<Self>,
+    ) -> Result<AbsPathBuf, Self::DefaultDirForRemoteProjectsError> {
+        Ok(data_dir(ctx)
+            .await?
+            .join(node!("nomad"))
+            .join(node!("collab"))
+            .join(node!("remote-projects")))
+    }
+
+    async fn jump_to(
+        buffer_id: Self::BufferId,
+        _: ByteOffset,
+        _: AgentId,
+        _: &mut Context<Self>,
+    ) {
+        match buffer_id {}
+    }
+
+    fn listen_for_agents(
+        socket_path: &AbsPath,
+        _: &mut Context<Self>,
+    ) -> Result<Self::AgentListener, Self::ListenForAgentsError> {
+        agent_socket::AgentListener::bind(socket_path)
+    }
+
+    fn lsp_root(
+        buffer_id: Self::BufferId,
+        _: &mut Context<Self>,
+    ) -> Result<Option<AbsPathBuf>, Self::LspRootError> {
+        match buffer_id {}
+    }
+
+    fn move_peer_selection(
+        (): &mut Self::PeerSelection,
+        _: Range<ByteOffset>,
+        _: &mut Context<Self>,
+    ) {
+    }
+
+    fn move_peer_tooltip(
+        (): &mut Self::PeerTooltip,
+        _: ByteOffset,
+        _: &mut Context<Self>,
+    ) {
+    }
+
+    fn on_blame_error(error: blame::BlameError, ctx: &mut Context<Self>) {
+        ctx.notify_error(error);
+    }
+
+    fn on_copied_session_id(
+        session_id: SessionId<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_info(format_args!("Copied '{session_id}'"));
+    }
+
+    fn on_copy_session_id_error(
+        error: copy_id::CopyIdError<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        match error {
+            copy_id::CopyIdError::CopySessionId(err, session_id) => {
+                ctx.notify_error(format_args!(
+                    "Couldn't copy '{session_id}': {err}"
+                ));
+            },
+            copy_id::CopyIdError::NoActiveSession => {
+                ctx.notify_error(NoActiveSessionError);
+            },
+        }
+    }
+
+    fn on_follow_error(
+        error: follow::FollowError<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_error(error);
+    }
+
+    fn on_host_changed(
+        new_host: &Peer,
+        proj: &Project<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        if new_host.id == proj.local_peer.id {
+            ctx.notify_info(format_args!(
+                "You are now the host of {}",
+                proj.name()
+            ));
+        } else {
+            ctx.notify_info(format_args!(
+                "{} is now the host of {}",
+                new_host.handle,
+                proj.name()
+            ));
+        }
+    }
+
+    fn on_host_local_error(
+        error: host_local::HostLocalError<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_error(error);
+    }
+
+    fn on_init(ctx: &mut Context<Self, Borrowed>) {
+        // The files on disk are the only buffers we have, so we need to
+        // watch them to propagate their changes to the other peers.
+        match real_fs::watcher::start() {
+            Ok(mut errors) => ctx.spawn_and_detach(async move |ctx| {
+                while let Some(err) = errors.next().await {
+                    ctx.notify_error(format_args!(
+                        "Couldn't watch the project for changes: {err}"
+                    ));
+                }
+            }),
+            Err(err) => ctx.notify_error(format_args!(
+                "Couldn't start watching the filesystem: {err}"
+            )),
+        }
+    }
+
+    fn on_jump_error(error: jump::JumpError<Self>, ctx: &mut Context<Self>) {
+        ctx.notify_error(error);
+    }
+
+    fn on_leave_error(error: leave::LeaveError, ctx: &mut Context<Self>) {
+        ctx.notify_error(error);
+    }
+
+    fn on_list_peers_error(
+        error: list_peers::ListPeersError<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_error(error);
+    }
+
+    fn on_pause_error(
+        error: pause::PauseError<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_error(error);
+    }
+
+    fn on_peer_left(
+        peer: &Peer,
+        proj: &Project<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_info(format_args!(
+            "{} has left {}",
+            peer.handle,
+            proj.name()
+        ));
+    }
+
+    fn on_peer_joined(
+        peer: &Peer,
+        proj: &Project<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_info(format_args!(
+            "{} has joined {}",
+            peer.handle,
+            proj.name()
+        ));
+    }
+
+    fn on_permissions_error(
+        error: permissions::PermissionsError<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_error(error);
+    }
+
+    fn on_resume_error(
+        error: resume::ResumeError<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_error(error);
+    }
+
+    fn on_session_disconnected(
+        infos: &SessionInfos<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_warn(format_args!(
+            "Lost connection to the server for project {}, reconnecting...",
+            infos.proj_name()
+        ));
+    }
+
+    fn on_session_ended(infos: &SessionInfos<Self>, ctx: &mut Context<Self>) {
+        ctx.notify_info(format_args!(
+            "Session for project {} has ended",
+            infos.proj_name()
+        ));
+        ctx.with_editor(|headless| headless.exit(ExitCode::SUCCESS));
+    }
+
+    fn on_session_error(error: SessionError<Self>, ctx: &mut Context<Self>) {
+        ctx.notify_error(error);
+        ctx.with_editor(|headless| headless.exit(ExitCode::FAILURE));
+    }
+
+    async fn on_session_joined(
+        infos: &SessionInfos<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_info(format_args!(
+            "Joined session {} under {}",
+            infos.session_id, infos.project_root_path
+        ));
+    }
+
+    fn on_session_left(infos: &SessionInfos<Self>, ctx: &mut Context<Self>) {
+        ctx.notify_info(format_args!(
+            "Left session for project {}",
+            infos.proj_name()
+        ));
+        ctx.with_editor(|headless| headless.exit(ExitCode::SUCCESS));
+    }
+
+    fn on_session_reconnected(
+        infos: &SessionInfos<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_info(format_args!(
+            "Reconnected to session for project {}",
+            infos.proj_name()
+        ));
+    }
+
+    async fn on_session_started(
+        infos: &SessionInfos<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_info(format_args!(
+            "Started session at {}, share this ID with other peers to let \
+             them join:",
+            infos.project_root_path
+        ));
+        // Print the ID on its own line to stdout so that scripts can read it.
+        println!("{}", infos.session_id);
+    }
+
+    fn on_stopped_following(peer: &Peer, ctx: &mut Context<Self>) {
+        ctx.notify_info(format_args!("Stopped following {}", peer.handle));
+    }
+
+    fn on_undo_error(error: undo::UndoError, ctx: &mut Context<Self>) {
+        ctx.notify_error(error);
+    }
+
+    fn on_unfollow_error(
+        error: unfollow::UnfollowError,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_error(error);
+    }
+
+    fn project_filter(
+        project_root: &<Self::Fs as fs::Fs>::Directory,
+        ctx: &mut Context<Self>,
+    ) -> Result<Self::ProjectFilter, Self::ProjectFilterError> {
+        let create_res = ctx.with_editor(|headless| {
+            let spawner = headless.executor().background_spawner();
+            gitignore::GitIgnore::new(project_root.path(), spawner)
+        });
+
+        match create_res {
+            Ok(gitignore) => Ok(Some(gitignore)),
+
+            Err(err) => match &err {
+                gitignore::CreateError::GitNotInPath
+                | gitignore::CreateError::PathNotInGitRepository => Ok(None),
+
+                gitignore::CreateError::CommandFailed(_)
+                | gitignore::CreateError::InvalidPath => Err(err),
+            },
+        }
+    }
+
+    async fn recordings_dir(
+        ctx: &mut Context<Self>,
+    ) -> Result<AbsPathBuf, Self::RecordingsDirError> {
+        Ok(data_dir(ctx)
+            .await?
+            .join(node!("nomad"))
+            .join(node!("collab"))
+            .join(node!("recordings")))
+    }
+
+    fn remove_buffer_blame((): Self::BufferBlame, _: &mut Context<Self>) {}
+
+    fn remove_peer_selection((): Self::PeerSelection, _: &mut Context<Self>) {}
+
+    fn remove_peer_tooltip((): Self::PeerTooltip, _: &mut Context<Self>) {}
+
+    fn render_statusline(statuses: &[statusline::SessionStatus]) -> String {
+        statuses
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+
+    async fn select_peer<'peers>(
+        _: &'peers [list_peers::PeerInfos],
+        _: &mut Context<Self>,
+    ) -> Option<&'peers list_peers::PeerInfos> {
+        // There's no way to prompt the user from the CLI.
+        None
+    }
+
+    async fn select_session<'pairs>(
+        sessions: &'pairs [(AbsPathBuf, SessionId<Self>)],
+        _: ActionForSelectedSession,
+        _: &mut Context<Self>,
+    ) -> Option<&'pairs (AbsPathBuf, SessionId<Self>)> {
+        // The CLI runs at most one session at a time.
+        sessions.first()
+    }
+
+    async fn serve_locally(
+        listen_addr: SocketAddr,
+        ctx: &mut Context<Self>,
+    ) -> Result<host_local::LocalServer, Self::ServeLocallyError> {
+        local_server::serve(listen_addr, ctx).await
+    }
+
+    fn set_buffer_read_only(
+        buffer_id: Self::BufferId,
+        _: bool,
+        _: &mut Context<Self>,
+    ) {
+        match buffer_id {}
+    }
+
+    fn set_peer_tooltip_activity(
+        (): &mut Self::PeerTooltip,
+        _: PeerActivity,
+        _: &mut Context<Self>,
+    ) {
+    }
+
+    fn should_remote_edit_create_buffer(
+        _: &AbsPath,
+        _: &mut Context<Self>,
+    ) -> bool {
+        false
+    }
+
+    fn should_remote_save_cause_local_save(_: &Self::Buffer<'_>) -> bool {
+        false
+    }
+
+    async fn sleep(duration: Duration, _: &mut Context<Self>) {
+        async_io::Timer::after(duration).await;
+    }
+
+    async fn snapshots_dir(
+        ctx: &mut Context<Self>,
+    ) -> Result<AbsPathBuf, Self::SnapshotsDirError> {
+        Ok(data_dir(ctx)
+            .await?
+            .join(node!("nomad"))
+            .join(node!("collab"))
+            .join(node!("snapshots")))
+    }
+}
+
+trait DisplayablePipeline: Pipeline {
+    fn display_state(state: Self::State<'_>) -> Option<String>;
+}
+
+impl<P: DisplayablePipeline> ProgressReporter<Headless, P>
+    for HeadlessProgressReporter
+{
+    fn new(_: &mut Context<Headless>) -> Self {
+        Self
+    }
+
+    fn report_success(self, _: P::Output<'_>, _: &mut Context<Headless>) {}
+
+    fn report_error(self, _: P::Error<'_>, _: &mut Context<Headless>) {}
+
+    fn report_progress(
+        &mut self,
+        state: P::State<'_>,
+        ctx: &mut Context<Headless>,
+    ) {
+        if let Some(message) = P::display_state(state) {
+            ctx.notify_info(message);
+        }
+    }
+
+    fn report_cancellation(self, _: &mut Context<Headless>) {}
+}
+
+impl DisplayablePipeline for join::Join<Headless> {
+    fn display_state(state: JoinState<'_>) -> Option<String> {
+        match state {
+            JoinState::ConnectingToServer(server_addr) => {
+                Some(format!("Connecting to server at {server_addr}"))
+            },
+            JoinState::JoiningSession => Some("Joining session".to_owned()),
+            JoinState::ReceivedWelcome(project_name) => {
+                Some(format!("Receiving files for {project_name}"))
+            },
+            // Printing every chunk we receive would flood the terminal.
+            JoinState::ReceivingProject(..) => None,
+            JoinState::WritingProject(root_path) => {
+                Some(format!("Writing project to {root_path}"))
+            },
+        }
+    }
+}
+
+impl DisplayablePipeline for start::Start<Headless> {
+    fn display_state(state: StartState<'_>) -> Option<String> {
+        match state {
+            StartState::ConnectingToServer(server_addr) => {
+                Some(format!("Connecting to server at {server_addr}"))
+            },
+            StartState::StartingSession => Some("Starting session".to_owned()),
+            StartState::ReadingProject(root_path) => {
+                Some(format!("Reading project at {root_path}"))
+            },
+        }
+    }
+}
+
+/// Returns the path to the directory where user-specific data files should
+/// be stored, following the XDG Base Directory specification.
+async fn data_dir(
+    ctx: &mut Context<Headless>,
+) -> Result<AbsPathBuf, HeadlessDataDirError> {
+    if let Some(data_home) = env::var_os("XDG_DATA_HOME")
+        .and_then(|path| AbsPathBuf::try_from(Path::new(&path)).ok())
+    {
+        return Ok(data_home);
+    }
+
+    let home = ctx
+        .fs()
+        .home()
+        .await
+        .map_err(HeadlessDataDirError::Home)?
+        .ok_or(HeadlessDataDirError::NoHome)?;
+
+    Ok(home.path().join(node!(".local")).join(node!("share")))
+}
+
+#[cfg(test)]
+mod tests {
+    use abs_path::path;
+
+    use super::*;
+
+    #[test]
+    fn join_progress_skips_received_chunks() {
+        let state = JoinState::ReceivingProject(1024, 4096);
+        assert_eq!(join::Join::<Headless>::display_state(state), None);
+    }
+
+    #[test]
+    fn start_progress_mentions_project_root() {
+        let state = StartState::ReadingProject(path!("/foo/bar").into());
+        assert_eq!(
+            start::Start::<Headless>::display_state(state).as_deref(),
+            Some("Reading project at /foo/bar")
+        );
+    }
+
+    #[test]
+    fn statusline_joins_sessions() {
+        let status =
+            |project_name: &str, num_peers| statusline::SessionStatus {
+                is_connected: true,
+                is_paused: false,
+                num_pending: 0,
+                num_peers,
+                project_name: project_name.to_owned(),
+            };
+        let statuses = [status("bar", 1), status("foo", 2)];
+        assert_eq!(
+            Headless::render_statusline(&statuses),
+            "bar: 1 peer | foo: 2 peers"
+        );
+    }
+}
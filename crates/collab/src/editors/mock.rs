@@ -2,7 +2,9 @@
 
 use core::convert::Infallible;
 use core::error::Error;
+use core::net::SocketAddr;
 use core::ops::Range;
+use core::time::Duration;
 use core::{fmt, ops};
 
 use abs_path::{AbsPath, AbsPathBuf, NodeName};
@@ -10,12 +12,36 @@ pub use collab_server::test::TestSessionId as MockSessionId;
 use collab_types::{Peer, PeerHandle};
 use duplex_stream::{DuplexStream, duplex};
 use editor::context::Borrowed;
-use editor::{AgentId, ByteOffset, Context, Editor, EditorAdapter};
+use editor::{
+    AgentId,
+    Buffer,
+    ByteOffset,
+    Context,
+    Cursor,
+    Editor,
+    EditorAdapter,
+};
 
 use crate::editors::{ActionForSelectedSession, CollabEditor};
+use crate::peers::PeerActivity;
 use crate::project::Project;
 use crate::session::{SessionError, SessionInfos};
-use crate::{config, copy_id, jump, leave, pause, resume};
+use crate::{
+    blame,
+    config,
+    copy_id,
+    follow,
+    host_local,
+    jump,
+    leave,
+    list_peers,
+    pause,
+    permissions,
+    resume,
+    statusline,
+    undo,
+    unfollow,
+};
 
 #[allow(clippy::type_complexity)]
 pub struct CollabMock<Ed: Editor, F = ()> {
@@ -25,6 +51,14 @@ pub struct CollabMock<Ed: Editor, F = ()> {
     default_dir_for_remote_projects: Option<AbsPathBuf>,
     lsp_root_with: Option<Box<dyn FnMut(Ed::BufferId) -> Option<AbsPathBuf>>>,
     project_filter_with: Box<dyn FnMut(&<Ed::Fs as fs::Fs>::Directory) -> F>,
+    recordings_dir: Option<AbsPathBuf>,
+    select_peer_with: Option<
+        Box<
+            dyn FnMut(
+                &[list_peers::PeerInfos],
+            ) -> Option<&list_peers::PeerInfos>,
+        >,
+    >,
     select_session_with: Option<
         Box<
             dyn FnMut(
@@ -34,6 +68,7 @@ pub struct CollabMock<Ed: Editor, F = ()> {
         >,
     >,
     server_tx: Option<flume::Sender<DuplexStream>>,
+    snapshots_dir: Option<AbsPathBuf>,
 }
 
 pub struct CollabServer {
@@ -57,10 +92,24 @@ pub struct AnyError {
     inner: Box<dyn Error>,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MockPeerTooltip {
+    pub offset: ByteOffset,
+    pub activity: PeerActivity,
+}
+
 #[derive(Debug, derive_more::Display, cauchy::Error)]
 #[display("no default directory for remote projects configured")]
 pub struct NoDefaultDirForRemoteProjectsError;
 
+#[derive(Debug, derive_more::Display, cauchy::Error)]
+#[display("no directory for session recordings configured")]
+pub struct NoRecordingsDirError;
+
+#[derive(Debug, derive_more::Display, cauchy::Error)]
+#[display("no directory for project snapshots configured")]
+pub struct NoSnapshotsDirError;
+
 impl<Ed: Editor> CollabMock<Ed, ()> {
     pub fn new(inner: Ed) -> Self {
         Self {
@@ -70,8 +119,11 @@ impl<Ed: Editor> CollabMock<Ed, ()> {
             inner,
             lsp_root_with: None,
             project_filter_with: Box::new(|_| ()),
+            recordings_dir: None,
+            select_peer_with: None,
             select_session_with: None,
             server_tx: None,
+            snapshots_dir: None,
         }
     }
 }
@@ -97,6 +149,15 @@ where
         self
     }
 
+    pub fn select_peer_with(
+        mut self,
+        fun: impl FnMut(&[list_peers::PeerInfos]) -> Option<&list_peers::PeerInfos>
+        + 'static,
+    ) -> Self {
+        self.select_peer_with = Some(Box::new(fun) as _);
+        self
+    }
+
     pub fn select_session_with(
         mut self,
         fun: impl FnMut(
@@ -134,11 +195,30 @@ where
                 .default_dir_for_remote_projects,
             lsp_root_with: self.lsp_root_with,
             project_filter_with: Box::new(project_filter),
+            recordings_dir: self.recordings_dir,
+            select_peer_with: self.select_peer_with,
             select_session_with: self.select_session_with,
             server_tx: self.server_tx,
+            snapshots_dir: self.snapshots_dir,
         }
     }
 
+    pub fn with_recordings_dir(
+        mut self,
+        dir_path: impl AsRef<AbsPath>,
+    ) -> Self {
+        self.recordings_dir = Some(dir_path.as_ref().to_owned());
+        self
+    }
+
+    pub fn with_snapshots_dir(
+        mut self,
+        dir_path: impl AsRef<AbsPath>,
+    ) -> Self {
+        self.snapshots_dir = Some(dir_path.as_ref().to_owned());
+        self
+    }
+
     pub fn with_server(mut self, server: &CollabServer) -> Self {
         self.server_tx = Some(server.conn_tx.clone());
         self
@@ -186,17 +266,23 @@ where
     Ed: Editor,
     F: fs::filter::Filter<Ed::Fs, Error: Send> + Send + Sync + 'static,
 {
+    type AgentListener = futures_util::stream::Empty<DuplexStream>;
+    type BufferBlame = Vec<blame::LineAuthor>;
     type Io = DuplexStream;
     type PeerSelection = ();
-    type PeerTooltip = ByteOffset;
+    type PeerTooltip = MockPeerTooltip;
     type ProgressReporter = ();
     type ProjectFilter = F;
     type ServerParams = MockParams;
 
     type ConnectToServerError = AnyError;
     type DefaultDirForRemoteProjectsError = NoDefaultDirForRemoteProjectsError;
+    type ListenForAgentsError = Infallible;
     type LspRootError = Infallible;
     type ProjectFilterError = Infallible;
+    type RecordingsDirError = NoRecordingsDirError;
+    type ServeLocallyError = AnyError;
+    type SnapshotsDirError = NoSnapshotsDirError;
 
     async fn confirm_start(
         project_root: &AbsPath,
@@ -223,6 +309,14 @@ where
         Ok(client_io)
     }
 
+    fn create_buffer_blame(
+        _buffer_id: Self::BufferId,
+        line_authors: &[blame::LineAuthor],
+        _ctx: &mut Context<Self>,
+    ) -> Self::BufferBlame {
+        line_authors.to_vec()
+    }
+
     fn create_peer_selection(
         _remote_peer: Peer,
         _selected_range: Range<ByteOffset>,
@@ -237,7 +331,10 @@ where
         _buffer_id: Self::BufferId,
         _ctx: &mut Context<Self>,
     ) -> Self::PeerTooltip {
-        tooltip_offset
+        MockPeerTooltip {
+            offset: tooltip_offset,
+            activity: PeerActivity::Active,
+        }
     }
 
     async fn default_dir_for_remote_projects(
@@ -251,12 +348,33 @@ where
     }
 
     async fn jump_to(
-        _buffer_id: Self::BufferId,
-        _offset: ByteOffset,
-        _agent_id: AgentId,
-        _ctx: &mut Context<Self>,
+        buffer_id: Self::BufferId,
+        offset: ByteOffset,
+        agent_id: AgentId,
+        ctx: &mut Context<Self>,
     ) {
-        todo!();
+        let Some(focus_buffer) = ctx.with_editor(|this| {
+            Some(this.buffer(buffer_id.clone())?.schedule_focus(agent_id))
+        }) else {
+            return;
+        };
+
+        focus_buffer.await;
+
+        ctx.with_editor(|this| {
+            let Some(mut buffer) = this.buffer(buffer_id) else { return };
+            buffer.for_each_cursor(|mut cursor| {
+                let _ = cursor.schedule_move(offset, agent_id);
+            });
+        });
+    }
+
+    fn listen_for_agents(
+        _: &AbsPath,
+        _: &mut Context<Self>,
+    ) -> Result<Self::AgentListener, Self::ListenForAgentsError> {
+        // Agents can't connect to mock sessions.
+        Ok(futures_util::stream::empty())
     }
 
     fn lsp_root(
@@ -278,9 +396,11 @@ where
         tooltip_offset: ByteOffset,
         _ctx: &mut Context<Self>,
     ) {
-        *tooltip = tooltip_offset;
+        tooltip.offset = tooltip_offset;
     }
 
+    fn on_blame_error(_: blame::BlameError, _: &mut Context<Self>) {}
+
     fn on_copied_session_id(_: MockSessionId, _: &mut Context<Self>) {}
 
     fn on_copy_session_id_error(
@@ -289,20 +409,45 @@ where
     ) {
     }
 
+    fn on_follow_error(_: follow::FollowError<Self>, _: &mut Context<Self>) {}
+
+    fn on_host_changed(_: &Peer, _: &Project<Self>, _: &mut Context<Self>) {}
+
+    fn on_host_local_error(
+        _: host_local::HostLocalError<Self>,
+        _: &mut Context<Self>,
+    ) {
+    }
+
     fn on_init(_: &mut Context<Self, Borrowed>) {}
 
     fn on_jump_error(_: jump::JumpError<Self>, _: &mut Context<Self>) {}
 
     fn on_leave_error(_: leave::LeaveError, _: &mut Context<Self>) {}
 
+    fn on_list_peers_error(
+        _: list_peers::ListPeersError<Self>,
+        _: &mut Context<Self>,
+    ) {
+    }
+
     fn on_pause_error(_: pause::PauseError<Self>, _: &mut Context<Self>) {}
 
     fn on_peer_left(_: &Peer, _: &Project<Self>, _: &mut Context<Self>) {}
 
     fn on_peer_joined(_: &Peer, _: &Project<Self>, _: &mut Context<Self>) {}
 
+    fn on_permissions_error(
+        _: permissions::PermissionsError<Self>,
+        _: &mut Context<Self>,
+    ) {
+    }
+
     fn on_resume_error(_: resume::ResumeError<Self>, _: &mut Context<Self>) {}
 
+    fn on_session_disconnected(_: &SessionInfos<Self>, _: &mut Context<Self>) {
+    }
+
     fn on_session_ended(_: &SessionInfos<Self>, _: &mut Context<Self>) {}
 
     fn on_session_error(_: SessionError<Self>, _: &mut Context<Self>) {}
@@ -312,12 +457,20 @@ where
 
     fn on_session_left(_: &SessionInfos<Self>, _: &mut Context<Self>) {}
 
+    fn on_session_reconnected(_: &SessionInfos<Self>, _: &mut Context<Self>) {}
+
     async fn on_session_started(
         _: &SessionInfos<Self>,
         _: &mut Context<Self>,
     ) {
     }
 
+    fn on_stopped_following(_: &Peer, _: &mut Context<Self>) {}
+
+    fn on_undo_error(_: undo::UndoError, _: &mut Context<Self>) {}
+
+    fn on_unfollow_error(_: unfollow::UnfollowError, _: &mut Context<Self>) {}
+
     fn project_filter(
         project_root: &<Self::Fs as fs::Fs>::Directory,
         ctx: &mut Context<Self>,
@@ -327,6 +480,20 @@ where
         }))
     }
 
+    async fn recordings_dir(
+        ctx: &mut Context<Self>,
+    ) -> Result<AbsPathBuf, Self::RecordingsDirError> {
+        ctx.with_editor(|this| {
+            this.recordings_dir.clone().ok_or(NoRecordingsDirError)
+        })
+    }
+
+    fn remove_buffer_blame(
+        _blame: Self::BufferBlame,
+        _ctx: &mut Context<Self>,
+    ) {
+    }
+
     fn remove_peer_selection(
         _selection: Self::PeerSelection,
         _ctx: &mut Context<Self>,
@@ -339,6 +506,21 @@ where
     ) {
     }
 
+    fn render_statusline(statuses: &[statusline::SessionStatus]) -> String {
+        statuses
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+
+    async fn select_peer<'peers>(
+        peers: &'peers [list_peers::PeerInfos],
+        ctx: &mut Context<Self>,
+    ) -> Option<&'peers list_peers::PeerInfos> {
+        ctx.with_editor(|this| this.select_peer_with.as_mut()?(peers))
+    }
+
     async fn select_session<'pairs>(
         sessions: &'pairs [(AbsPathBuf, MockSessionId)],
         action: ActionForSelectedSession,
@@ -349,9 +531,54 @@ where
         })
     }
 
+    async fn serve_locally(
+        _: SocketAddr,
+        ctx: &mut Context<Self>,
+    ) -> Result<host_local::LocalServer, Self::ServeLocallyError> {
+        // The mock server already runs in-process, and connecting to it
+        // doesn't depend on the address.
+        ctx.with_editor(|this| this.server_tx.is_some())
+            .then(|| host_local::LocalServer::new(Default::default()).0)
+            .ok_or(AnyError::from_str("no server set"))
+    }
+
+    fn set_buffer_read_only(
+        _: Self::BufferId,
+        _: bool,
+        _: &mut Context<Self>,
+    ) {
+    }
+
+    fn set_peer_tooltip_activity(
+        tooltip: &mut Self::PeerTooltip,
+        activity: PeerActivity,
+        _: &mut Context<Self>,
+    ) {
+        tooltip.activity = activity;
+    }
+
+    fn should_remote_edit_create_buffer(
+        _: &AbsPath,
+        _: &mut Context<Self>,
+    ) -> bool {
+        true
+    }
+
     fn should_remote_save_cause_local_save(_: &Self::Buffer<'_>) -> bool {
         true
     }
+
+    async fn sleep(_: Duration, _: &mut Context<Self>) {
+        // Don't actually wait, to keep tests fast.
+    }
+
+    async fn snapshots_dir(
+        ctx: &mut Context<Self>,
+    ) -> Result<AbsPathBuf, Self::SnapshotsDirError> {
+        ctx.with_editor(|this| {
+            this.snapshots_dir.clone().ok_or(NoSnapshotsDirError)
+        })
+    }
 }
 
 impl<Ed: Editor + Default> Default for CollabMock<Ed, ()> {
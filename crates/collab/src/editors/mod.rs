@@ -1,5 +1,7 @@
 //! Contains the editor-specific implementations of [`CollabEditor`].
 
+#[cfg(feature = "headless")]
+pub mod headless;
 #[cfg(feature = "mock")]
 pub mod mock;
 #[cfg(feature = "neovim")]
@@ -7,23 +9,53 @@ pub mod neovim;
 
 use core::error::Error;
 use core::fmt::{Debug, Display};
+use core::net::SocketAddr;
 use core::ops::Range;
 use core::str::FromStr;
+use core::time::Duration;
 
 use abs_path::{AbsPath, AbsPathBuf};
 use collab_types::Peer;
 use editor::context::Borrowed;
 use editor::{AgentId, ByteOffset, Context, Editor};
-use futures_util::{AsyncRead, AsyncWrite};
+use futures_util::{AsyncRead, AsyncWrite, Stream};
 
+use crate::peers::PeerActivity;
 use crate::progress::ProgressReporter;
 use crate::project::Project;
 use crate::session::{SessionError, SessionInfos};
-use crate::{config, copy_id, join, jump, leave, pause, resume, start};
+use crate::{
+    blame,
+    config,
+    copy_id,
+    follow,
+    host_local,
+    join,
+    jump,
+    leave,
+    list_peers,
+    pause,
+    permissions,
+    resume,
+    start,
+    statusline,
+    undo,
+    unfollow,
+};
 
 /// An [`Editor`] subtrait defining additional capabilities needed by the
 /// actions in this crate.
 pub trait CollabEditor: Editor {
+    /// The type of stream yielding the connections of the agents that
+    /// connect to a session's agent socket.
+    type AgentListener: Stream<Item: AsyncRead + AsyncWrite + Unpin + 'static>
+        + Unpin
+        + 'static;
+
+    /// The type representing the authors of the lines in a buffer, as
+    /// displayed by the [`Blame`](blame::Blame) action.
+    type BufferBlame;
+
     /// TODO: docs.
     type Io: AsyncRead + AsyncWrite + Unpin;
 
@@ -56,6 +88,10 @@ pub trait CollabEditor: Editor {
     /// [`default_dir_for_remote_projects`](CollabEditor::default_dir_for_remote_projects).
     type DefaultDirForRemoteProjectsError: Debug;
 
+    /// The type of error returned by
+    /// [`listen_for_agents`](CollabEditor::listen_for_agents).
+    type ListenForAgentsError: Debug;
+
     /// The type of error returned by [`lsp_root`](CollabEditor::lsp_root).
     type LspRootError: Debug;
 
@@ -63,6 +99,18 @@ pub trait CollabEditor: Editor {
     /// [`project_filter`](CollabEditor::project_filter).
     type ProjectFilterError: Error + Send;
 
+    /// The type of error returned by
+    /// [`recordings_dir`](CollabEditor::recordings_dir).
+    type RecordingsDirError: Debug;
+
+    /// The type of error returned by
+    /// [`serve_locally`](CollabEditor::serve_locally).
+    type ServeLocallyError: Debug;
+
+    /// The type of error returned by
+    /// [`snapshots_dir`](CollabEditor::snapshots_dir).
+    type SnapshotsDirError: Debug;
+
     /// Asks the user to confirm starting a new collaborative editing session
     /// rooted at the given path.
     fn confirm_start(
@@ -76,6 +124,13 @@ pub trait CollabEditor: Editor {
         ctx: &mut Context<Self>,
     ) -> impl Future<Output = Result<Self::Io, Self::ConnectToServerError>>;
 
+    /// Displays the authors of the lines in the buffer with the given ID.
+    fn create_buffer_blame(
+        buffer_id: Self::BufferId,
+        line_authors: &[blame::LineAuthor],
+        ctx: &mut Context<Self>,
+    ) -> Self::BufferBlame;
+
     /// TODO: docs.
     fn create_peer_selection(
         remote_peer: Peer,
@@ -108,6 +163,15 @@ pub trait CollabEditor: Editor {
         ctx: &mut Context<Self>,
     ) -> impl Future<Output = ()>;
 
+    /// Starts listening for agents on the Unix domain socket at the given
+    /// path, creating it if necessary.
+    ///
+    /// The socket should be removed once the returned listener is dropped.
+    fn listen_for_agents(
+        socket_path: &AbsPath,
+        ctx: &mut Context<Self>,
+    ) -> Result<Self::AgentListener, Self::ListenForAgentsError>;
+
     /// Returns the path to the root of the workspace containing the buffer
     /// with the given ID, or `None` if there's no language server attached to
     /// it.
@@ -130,6 +194,9 @@ pub trait CollabEditor: Editor {
         ctx: &mut Context<Self>,
     );
 
+    /// Called when the [`Blame`](blame::Blame) action returns an error.
+    fn on_blame_error(error: blame::BlameError, ctx: &mut Context<Self>);
+
     /// Called after the given session ID has been copied to the clipboard.
     fn on_copied_session_id(
         session_id: SessionId<Self>,
@@ -142,6 +209,28 @@ pub trait CollabEditor: Editor {
         ctx: &mut Context<Self>,
     );
 
+    /// Called when the [`Follow`](follow::Follow) action returns an error, or
+    /// when jumping to the followed peer's cursor fails.
+    fn on_follow_error(
+        error: follow::FollowError<Self>,
+        ctx: &mut Context<Self>,
+    );
+
+    /// Called when the host of the session for the given project leaves, and
+    /// the given peer is elected as the new host.
+    fn on_host_changed(
+        new_host: &Peer,
+        proj: &Project<Self>,
+        ctx: &mut Context<Self>,
+    );
+
+    /// Called when the [`HostLocal`](host_local::HostLocal) action fails
+    /// before a session could be started.
+    fn on_host_local_error(
+        error: host_local::HostLocalError<Self>,
+        ctx: &mut Context<Self>,
+    );
+
     /// Called when the [`Collab`](crate::Collab) module is initialized.
     fn on_init(ctx: &mut Context<Self, Borrowed>);
 
@@ -151,6 +240,13 @@ pub trait CollabEditor: Editor {
     /// Called when the [`Leave`](leave::Leave) action returns an error.
     fn on_leave_error(error: leave::LeaveError, ctx: &mut Context<Self>);
 
+    /// Called when the [`ListPeers`](list_peers::ListPeers) action returns
+    /// an error.
+    fn on_list_peers_error(
+        error: list_peers::ListPeersError<Self>,
+        ctx: &mut Context<Self>,
+    );
+
     /// Called when the [`Pause`](pause::Pause) action returns an error.
     fn on_pause_error(error: pause::PauseError<Self>, ctx: &mut Context<Self>);
 
@@ -168,12 +264,26 @@ pub trait CollabEditor: Editor {
         ctx: &mut Context<Self>,
     );
 
+    /// Called when the [`ReadOnly`](crate::read_only::ReadOnly) or
+    /// [`ReadWrite`](crate::read_write::ReadWrite) actions return an error.
+    fn on_permissions_error(
+        error: permissions::PermissionsError<Self>,
+        ctx: &mut Context<Self>,
+    );
+
     /// Called when the [`Resume`](resume::Resume) action returns an error.
     fn on_resume_error(
         error: resume::ResumeError<Self>,
         ctx: &mut Context<Self>,
     );
 
+    /// Called when the connection to the server is lost, right before trying
+    /// to reconnect.
+    fn on_session_disconnected(
+        session_infos: &SessionInfos<Self>,
+        ctx: &mut Context<Self>,
+    );
+
     /// Called when a session ends.
     fn on_session_ended(
         session_infos: &SessionInfos<Self>,
@@ -195,18 +305,50 @@ pub trait CollabEditor: Editor {
         ctx: &mut Context<Self>,
     );
 
+    /// Called after successfully reconnecting to a session whose connection
+    /// to the server was lost.
+    fn on_session_reconnected(
+        session_infos: &SessionInfos<Self>,
+        ctx: &mut Context<Self>,
+    );
+
     /// Called when a new session is started.
     fn on_session_started(
         session_infos: &SessionInfos<Self>,
         ctx: &mut Context<Self>,
     ) -> impl Future<Output = ()>;
 
+    /// Called when the local user stops following the given remote peer,
+    /// either explicitly or by moving their own cursor.
+    fn on_stopped_following(peer: &Peer, ctx: &mut Context<Self>);
+
+    /// Called when the [`Undo`](undo::Undo) or [`Redo`](crate::redo::Redo)
+    /// actions return an error.
+    fn on_undo_error(error: undo::UndoError, ctx: &mut Context<Self>);
+
+    /// Called when the [`Unfollow`](unfollow::Unfollow) action returns an
+    /// error.
+    fn on_unfollow_error(
+        error: unfollow::UnfollowError,
+        ctx: &mut Context<Self>,
+    );
+
     /// TODO: docs.
     fn project_filter(
         project_root: &<Self::Fs as fs::Fs>::Directory,
         ctx: &mut Context<Self>,
     ) -> Result<Self::ProjectFilter, Self::ProjectFilterError>;
 
+    /// Returns the path to the directory where sessions are recorded when
+    /// [`record_sessions`](crate::config::Config) is enabled.
+    fn recordings_dir(
+        ctx: &mut Context<Self>,
+    ) -> impl Future<Output = Result<AbsPathBuf, Self::RecordingsDirError>>;
+
+    /// Removes the authors displayed by
+    /// [`create_buffer_blame`](CollabEditor::create_buffer_blame).
+    fn remove_buffer_blame(blame: Self::BufferBlame, ctx: &mut Context<Self>);
+
     /// TODO: docs.
     fn remove_peer_selection(
         selection: Self::PeerSelection,
@@ -219,6 +361,16 @@ pub trait CollabEditor: Editor {
         ctx: &mut Context<Self>,
     );
 
+    /// Renders the given (non-empty) session statuses into the string
+    /// returned by the [`Statusline`](statusline::Statusline) function.
+    fn render_statusline(statuses: &[statusline::SessionStatus]) -> String;
+
+    /// Prompts the user to select one of the given remote peers.
+    fn select_peer<'peers>(
+        peers: &'peers [list_peers::PeerInfos],
+        ctx: &mut Context<Self>,
+    ) -> impl Future<Output = Option<&'peers list_peers::PeerInfos>>;
+
     /// Prompts the user to select one of the given `(project_root,
     /// session_id)` pairs.
     fn select_session<'pairs>(
@@ -227,8 +379,58 @@ pub trait CollabEditor: Editor {
         ctx: &mut Context<Self>,
     ) -> impl Future<Output = Option<&'pairs (AbsPathBuf, SessionId<Self>)>>;
 
+    /// Starts a collab server inside the editor's process, listening for
+    /// connections on the given address.
+    ///
+    /// The server should keep running until the returned
+    /// [`LocalServer`](host_local::LocalServer) is dropped.
+    fn serve_locally(
+        listen_addr: SocketAddr,
+        ctx: &mut Context<Self>,
+    ) -> impl Future<
+        Output = Result<host_local::LocalServer, Self::ServeLocallyError>,
+    >;
+
+    /// Makes the buffer with the given ID non-modifiable if `read_only` is
+    /// true, or modifiable otherwise.
+    fn set_buffer_read_only(
+        buffer_id: Self::BufferId,
+        read_only: bool,
+        ctx: &mut Context<Self>,
+    );
+
+    /// Updates the given tooltip to reflect the [`PeerActivity`] of the remote
+    /// peer it belongs to.
+    fn set_peer_tooltip_activity(
+        tooltip: &mut Self::PeerTooltip,
+        activity: PeerActivity,
+        ctx: &mut Context<Self>,
+    );
+
+    /// Returns whether a remote edit to the file at the given path, which is
+    /// not currently open in any buffer, should open a new buffer for it.
+    ///
+    /// If this returns false, the file's new contents are written straight
+    /// to disk instead.
+    fn should_remote_edit_create_buffer(
+        file_path: &AbsPath,
+        ctx: &mut Context<Self>,
+    ) -> bool;
+
     /// TODO: docs.
     fn should_remote_save_cause_local_save(buffer: &Self::Buffer<'_>) -> bool;
+
+    /// Returns a future that completes after the given duration has elapsed.
+    fn sleep(
+        duration: Duration,
+        ctx: &mut Context<Self>,
+    ) -> impl Future<Output = ()>;
+
+    /// Returns the path to the directory where the snapshots of the projects
+    /// are saved when a session ends, so that they can be resumed later.
+    fn snapshots_dir(
+        ctx: &mut Context<Self>,
+    ) -> impl Future<Output = Result<AbsPathBuf, Self::SnapshotsDirError>>;
 }
 
 /// TODO: docs
@@ -242,6 +444,12 @@ pub enum ActionForSelectedSession {
     /// TODO: docs
     Pause,
 
+    /// Mark the local peer as read-only.
+    ReadOnly,
+
+    /// Mark the local peer as read-write.
+    ReadWrite,
+
     /// TODO: docs
     Resume,
 }
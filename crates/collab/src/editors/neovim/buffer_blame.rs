@@ -0,0 +1,66 @@
+use compact_str::format_compact;
+use neovim::oxi::api;
+
+use crate::blame::LineAuthor;
+use crate::editors::neovim::{PeerHandleHighlightGroup, PeerHighlightGroup};
+
+/// The authors of the lines in a buffer, each displayed as virtual text at
+/// the end of the line they wrote.
+pub struct NeovimBufferBlame {
+    /// The buffer the authors are displayed in.
+    buffer: api::Buffer,
+
+    /// The IDs of the extmarks used to display the authors, one per line.
+    extmark_ids: Vec<u32>,
+
+    /// The ID of the namespace the [`extmark_ids`](Self::extmark_ids) belong
+    /// to.
+    namespace_id: u32,
+}
+
+impl NeovimBufferBlame {
+    /// Displays the given line authors in the given buffer.
+    pub(super) fn create(
+        mut buffer: api::Buffer,
+        line_authors: &[LineAuthor],
+        namespace_id: u32,
+    ) -> Self {
+        let extmark_ids = line_authors
+            .iter()
+            .map(|author| {
+                let text = match &author.peer_handle {
+                    Some(handle) => format_compact!(" {handle} "),
+                    None => {
+                        format_compact!(
+                            " peer #{} ",
+                            author.peer_id.into_u64()
+                        )
+                    },
+                };
+
+                let hl_group_id =
+                    PeerHandleHighlightGroup::group_id(author.peer_id);
+
+                let opts = api::opts::SetExtmarkOpts::builder()
+                    .virt_text([(text.as_str(), hl_group_id)])
+                    .virt_text_pos(api::types::ExtmarkVirtTextPosition::Eol)
+                    .build();
+
+                buffer
+                    .set_extmark(namespace_id, author.line_idx, 0, &opts)
+                    .expect("couldn't create extmark")
+            })
+            .collect();
+
+        Self { buffer, extmark_ids, namespace_id }
+    }
+
+    /// Removes the authors from the buffer.
+    pub(super) fn remove(mut self) {
+        for extmark_id in self.extmark_ids {
+            self.buffer
+                .del_extmark(self.namespace_id, extmark_id)
+                .expect("couldn't delete extmark");
+        }
+    }
+}
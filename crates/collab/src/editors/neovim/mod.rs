@@ -1,5 +1,6 @@
 #![allow(missing_docs)]
 
+mod buffer_blame;
 mod neovim;
 mod notifications;
 mod peer_cursor;
@@ -7,12 +8,14 @@ mod peer_handle;
 mod peer_highlight_group;
 mod peer_selection;
 mod progress_reporter;
+mod statusline;
 
-pub use neovim::{NeovimConnectToServerError, NeovimLspRootError};
+pub use buffer_blame::NeovimBufferBlame;
+pub use neovim::NeovimLspRootError;
 pub use peer_cursor::NeovimPeerCursor;
 use peer_cursor::PeerCursorHighlightGroup;
 pub use peer_handle::NeovimPeerHandle;
-use peer_handle::PeerHandleHighlightGroup;
+use peer_handle::{IdlePeerHandleHighlightGroup, PeerHandleHighlightGroup};
 use peer_highlight_group::PeerHighlightGroup;
 pub use peer_selection::NeovimPeerSelection;
 use peer_selection::PeerSelectionHighlightGroup;
@@ -1,17 +1,15 @@
+use core::net::SocketAddr;
 use core::ops::Range;
+use core::time::Duration;
 use std::io;
-use std::sync::{Arc, OnceLock};
 
 use abs_path::{AbsPath, AbsPathBuf, node};
-use async_net::TcpStream;
 use collab_types::Peer;
 use compact_str::{ToCompactString, format_compact};
 use editor::context::Borrowed;
 use editor::{AgentId, Buffer, ByteOffset, Context, Cursor, Editor};
 use executor::Executor;
 use fs::Directory;
-use futures_rustls::client::TlsStream;
-use futures_rustls::{TlsConnector, rustls};
 use futures_util::future::{self, Either, FutureExt};
 use mlua::{Function, Table};
 use neovim::buffer::{BufferExt, BufferId};
@@ -19,6 +17,8 @@ use neovim::notify::{self, NotifyContextExt};
 use neovim::{Neovim, mlua, oxi};
 
 use crate::editors::neovim::{
+    IdlePeerHandleHighlightGroup,
+    NeovimBufferBlame,
     NeovimPeerCursor,
     NeovimPeerHandle,
     NeovimPeerSelection,
@@ -28,24 +28,32 @@ use crate::editors::neovim::{
     PeerHighlightGroup,
     PeerSelectionHighlightGroup,
     notifications,
+    statusline,
 };
 use crate::editors::{ActionForSelectedSession, CollabEditor};
+use crate::peers::PeerActivity;
 use crate::project::Project;
 use crate::session::{NoActiveSessionError, SessionError, SessionInfos};
-use crate::tcp_stream_ext::TcpStreamExt;
-use crate::{SessionId, config, copy_id, jump, leave, pause, resume};
-
-#[derive(Debug, derive_more::Display, cauchy::Error)]
-pub enum NeovimConnectToServerError {
-    #[display("couldn't establish TCP connection with server: {_0}")]
-    ConnectTcp(io::Error),
-
-    #[display("couldn't establish TLS connection with server: {_0}")]
-    ConnectTls(io::Error),
-
-    #[display("couldn't obtain TLS certificates from OS: {_0}")]
-    Certificates(futures_rustls::rustls::Error),
-}
+use crate::statusline::SessionStatus;
+use crate::{
+    SessionId,
+    agent_socket,
+    blame,
+    config,
+    connect,
+    copy_id,
+    follow,
+    host_local,
+    jump,
+    leave,
+    list_peers,
+    local_server,
+    pause,
+    permissions,
+    resume,
+    undo,
+    unfollow,
+};
 
 #[derive(Debug, derive_more::Display, cauchy::Error)]
 #[display("LSP root at {root_dir} is not an absolute path")]
@@ -54,17 +62,23 @@ pub struct NeovimLspRootError {
 }
 
 impl CollabEditor for Neovim {
-    type Io = Either<TlsStream<TcpStream>, TcpStream>;
+    type AgentListener = agent_socket::AgentListener;
+    type BufferBlame = NeovimBufferBlame;
+    type Io = connect::TcpIo;
     type PeerSelection = NeovimPeerSelection;
     type PeerTooltip = (NeovimPeerCursor, NeovimPeerHandle);
     type ProgressReporter = NeovimProgressReporter;
     type ProjectFilter = Option<gitignore::GitIgnore>;
     type ServerParams = nomad_collab_params::NomadParams;
 
-    type ConnectToServerError = NeovimConnectToServerError;
+    type ConnectToServerError = connect::ConnectToServerError;
     type DefaultDirForRemoteProjectsError = neovim::DataDirError;
+    type ListenForAgentsError = io::Error;
     type LspRootError = NeovimLspRootError;
     type ProjectFilterError = gitignore::CreateError;
+    type RecordingsDirError = neovim::DataDirError;
+    type ServeLocallyError = io::Error;
+    type SnapshotsDirError = neovim::DataDirError;
 
     async fn confirm_start(
         project_root: &AbsPath,
@@ -105,28 +119,19 @@ impl CollabEditor for Neovim {
         server_addr: config::ServerAddress<'static>,
         ctx: &mut Context<Self>,
     ) -> Result<Self::Io, Self::ConnectToServerError> {
-        let tcp_stream =
-            <TcpStream as TcpStreamExt>::connect(server_addr.clone(), ctx)
-                .await
-                .map_err(NeovimConnectToServerError::ConnectTcp)?;
-
-        // If we're connecting to a loopback address we're probably testing
-        // against a local server without TLS, so use plain TCP.
-        if let config::Host::Ip(ip) = &server_addr.host
-            && ip.is_loopback()
-        {
-            return Ok(Either::Right(tcp_stream));
-        }
+        connect::connect_to_server(server_addr, ctx).await
+    }
 
-        let tls_connector = tls_connector(ctx)
-            .await
-            .map_err(NeovimConnectToServerError::Certificates)?;
+    fn create_buffer_blame(
+        buffer_id: Self::BufferId,
+        line_authors: &[blame::LineAuthor],
+        ctx: &mut Context<Self>,
+    ) -> Self::BufferBlame {
+        let buffer = oxi::api::Buffer::from(buffer_id);
 
-        tls_connector
-            .connect(server_addr.host.into(), tcp_stream)
-            .await
-            .map(Either::Left)
-            .map_err(NeovimConnectToServerError::ConnectTls)
+        let namespace_id = ctx.with_editor(|nvim| nvim.namespace_id());
+
+        NeovimBufferBlame::create(buffer, line_authors, namespace_id)
     }
 
     fn create_peer_selection(
@@ -213,6 +218,13 @@ impl CollabEditor for Neovim {
         });
     }
 
+    fn listen_for_agents(
+        socket_path: &AbsPath,
+        _: &mut Context<Self>,
+    ) -> Result<Self::AgentListener, Self::ListenForAgentsError> {
+        agent_socket::AgentListener::bind(socket_path)
+    }
+
     fn lsp_root(
         buffer_id: BufferId,
         _: &mut Context<Self>,
@@ -261,6 +273,10 @@ impl CollabEditor for Neovim {
         handle.r#move(new_offset);
     }
 
+    fn on_blame_error(error: blame::BlameError, ctx: &mut Context<Self>) {
+        ctx.notify_error(error.to_string());
+    }
+
     fn on_copied_session_id(
         session_id: SessionId<Self>,
         ctx: &mut Context<Self>,
@@ -295,10 +311,52 @@ impl CollabEditor for Neovim {
         }
     }
 
+    fn on_follow_error(
+        error: follow::FollowError<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_error(error.to_string());
+    }
+
+    fn on_host_changed(
+        new_host: &Peer,
+        proj: &Project<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        let mut chunks = notify::Chunks::default();
+
+        if new_host.id == proj.local_peer.id {
+            chunks.push("You are");
+        } else {
+            chunks
+                .push_highlighted(
+                    new_host.handle.as_str(),
+                    notifications::PEER_HANDLE_HL_GROUP,
+                )
+                .push(" is");
+        }
+
+        chunks.push(" now the host of ").push_highlighted(
+            proj.name().as_str(),
+            notifications::PROJ_NAME_HL_GROUP,
+        );
+
+        ctx.notify_info(chunks);
+    }
+
+    fn on_host_local_error(
+        error: host_local::HostLocalError<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_error(error.to_string());
+    }
+
     fn on_init(ctx: &mut Context<Self, Borrowed>) {
         PeerCursorHighlightGroup::create_all();
         PeerHandleHighlightGroup::create_all();
+        IdlePeerHandleHighlightGroup::create_all();
         PeerSelectionHighlightGroup::create_all();
+        statusline::create_highlight_groups();
         notifications::on_init(ctx);
     }
 
@@ -310,6 +368,13 @@ impl CollabEditor for Neovim {
         ctx.notify_error(error.to_string());
     }
 
+    fn on_list_peers_error(
+        error: list_peers::ListPeersError<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_error(error.to_string());
+    }
+
     fn on_pause_error(
         error: pause::PauseError<Self>,
         ctx: &mut Context<Self>,
@@ -322,6 +387,13 @@ impl CollabEditor for Neovim {
         proj: &Project<Self>,
         ctx: &mut Context<Self>,
     ) {
+        exec_user_autocmd(
+            "NomadCollabPeerLeft",
+            proj.session_id,
+            proj.root_path(),
+            Some(peer),
+        );
+
         let mut chunks = notify::Chunks::default();
 
         chunks
@@ -343,6 +415,13 @@ impl CollabEditor for Neovim {
         proj: &Project<Self>,
         ctx: &mut Context<Self>,
     ) {
+        exec_user_autocmd(
+            "NomadCollabPeerJoined",
+            proj.session_id,
+            proj.root_path(),
+            Some(peer),
+        );
+
         let mut chunks = notify::Chunks::default();
 
         chunks
@@ -359,6 +438,13 @@ impl CollabEditor for Neovim {
         ctx.notify_info(chunks);
     }
 
+    fn on_permissions_error(
+        error: permissions::PermissionsError<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_error(error.to_string());
+    }
+
     fn on_resume_error(
         error: resume::ResumeError<Self>,
         ctx: &mut Context<Self>,
@@ -366,7 +452,31 @@ impl CollabEditor for Neovim {
         ctx.notify_error(error.to_string());
     }
 
+    fn on_session_disconnected(
+        infos: &SessionInfos<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        let mut chunks = notify::Chunks::default();
+
+        chunks
+            .push("Lost connection to the server for project ")
+            .push_highlighted(
+                infos.proj_name().as_str(),
+                notifications::PROJ_NAME_HL_GROUP,
+            )
+            .push(", reconnecting...");
+
+        ctx.notify_warn(chunks);
+    }
+
     fn on_session_ended(infos: &SessionInfos<Self>, ctx: &mut Context<Self>) {
+        exec_user_autocmd(
+            "NomadCollabSessionEnded",
+            Some(infos.session_id),
+            &infos.project_root_path,
+            None,
+        );
+
         let mut chunks = notify::Chunks::default();
 
         chunks
@@ -388,14 +498,20 @@ impl CollabEditor for Neovim {
         infos: &SessionInfos<Self>,
         ctx: &mut Context<Self>,
     ) {
-        let host = infos
-            .remote_peers
-            .get(infos.host_id)
-            .expect("session must have host peer");
+        exec_user_autocmd(
+            "NomadCollabSessionJoined",
+            Some(infos.session_id),
+            &infos.project_root_path,
+            None,
+        );
 
-        let Some((peer_handle, cursor_id)) = host
-            .main_cursor()
-            .map(|cursor_id| (host.into_inner().handle, cursor_id))
+        let Some((peer_handle, cursor_id)) = infos
+            .remote_peers
+            .get(infos.host_id())
+            .and_then(|host| {
+                host.main_cursor()
+                    .map(|cursor_id| (host.into_inner().handle, cursor_id))
+            })
             .or_else(|| {
                 infos.remote_peers.find_map(|peer| {
                     peer.main_cursor()
@@ -462,6 +578,13 @@ impl CollabEditor for Neovim {
     }
 
     fn on_session_left(infos: &SessionInfos<Self>, ctx: &mut Context<Self>) {
+        exec_user_autocmd(
+            "NomadCollabSessionLeft",
+            Some(infos.session_id),
+            &infos.project_root_path,
+            None,
+        );
+
         let mut chunks = notify::Chunks::default();
 
         chunks.push("Left session for project ").push_highlighted(
@@ -472,6 +595,20 @@ impl CollabEditor for Neovim {
         ctx.notify_info(chunks);
     }
 
+    fn on_session_reconnected(
+        infos: &SessionInfos<Self>,
+        ctx: &mut Context<Self>,
+    ) {
+        let mut chunks = notify::Chunks::default();
+
+        chunks.push("Reconnected to session for project ").push_highlighted(
+            infos.proj_name().as_str(),
+            notifications::PROJ_NAME_HL_GROUP,
+        );
+
+        ctx.notify_info(chunks);
+    }
+
     async fn on_session_started(
         infos: &SessionInfos<Self>,
         ctx: &mut Context<Self>,
@@ -484,6 +621,13 @@ impl CollabEditor for Neovim {
         // [with]: https://github.com/user-attachments/assets/031d24e9-e030-4611-872c-1b51d3076e23
         neovim::utils::schedule(|| ()).await;
 
+        exec_user_autocmd(
+            "NomadCollabSessionStarted",
+            Some(infos.session_id),
+            &infos.project_root_path,
+            None,
+        );
+
         let session_id = infos.session_id;
 
         let prompt = format!(
@@ -518,6 +662,28 @@ impl CollabEditor for Neovim {
         }
     }
 
+    fn on_stopped_following(peer: &Peer, ctx: &mut Context<Self>) {
+        let mut chunks = notify::Chunks::default();
+
+        chunks.push("Stopped following ").push_highlighted(
+            peer.handle.as_str(),
+            notifications::PEER_HANDLE_HL_GROUP,
+        );
+
+        ctx.notify_info(chunks);
+    }
+
+    fn on_undo_error(error: undo::UndoError, ctx: &mut Context<Self>) {
+        ctx.notify_error(error.to_string());
+    }
+
+    fn on_unfollow_error(
+        error: unfollow::UnfollowError,
+        ctx: &mut Context<Self>,
+    ) {
+        ctx.notify_error(error.to_string());
+    }
+
     fn project_filter(
         project_root: &<Self::Fs as fs::Fs>::Directory,
         ctx: &mut Context<Self>,
@@ -542,6 +708,21 @@ impl CollabEditor for Neovim {
         }
     }
 
+    async fn recordings_dir(
+        ctx: &mut Context<Self>,
+    ) -> Result<AbsPathBuf, Self::RecordingsDirError> {
+        let data_dir_path = ctx.with_editor(|nvim| nvim.data_dir_path())?;
+
+        Ok(data_dir_path
+            .join(node!("nomad"))
+            .join(node!("collab"))
+            .join(node!("recordings")))
+    }
+
+    fn remove_buffer_blame(blame: Self::BufferBlame, _: &mut Context<Self>) {
+        blame.remove();
+    }
+
     fn remove_peer_selection(
         selection: Self::PeerSelection,
         _ctx: &mut Context<Self>,
@@ -557,6 +738,64 @@ impl CollabEditor for Neovim {
         handle.remove();
     }
 
+    fn render_statusline(statuses: &[SessionStatus]) -> String {
+        statusline::render(statuses)
+    }
+
+    async fn select_peer<'peers>(
+        peers: &'peers [list_peers::PeerInfos],
+        ctx: &mut Context<Self>,
+    ) -> Option<&'peers list_peers::PeerInfos> {
+        let select = get_lua_value::<Function>(&["vim", "ui", "select"])?;
+
+        let items = {
+            let t = mlua::lua().create_table().ok()?;
+            for (idx, peer) in peers.iter().enumerate() {
+                let mut item = match &peer.position {
+                    Some(pos) => {
+                        let path =
+                            notifications::path_chunk(&pos.file_path, ctx);
+                        format!(
+                            "{} at {}:{}",
+                            peer.handle,
+                            path.text(),
+                            pos.line_idx + 1
+                        )
+                    },
+                    None => peer.handle.to_string(),
+                };
+                if peer.activity != PeerActivity::Active {
+                    let idle_mins = peer.idle_time.as_secs() / 60;
+                    item.push_str(&format!(" (idle for {idle_mins}m)"));
+                }
+                t.raw_set(idx + 1, item).ok()?;
+            }
+            t
+        };
+
+        let opts = {
+            let t = mlua::lua().create_table().ok()?;
+            t.raw_set("prompt", "Choose the peer to jump to:").ok()?;
+            t
+        };
+
+        let (idx_tx, idx_rx) = flume::bounded(1);
+
+        let on_choice = mlua::lua()
+            .create_function(
+                move |_, (_, lua_idx): (mlua::Value, Option<usize>)| {
+                    let idx = lua_idx.map(|idx| idx - 1);
+                    let _ = idx_tx.send(idx);
+                    Ok(())
+                },
+            )
+            .ok()?;
+
+        select.call::<()>((items, opts, on_choice)).ok()?;
+
+        idx_rx.recv_async().await.ok()?.and_then(|idx| peers.get(idx))
+    }
+
     async fn select_session<'pairs>(
         sessions: &'pairs [(AbsPathBuf, SessionId<Self>)],
         action: ActionForSelectedSession,
@@ -577,6 +816,8 @@ impl CollabEditor for Neovim {
             ActionForSelectedSession::CopySessionId => "copy the ID of",
             ActionForSelectedSession::Leave => "leave",
             ActionForSelectedSession::Pause => "pause",
+            ActionForSelectedSession::ReadOnly => "make read-only",
+            ActionForSelectedSession::ReadWrite => "make read-write",
             ActionForSelectedSession::Resume => "resume",
         };
 
@@ -609,9 +850,62 @@ impl CollabEditor for Neovim {
             .and_then(|idx| sessions.get(idx as usize))
     }
 
+    async fn serve_locally(
+        listen_addr: SocketAddr,
+        ctx: &mut Context<Self>,
+    ) -> Result<host_local::LocalServer, Self::ServeLocallyError> {
+        local_server::serve(listen_addr, ctx).await
+    }
+
+    fn set_buffer_read_only(
+        buffer_id: Self::BufferId,
+        read_only: bool,
+        _: &mut Context<Self>,
+    ) {
+        let buffer = oxi::api::Buffer::from(buffer_id);
+
+        let opts = oxi::api::opts::OptionOpts::builder().buf(buffer).build();
+
+        if let Err(err) =
+            oxi::api::set_option_value("modifiable", !read_only, &opts)
+        {
+            tracing::error!("couldn't set 'modifiable' on buffer: {err}");
+        }
+    }
+
+    fn set_peer_tooltip_activity(
+        (_, handle): &mut Self::PeerTooltip,
+        activity: PeerActivity,
+        _: &mut Context<Self>,
+    ) {
+        handle.set_activity(activity);
+    }
+
+    fn should_remote_edit_create_buffer(
+        _: &AbsPath,
+        _: &mut Context<Self>,
+    ) -> bool {
+        true
+    }
+
     fn should_remote_save_cause_local_save(_: &Self::Buffer<'_>) -> bool {
         true
     }
+
+    async fn sleep(duration: Duration, _: &mut Context<Self>) {
+        async_io::Timer::after(duration).await;
+    }
+
+    async fn snapshots_dir(
+        ctx: &mut Context<Self>,
+    ) -> Result<AbsPathBuf, Self::SnapshotsDirError> {
+        let data_dir_path = ctx.with_editor(|nvim| nvim.data_dir_path())?;
+
+        Ok(data_dir_path
+            .join(node!("nomad"))
+            .join(node!("collab"))
+            .join(node!("snapshots")))
+    }
 }
 
 #[track_caller]
@@ -630,24 +924,43 @@ fn get_lua_value<T: mlua::FromLua>(namespace: &[&str]) -> Option<T> {
     }
 }
 
-async fn tls_connector(
-    ctx: &mut Context<impl Editor>,
-) -> Result<&TlsConnector, rustls::Error> {
-    static TLS_CONNECTOR: OnceLock<TlsConnector> = OnceLock::new();
+/// Fires a `User` autocommand with the given pattern, passing the ID of the
+/// session, the root of its project and the given peer (if any) as the
+/// autocommand's `data`.
+fn exec_user_autocmd(
+    pattern: &str,
+    session_id: Option<SessionId<Neovim>>,
+    project_root: &AbsPath,
+    peer: Option<&Peer>,
+) {
+    let try_block = || -> mlua::Result<()> {
+        let Some(exec_autocmds) =
+            get_lua_value::<Function>(&["vim", "api", "nvim_exec_autocmds"])
+        else {
+            return Ok(());
+        };
 
-    if let Some(connector) = TLS_CONNECTOR.get() {
-        return Ok(connector);
-    }
+        let lua = mlua::lua();
 
-    // Getting the certificates from the OS blocks, so we do it in a
-    // background thread.
-    let client_config = ctx
-        .spawn_background(async {
-            use rustls_platform_verifier::ConfigVerifierExt;
-            rustls::ClientConfig::with_platform_verifier()
-        })
-        .await?;
+        let data = lua.create_table()?;
+        if let Some(session_id) = session_id {
+            data.raw_set("session_id", session_id.to_string())?;
+        }
+        data.raw_set("project_root", project_root.as_str())?;
+        if let Some(peer) = peer {
+            data.raw_set("peer_handle", peer.handle.as_str())?;
+            data.raw_set("peer_id", peer.id.into_u64())?;
+        }
+
+        let opts = lua.create_table()?;
+        opts.raw_set("pattern", pattern)?;
+        opts.raw_set("data", data)?;
+        opts.raw_set("modeline", false)?;
 
-    Ok(TLS_CONNECTOR
-        .get_or_init(|| TlsConnector::from(Arc::new(client_config))))
+        exec_autocmds.call::<()>(("User", opts))
+    };
+
+    if let Err(err) = try_block() {
+        tracing::error!("couldn't fire the {pattern} autocommand: {err}");
+    }
 }
@@ -1,11 +1,12 @@
 use core::cell::{Cell, LazyCell};
 
-use collab_types::{Peer, PeerHandle};
+use collab_types::{Peer, PeerHandle, PeerId};
 use editor::ByteOffset;
 use neovim::buffer::BufferExt;
 use neovim::oxi::api;
 
 use crate::editors::neovim::{PeerCursorHighlightGroup, PeerHighlightGroup};
+use crate::peers::PeerActivity;
 
 thread_local! {
     /// The highlight group ID of the `Normal` highlight group.
@@ -21,6 +22,9 @@ pub struct NeovimPeerHandle {
     /// The buffer the cursor is in.
     buffer: api::Buffer,
 
+    /// The byte offset of the cursor the handle is displayed next to.
+    cursor_offset: ByteOffset,
+
     /// The ID of the extmark used to display the handle.
     extmark_id: u32,
 
@@ -33,11 +37,18 @@ pub struct NeovimPeerHandle {
 
     /// The remote peer's handle.
     peer_handle: PeerHandle,
+
+    /// The remote peer's ID.
+    peer_id: PeerId,
 }
 
 /// The highlight group used to highlight a remote peer's handle.
 pub(super) struct PeerHandleHighlightGroup;
 
+/// The highlight group used to highlight the handle of a remote peer that's
+/// [`Idle`](PeerActivity::Idle) or [`Away`](PeerActivity::Away).
+pub(super) struct IdlePeerHandleHighlightGroup;
+
 impl PeerHandleHighlightGroup {
     thread_local! {
         static GROUP_IDS: Cell<[u32; PeerCursorHighlightGroup::NUM_HIGHLIGHTS]>
@@ -45,6 +56,13 @@ impl PeerHandleHighlightGroup {
     }
 }
 
+impl IdlePeerHandleHighlightGroup {
+    thread_local! {
+        static GROUP_IDS: Cell<[u32; PeerCursorHighlightGroup::NUM_HIGHLIGHTS]>
+            = const { Cell::new([0; _]) };
+    }
+}
+
 impl NeovimPeerHandle {
     /// Creates a new handle for the given remote peer to be displayed above or
     /// below the cursor at the given offset in the given buffer.
@@ -69,15 +87,19 @@ impl NeovimPeerHandle {
 
         Self {
             buffer,
+            cursor_offset,
             extmark_id,
             hl_group_id,
             namespace_id,
             peer_handle: peer.handle,
+            peer_id: peer.id,
         }
     }
 
     /// Moves the handle to keep it in sync with the new cursor offset.
     pub(super) fn r#move(&mut self, new_cursor_offset: ByteOffset) {
+        self.cursor_offset = new_cursor_offset;
+
         let (line, mut opts_builder) = Self::extmark_params(
             self.buffer.clone(),
             new_cursor_offset,
@@ -95,6 +117,23 @@ impl NeovimPeerHandle {
         debug_assert_eq!(new_extmark_id, self.extmark_id);
     }
 
+    /// Dims the handle if the peer is not [`Active`](PeerActivity::Active).
+    pub(super) fn set_activity(&mut self, activity: PeerActivity) {
+        let hl_group_id = match activity {
+            PeerActivity::Active => {
+                PeerHandleHighlightGroup::group_id(self.peer_id)
+            },
+            PeerActivity::Idle | PeerActivity::Away => {
+                IdlePeerHandleHighlightGroup::group_id(self.peer_id)
+            },
+        };
+
+        if hl_group_id != self.hl_group_id {
+            self.hl_group_id = hl_group_id;
+            self.r#move(self.cursor_offset);
+        }
+    }
+
     /// Removes the handle from the buffer.
     pub(super) fn remove(mut self) {
         self.buffer
@@ -179,3 +218,15 @@ impl PeerHighlightGroup for PeerHandleHighlightGroup {
         Self::GROUP_IDS.with(|ids| fun(ids.as_array_of_cells().as_slice()))
     }
 }
+
+impl PeerHighlightGroup for IdlePeerHandleHighlightGroup {
+    const NAME_PREFIX: &str = "NomadCollabPeerHandleIdle";
+
+    fn set_hl_opts() -> api::opts::SetHighlightOpts {
+        api::opts::SetHighlightOpts::builder().link("Pmenu").build()
+    }
+
+    fn with_group_ids<R>(fun: impl FnOnce(&[Cell<u32>]) -> R) -> R {
+        Self::GROUP_IDS.with(|ids| fun(ids.as_array_of_cells().as_slice()))
+    }
+}
@@ -0,0 +1,70 @@
+//! Contains the functions used to render the [`Statusline`] function's
+//! output using Neovim's `'statusline'` syntax.
+//!
+//! [`Statusline`]: crate::statusline::Statusline
+
+use core::fmt::Write;
+
+use neovim::oxi::api;
+
+use crate::statusline::SessionStatus;
+
+/// The name and default link of the highlight group used for sessions that
+/// are connected and not paused.
+const CONNECTED_HL_GROUP: (&str, &str) =
+    ("NomadCollabStatusline", "DiagnosticOk");
+
+/// The name and default link of the highlight group used for sessions that
+/// are paused.
+const PAUSED_HL_GROUP: (&str, &str) =
+    ("NomadCollabStatuslinePaused", "DiagnosticWarn");
+
+/// The name and default link of the highlight group used for sessions that
+/// are trying to reconnect to the server.
+const RECONNECTING_HL_GROUP: (&str, &str) =
+    ("NomadCollabStatuslineReconnecting", "DiagnosticError");
+
+/// Creates the highlight groups used in the statusline, linking them to
+/// their default groups unless the user has already defined them.
+pub(super) fn create_highlight_groups() {
+    for (name, link) in
+        [CONNECTED_HL_GROUP, PAUSED_HL_GROUP, RECONNECTING_HL_GROUP]
+    {
+        if let Ok(id) = api::call_function::<_, u32>("hlID", (name,))
+            && id != 0
+        {
+            continue;
+        }
+
+        let opts = api::opts::SetHighlightOpts::builder().link(link).build();
+
+        api::set_hl(0, name, &opts).expect("couldn't create highlight group");
+    }
+}
+
+/// Renders the given session statuses as a `'statusline'` item, highlighting
+/// each one based on its connection and pause state.
+pub(super) fn render(statuses: &[SessionStatus]) -> String {
+    let mut rendered = String::new();
+
+    for (idx, status) in statuses.iter().enumerate() {
+        let (hl_group, _) = if !status.is_connected {
+            RECONNECTING_HL_GROUP
+        } else if status.is_paused {
+            PAUSED_HL_GROUP
+        } else {
+            CONNECTED_HL_GROUP
+        };
+
+        if idx > 0 {
+            rendered.push(' ');
+        }
+
+        // '%' starts an item in the statusline, so it has to be escaped.
+        let text = status.to_string().replace('%', "%%");
+
+        let _ = write!(rendered, "%#{hl_group}#{text}%*");
+    }
+
+    rendered
+}
@@ -3,7 +3,7 @@
 use core::ops::Range;
 
 use abs_path::AbsPathBuf;
-use editor::{ByteOffset, Editor, Replacement};
+use editor::{AgentId, ByteOffset, Editor, Replacement};
 use fs::{DirectoryEvent, FileEvent};
 use smallvec::SmallVec;
 
@@ -46,6 +46,9 @@ pub enum BufferEvent<Ed: Editor> {
 /// TODO: docs.
 #[derive(cauchy::Debug)]
 pub struct CursorEvent<Ed: Editor> {
+    /// The ID of the agent that caused the event.
+    pub agent_id: AgentId,
+
     /// TODO: docs.
     pub cursor_id: Ed::CursorId,
 
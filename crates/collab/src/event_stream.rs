@@ -4,7 +4,7 @@ use abs_path::AbsPathBuf;
 use editor::context::{Buffer, Context, Cursor, EventHandle, Selection};
 use editor::{AgentId, Buffer as _, Cursor as _, Selection as _, Shared};
 use either::Either;
-use fs::filter::Filter;
+use fs::filter::{And, Filter};
 use fs::{Directory, File, Fs};
 use futures_util::future::FusedFuture;
 use futures_util::select_biased;
@@ -15,6 +15,7 @@ use rand::Rng;
 use crate::editors::CollabEditor;
 use crate::event::{self, Event};
 use crate::list_ext::List;
+use crate::nomadignore::NomadIgnore;
 use crate::start::{AllButOne, ProjectFilter};
 
 type FxIndexMap<K, V> = indexmap::IndexMap<K, V, FxBuildHasher>;
@@ -454,7 +455,7 @@ impl<Ed: CollabEditor> EventStream<Ed> {
             .await
             .map(|should_filter| !should_filter)
             .map_err(|err| match err {
-                Either::Left(err) => EventError::Filter(err),
+                Either::Left(Either::Left(err)) => EventError::Filter(err),
             })
     }
 
@@ -509,7 +510,8 @@ impl<Fs: fs::Fs> EventStreamBuilder<Fs, NeedsProjectFilter> {
     }
 }
 
-impl<Fs, Fi> EventStreamBuilder<Fs, Done<Either<Fi, AllButOne<Fs>>>>
+impl<Fs, Fi>
+    EventStreamBuilder<Fs, Done<Either<And<Fi, NomadIgnore>, AllButOne<Fs>>>>
 where
     Fs: fs::Fs,
     Fi: Filter<Fs>,
@@ -631,8 +633,9 @@ impl<Ed: CollabEditor> CursorStreams<Ed> {
         };
 
         let event_tx = self.event_tx.clone();
-        let moved_handle = cursor.on_moved(move |cursor, _moved_by| {
+        let moved_handle = cursor.on_moved(move |cursor, moved_by| {
             let _ = event_tx.send(event::CursorEvent {
+                agent_id: moved_by,
                 cursor_id: cursor.id(),
                 kind: event::CursorEventKind::Moved(cursor.byte_offset()),
             });
@@ -640,8 +643,9 @@ impl<Ed: CollabEditor> CursorStreams<Ed> {
 
         let event_tx = self.event_tx.clone();
         let removed_handle =
-            cursor.on_removed(move |cursor_id, _removed_by| {
+            cursor.on_removed(move |cursor_id, removed_by| {
                 let event = event::CursorEvent {
+                    agent_id: removed_by,
                     cursor_id,
                     kind: event::CursorEventKind::Removed,
                 };
@@ -656,8 +660,9 @@ impl<Ed: CollabEditor> CursorStreams<Ed> {
 
         let new_cursors_handle = {
             let event_tx = event_tx.clone();
-            ctx.on_cursor_created(move |cursor, _created_by| {
+            ctx.on_cursor_created(move |cursor, created_by| {
                 let _ = event_tx.send(event::CursorEvent {
+                    agent_id: created_by,
                     cursor_id: cursor.id(),
                     kind: event::CursorEventKind::Created(
                         cursor.buffer_id(),
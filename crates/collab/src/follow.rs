@@ -0,0 +1,106 @@
+//! TODO: docs.
+
+use collab_types::{GitHubHandle, PeerHandle};
+use editor::Context;
+use editor::command::{self, ToCompletionFn};
+use editor::module::AsyncAction;
+
+use crate::collab::Collab;
+use crate::editors::CollabEditor;
+use crate::jump::{self, Jump, JumpToCursorError};
+use crate::session::Sessions;
+
+/// The `Action` used to start following a remote peer, i.e. to continuously
+/// jump to the position of their main cursor every time it moves.
+///
+/// Following automatically stops when the local user moves their own cursor,
+/// or via the [`Unfollow`](crate::unfollow::Unfollow) action.
+#[derive(cauchy::Clone)]
+pub struct Follow<Ed: CollabEditor> {
+    sessions: Sessions<Ed>,
+}
+
+impl<Ed: CollabEditor> Follow<Ed> {
+    pub(crate) async fn call_inner(
+        &self,
+        peer_handle: PeerHandle,
+    ) -> Result<(), FollowError<Ed>> {
+        let mut maybe_peer = None;
+
+        let Some(sesh) = self.sessions.find(|sesh| {
+            maybe_peer =
+                sesh.remote_peers.find(|peer| peer.handle == peer_handle);
+            maybe_peer.is_some()
+        }) else {
+            return Err(FollowError::UnknownPeer(peer_handle));
+        };
+
+        let peer = maybe_peer.expect("session was found");
+
+        // We can only follow one peer at a time, so stop following anyone in
+        // the other sessions.
+        self.sessions.for_each(|infos| {
+            if infos.session_id != sesh.session_id {
+                infos.remote_peers.set_followed(None);
+            }
+        });
+
+        sesh.remote_peers.set_followed(Some(peer.id));
+
+        // The peer may not have a cursor in the project yet, in which case
+        // we'll jump to it as soon as it's created.
+        let Some(cursor_id) = peer.main_cursor() else { return Ok(()) };
+
+        sesh.project_access
+            .with(async move |proj, ctx| {
+                Jump::jump_to(proj, cursor_id, ctx).await
+            })
+            .await
+            .ok_or(FollowError::UnknownPeer(peer_handle))?
+            .map_err(FollowError::Jump)
+    }
+}
+
+impl<Ed: CollabEditor> AsyncAction<Ed> for Follow<Ed> {
+    const NAME: &str = "follow";
+
+    type Args = command::Parse<GitHubHandle>;
+
+    async fn call(
+        &mut self,
+        command::Parse(github_handle): Self::Args,
+        ctx: &mut Context<Ed>,
+    ) {
+        if let Err(err) =
+            self.call_inner(PeerHandle::GitHub(github_handle)).await
+        {
+            Ed::on_follow_error(err, ctx);
+        }
+    }
+}
+
+/// The type of error that can occur when [`Follow`]ing fails.
+#[derive(
+    cauchy::Debug, derive_more::Display, cauchy::Error, cauchy::PartialEq,
+)]
+pub enum FollowError<Ed: CollabEditor> {
+    /// Jumping to the followed peer's cursor failed.
+    #[display("{_0}")]
+    Jump(JumpToCursorError<Ed>),
+
+    /// There's no peer with the given handle in any of the sessions.
+    #[display("There's no peer with handle '{_0}' in any of the sessions")]
+    UnknownPeer(PeerHandle),
+}
+
+impl<Ed: CollabEditor> From<&Collab<Ed>> for Follow<Ed> {
+    fn from(collab: &Collab<Ed>) -> Self {
+        Self { sessions: collab.sessions.clone() }
+    }
+}
+
+impl<Ed: CollabEditor> ToCompletionFn<Ed> for Follow<Ed> {
+    fn to_completion_fn(&self) -> impl command::CompletionFn + 'static {
+        jump::complete_peer_handles(self.sessions.clone())
+    }
+}
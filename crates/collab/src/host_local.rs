@@ -0,0 +1,155 @@
+//! TODO: docs.
+
+use core::convert::Infallible;
+use core::net::{Ipv4Addr, SocketAddr};
+use core::str::FromStr;
+
+use editor::Context;
+use editor::command::{self, ToCompletionFn};
+use editor::module::AsyncAction;
+
+use crate::collab::Collab;
+use crate::config::ServerAddress;
+use crate::editors::CollabEditor;
+use crate::progress::ProgressReporter;
+use crate::session::SessionInfos;
+use crate::start::{Start, StartError};
+
+/// The `Action` used to start a new collaborative editing session on a collab
+/// server running inside the editor's process, for when neither the default
+/// server nor a self-hosted one can be reached (e.g. on air-gapped networks).
+///
+/// The server only listens on the loopback interface unless it's given an
+/// explicit `{ip}:{port}` address, in which case the other peers can join the
+/// session by setting their server address to `tcp://{ip}:{port}`.
+#[derive(cauchy::Clone)]
+pub struct HostLocal<Ed: CollabEditor> {
+    start: Start<Ed>,
+}
+
+impl<Ed: CollabEditor> HostLocal<Ed> {
+    pub(crate) async fn call_inner(
+        &self,
+        listen_addr: ListenAddress,
+        progress_reporter: &mut impl ProgressReporter<Ed, Start<Ed>>,
+        ctx: &mut Context<Ed>,
+    ) -> Result<SessionInfos<Ed>, HostLocalError<Ed>> {
+        let local_server = Ed::serve_locally(listen_addr.0, ctx)
+            .await
+            .map_err(HostLocalError::ServeLocally)?;
+
+        self.start
+            .call_with_server(
+                local_server.address(),
+                Some(local_server),
+                progress_reporter,
+                ctx,
+            )
+            .await
+            .map_err(HostLocalError::Start)
+    }
+}
+
+impl<Ed: CollabEditor> AsyncAction<Ed> for HostLocal<Ed> {
+    const NAME: &str = "host-local";
+
+    type Args = command::Parse<ListenAddress>;
+
+    async fn call(
+        &mut self,
+        command::Parse(listen_addr): Self::Args,
+        ctx: &mut Context<Ed>,
+    ) {
+        let mut progress_reporter =
+            <Ed::ProgressReporter as ProgressReporter<Ed, Start<Ed>>>::new(
+                ctx,
+            );
+
+        let result = match self
+            .call_inner(listen_addr, &mut progress_reporter, ctx)
+            .await
+        {
+            Ok(session_infos) => Ok(session_infos),
+            Err(HostLocalError::Start(err)) => Err(err),
+            Err(err @ HostLocalError::ServeLocally(_)) => {
+                ProgressReporter::<Ed, Start<Ed>>::report_cancellation(
+                    progress_reporter,
+                    ctx,
+                );
+                Ed::on_host_local_error(err, ctx);
+                return;
+            },
+        };
+
+        Start::report(result, progress_reporter, ctx).await;
+    }
+}
+
+/// A collab server running inside the editor's process, started by
+/// [`CollabEditor::serve_locally`].
+///
+/// The server is shut down when this is dropped, which happens when the
+/// session hosted on it ends.
+pub struct LocalServer {
+    address: ServerAddress<'static>,
+    _stop_tx: flume::Sender<Infallible>,
+}
+
+/// The address the local collab server listens on, parsed from either a
+/// `{port}`, which only accepts connections from the local machine, or an
+/// `{ip}:{port}`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ListenAddress(SocketAddr);
+
+/// The type of error that can occur when [`HostLocal`] fails.
+#[derive(
+    cauchy::Debug, derive_more::Display, cauchy::Error, cauchy::PartialEq,
+)]
+pub enum HostLocalError<Ed: CollabEditor> {
+    /// Starting the local server failed.
+    #[display("Couldn't start local server: {_0}")]
+    ServeLocally(Ed::ServeLocallyError),
+
+    /// Starting the session on the local server failed.
+    #[display("{_0}")]
+    Start(StartError<Ed>),
+}
+
+impl LocalServer {
+    /// Returns the address the local peer should use to connect to the
+    /// server.
+    pub fn address(&self) -> ServerAddress<'static> {
+        self.address.clone()
+    }
+
+    /// Creates a new `LocalServer` reachable at the given address, together
+    /// with a receiver that's disconnected when the `LocalServer` is dropped,
+    /// which the server should listen to to know when to shut down.
+    pub fn new(
+        address: ServerAddress<'static>,
+    ) -> (Self, flume::Receiver<Infallible>) {
+        let (stop_tx, stop_rx) = flume::bounded(1);
+        (Self { address, _stop_tx: stop_tx }, stop_rx)
+    }
+}
+
+impl FromStr for ListenAddress {
+    type Err = <SocketAddr as FromStr>::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<u16>() {
+            Ok(port) => Ok(Self((Ipv4Addr::LOCALHOST, port).into())),
+            Err(_) => s.parse().map(Self),
+        }
+    }
+}
+
+impl<Ed: CollabEditor> From<&Collab<Ed>> for HostLocal<Ed> {
+    fn from(collab: &Collab<Ed>) -> Self {
+        Self { start: Start::from(collab) }
+    }
+}
+
+impl<Ed: CollabEditor> ToCompletionFn<Ed> for HostLocal<Ed> {
+    fn to_completion_fn(&self) {}
+}
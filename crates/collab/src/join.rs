@@ -20,21 +20,27 @@ use editor::module::{AsyncAction, Module};
 use editor::shared::{MultiThreaded, Shared};
 use editor::{Access, Context};
 use either::Either;
+use fs::filter::Filter as _;
 use fs::{Directory, File, Fs, Symlink};
 use futures_util::{AsyncReadExt, SinkExt, StreamExt, future, stream};
 use fxhash::FxHashMap;
 use puff::directory::LocalDirectoryId;
 use puff::file::LocalFileId;
 
+use crate::agents::Agents;
 use crate::collab::Collab;
 use crate::config::Config;
 use crate::editors::{CollabEditor, SessionId, Welcome};
 use crate::event_stream::EventStreamBuilder;
+use crate::nomadignore::NomadIgnore;
 use crate::pausable_stream::PausableStream;
 use crate::peers::RemotePeers;
+use crate::permissions::Permissions;
 use crate::progress::{JoinState, ProgressReporter};
 use crate::project::{self, IdMaps};
+use crate::reconnect::Reconnector;
 use crate::session::{Session, SessionInfos, Sessions};
+use crate::undo::UndoHistory;
 
 /// The `Action` used to join an existing collaborative editing session.
 #[derive(cauchy::Clone)]
@@ -64,13 +70,13 @@ impl<Ed: CollabEditor> Join<Ed> {
             ctx,
         );
 
-        let (reader, writer) = Ed::connect_to_server(server_addr, ctx)
+        let (reader, writer) = Ed::connect_to_server(server_addr.clone(), ctx)
             .await
             .map_err(JoinError::ConnectToServer)?
             .split();
 
         let knock = client::Knock::<Ed::ServerParams> {
-            auth_infos: jwt.into(),
+            auth_infos: jwt.clone().into(),
             session_intent: client::SessionIntent::JoinExisting(session_id),
         };
 
@@ -82,6 +88,12 @@ impl<Ed: CollabEditor> Join<Ed> {
 
         let local_peer = welcome.peer.clone();
 
+        let host_id = project::current_host_id(
+            welcome.host_id,
+            welcome.other_peers.as_slice(),
+            &local_peer,
+        );
+
         let project_root = match self
             .config
             .with(|c| c.store_remote_projects_under.clone())
@@ -100,6 +112,7 @@ impl<Ed: CollabEditor> Join<Ed> {
 
         let (project, buffered) = request_project::<Ed>(
             local_peer.id,
+            host_id,
             &mut welcome,
             progress_reporter,
             ctx,
@@ -120,33 +133,62 @@ impl<Ed: CollabEditor> Join<Ed> {
         let project_filter = Ed::project_filter(&project_root, ctx)
             .map_err(JoinError::ProjectFilter)?;
 
+        let exclude = self.config.with(|c| c.exclude.clone());
+
+        let nomadignore =
+            NomadIgnore::read(&ctx.fs(), project_root.path(), &exclude)
+                .await
+                .map_err(JoinError::NomadIgnore)?;
+
         let event_stream = stream_builder
-            .push_filter(Either::Left(project_filter))
+            .push_filter(Either::Left(project_filter.and(nomadignore)))
             .build(ctx);
 
         let remote_peers = RemotePeers::new(welcome.other_peers, &project);
 
+        let host_id = Shared::new(host_id);
+
+        let permissions = Shared::<Permissions>::default();
+
         let project = project::Project {
             agent_id: event_stream.agent_id(),
+            blames: FxHashMap::default(),
+            host_id: host_id.clone(),
             id_maps: id_maps.into(),
             inner: project,
             local_peer: local_peer.clone(),
             peer_cursors: FxHashMap::default(),
             peer_selections: FxHashMap::default(),
+            permissions: permissions.clone(),
             remote_peers: remote_peers.clone(),
             root_path: project_root.path().to_owned(),
+            session_id: Some(welcome.session_id),
+            undo_history: UndoHistory::default(),
         };
 
         let message_rx = PausableStream::new(
             stream::iter(buffered).map(Ok).chain(welcome.rx),
         );
 
+        let reconnector = Reconnector::new(
+            jwt,
+            server_addr,
+            welcome.session_id,
+            |welcome| {
+                let rx = stream::iter(Vec::new()).map(Ok).chain(welcome.rx);
+                (welcome.tx, rx)
+            },
+        );
+
         let (stop_tx, stop_rx) = flume::bounded(1);
 
         let session_infos = SessionInfos {
-            host_id: welcome.host_id,
+            files: Default::default(),
+            host_id,
+            is_connected: Shared::new(true),
             local_peer,
             remote_peers,
+            permissions,
             pause_remote: message_rx.remote(),
             project_access: Default::default(),
             project_root_path: project_root.path().to_owned(),
@@ -155,12 +197,17 @@ impl<Ed: CollabEditor> Join<Ed> {
         };
 
         let session = Session {
+            agents: Agents::new(self.config.with(|c| c.agent_socket.clone())),
             event_stream,
+            local_server: None,
             message_rx,
             message_tx: welcome.tx,
             project,
             project_access: session_infos.project_access.clone(),
+            record: self.config.with(|c| c.record_sessions),
+            save_snapshots: false,
             stop_rx,
+            reconnector,
             remove_on_drop: self.sessions.insert(session_infos.clone()),
         };
 
@@ -225,21 +272,14 @@ impl<Ed: CollabEditor> ToCompletionFn<Ed> for Join<Ed> {
 /// TODO: docs.
 async fn request_project<Ed: CollabEditor>(
     local_id: PeerId,
+    host_id: PeerId,
     welcome: &mut Welcome<Ed>,
     progress_reporter: &mut impl ProgressReporter<Ed, Join<Ed>>,
     ctx: &mut Context<Ed>,
 ) -> Result<(Project, Vec<MessageFragment>), RequestProjectError> {
     let request_id = MessageId { sender_id: local_id, message_seq: 0 };
 
-    let request = ProjectRequest {
-        request_from: welcome
-            .other_peers
-            .as_slice()
-            .first()
-            .expect("can't be empty")
-            .id,
-        request_id,
-    };
+    let request = ProjectRequest { request_from: host_id, request_id };
 
     welcome.tx.send(Message::ProjectRequest(request)).await?;
 
@@ -278,8 +318,9 @@ async fn request_project<Ed: CollabEditor>(
     }
 }
 
-/// TODO: docs.
-async fn write_project<Ed: CollabEditor>(
+/// Writes the given project to disk under the given root, replacing any
+/// node that's already there.
+pub(crate) async fn write_project<Ed: CollabEditor>(
     project: &Project,
     root_path: AbsPathBuf,
     ctx: &mut Context<Ed>,
@@ -438,6 +479,10 @@ pub enum JoinError<Ed: CollabEditor> {
     /// TODO: docs.
     Knock(client::KnockError<Ed::ServerParams>),
 
+    /// The project's `.nomadignore` file couldn't be read.
+    #[display("Couldn't read .nomadignore: {_0}")]
+    NomadIgnore(fs::ReadFileToStringError<Ed::Fs>),
+
     /// The project filter couldn't be created.
     ProjectFilter(Ed::ProjectFilterError),
 
@@ -84,7 +84,9 @@ impl<Ed: CollabEditor> Jump<Ed> {
         Ok(())
     }
 
-    fn agent_id(ctx: &mut Context<Ed>) -> AgentId {
+    /// Returns the [`AgentId`] used to move the local user's cursor when
+    /// jumping to a remote peer.
+    pub(crate) fn agent_id(ctx: &mut Context<Ed>) -> AgentId {
         thread_local! {
             static AGENT_ID: OnceCell<AgentId> = const { OnceCell::new() };
         }
@@ -156,31 +158,7 @@ impl<Ed: CollabEditor> From<&Collab<Ed>> for Jump<Ed> {
 
 impl<Ed: CollabEditor> command::ToCompletionFn<Ed> for Jump<Ed> {
     fn to_completion_fn(&self) -> impl command::CompletionFn + 'static {
-        let sessions = self.sessions.clone();
-
-        move |command_args: CommandArgs<'_, ByteOffset>| {
-            let mut completions = SmallVec::<[_; 2]>::new();
-
-            let handle_prefix = match command_args.cursor_pos() {
-                CursorPosition::InArg(arg, offset) if arg.is_first() => {
-                    &arg.as_str()[..offset]
-                },
-                CursorPosition::BetweenArgs(prev, _) if prev.is_none() => "",
-                _ => return completions,
-            };
-
-            sessions.for_each(|session_infos| {
-                session_infos.remote_peers.for_each(|peer| {
-                    if peer.handle.as_str().starts_with(handle_prefix) {
-                        completions.push(CommandCompletion::new(
-                            peer.handle.as_str(),
-                        ));
-                    }
-                })
-            });
-
-            completions
-        }
+        complete_peer_handles(self.sessions.clone())
     }
 }
 
@@ -189,3 +167,32 @@ impl<Ed: CollabEditor> From<NoActiveSessionError> for JumpError<Ed> {
         Self::NoActiveSession
     }
 }
+
+/// Returns a [`CompletionFn`](command::CompletionFn) that completes the
+/// handles of the remote peers in any of the given sessions.
+pub(crate) fn complete_peer_handles<Ed: CollabEditor>(
+    sessions: Sessions<Ed>,
+) -> impl command::CompletionFn + 'static {
+    move |command_args: CommandArgs<'_, ByteOffset>| {
+        let mut completions = SmallVec::<[_; 2]>::new();
+
+        let handle_prefix = match command_args.cursor_pos() {
+            CursorPosition::InArg(arg, offset) if arg.is_first() => {
+                &arg.as_str()[..offset]
+            },
+            CursorPosition::BetweenArgs(prev, _) if prev.is_none() => "",
+            _ => return completions,
+        };
+
+        sessions.for_each(|session_infos| {
+            session_infos.remote_peers.for_each(|peer| {
+                if peer.handle.as_str().starts_with(handle_prefix) {
+                    completions
+                        .push(CommandCompletion::new(peer.handle.as_str()));
+                }
+            })
+        });
+
+        completions
+    }
+}
@@ -2,28 +2,53 @@
 
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 
+#[cfg(any(feature = "headless", feature = "neovim"))]
+pub mod agent_socket;
+pub mod agents;
+pub mod blame;
 mod collab;
 pub mod config;
+#[cfg(any(feature = "headless", feature = "neovim"))]
+pub mod connect;
 mod convert;
 pub mod copy_id;
 pub mod editors;
 pub mod event;
 mod event_stream;
+pub mod follow;
+pub mod host_local;
 pub mod join;
 pub mod jump;
 pub mod leave;
 mod list_ext;
+pub mod list_peers;
+#[cfg(any(feature = "headless", feature = "neovim"))]
+mod local_server;
+pub mod nomadignore;
 mod pausable_stream;
 pub mod pause;
+pub mod peer_status;
 pub mod peers;
+pub mod permissions;
 pub mod progress;
 pub mod project;
+pub mod query;
+pub mod read_only;
+pub mod read_write;
+mod reconnect;
+pub mod recording;
+pub mod redo;
 pub mod resume;
 mod root_markers;
 pub mod session;
+#[doc(hidden)]
+pub mod snapshot;
 pub mod start;
-#[cfg(feature = "neovim")]
+pub mod statusline;
+#[cfg(any(feature = "headless", feature = "neovim"))]
 mod tcp_stream_ext;
+pub mod undo;
+pub mod unfollow;
 
 pub use collab::Collab;
 pub use collab_types::{Peer, PeerHandle, PeerId};
@@ -0,0 +1,201 @@
+//! Contains the [`ListPeers`] action, which lets the user pick one of the
+//! remote peers and jump to their cursor.
+
+use core::time::Duration;
+
+use abs_path::AbsPathBuf;
+use collab_project::text::CursorId;
+use collab_types::{PeerHandle, PeerId};
+use editor::command::ToCompletionFn;
+use editor::module::AsyncAction;
+use editor::{Access, Context};
+
+use crate::collab::Collab;
+use crate::editors::CollabEditor;
+use crate::jump::{Jump, JumpToCursorError};
+use crate::peers::{PeerActivity, PeerPosition, RemotePeer};
+use crate::project::Project;
+use crate::session::{NoActiveSessionError, Sessions};
+
+/// An `Action` that lists the remote peers in all the current sessions
+/// together with the file and line they're at, and jumps to the cursor of
+/// the one selected by the user.
+#[derive(cauchy::Clone)]
+pub struct ListPeers<Ed: CollabEditor> {
+    sessions: Sessions<Ed>,
+}
+
+/// A remote peer as shown by [`ListPeers`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerInfos {
+    /// Whether the peer is active, idle or away.
+    pub activity: PeerActivity,
+
+    /// The peer's handle.
+    pub handle: PeerHandle,
+
+    /// The peer's ID.
+    pub id: PeerId,
+
+    /// How long it's been since the peer last interacted with the project.
+    pub idle_time: Duration,
+
+    /// The paths of the files the peer has open, with the most recently
+    /// focused one first.
+    ///
+    /// See [`RemotePeer::open_files`] for how these are tracked.
+    pub open_files: Vec<AbsPathBuf>,
+
+    /// The position of the peer's main cursor, or `None` if the peer
+    /// doesn't have a cursor in the project.
+    pub position: Option<PeerPosition>,
+
+    /// The ID of the peer's main cursor.
+    cursor_id: Option<CursorId>,
+}
+
+impl<Ed: CollabEditor> ListPeers<Ed> {
+    pub(crate) async fn call_inner(
+        &self,
+        ctx: &mut Context<Ed>,
+    ) -> Result<(), ListPeersError<Ed>> {
+        let mut sessions = Vec::new();
+        self.sessions.for_each(|infos| sessions.push(infos.clone()));
+
+        if sessions.is_empty() {
+            return Err(ListPeersError::NoActiveSession);
+        }
+
+        let mut peers = Vec::new();
+
+        for session in sessions {
+            let Some(session_peers) = session
+                .project_access
+                .with(async move |proj, _| {
+                    proj.remote_peers.with(|map| {
+                        map.values()
+                            .map(|peer| PeerInfos::new(peer, proj))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .await
+            else {
+                continue;
+            };
+
+            peers.extend(
+                session_peers.into_iter().map(|peer| (peer, session.clone())),
+            );
+        }
+
+        if peers.is_empty() {
+            return Err(ListPeersError::NoRemotePeers);
+        }
+
+        peers.sort_by(|(lhs, _), (rhs, _)| {
+            lhs.handle.as_str().cmp(rhs.handle.as_str())
+        });
+
+        let (peer_infos, sessions): (Vec<_>, Vec<_>) =
+            peers.into_iter().unzip();
+
+        let Some(selected) = Ed::select_peer(&peer_infos, ctx).await else {
+            return Ok(());
+        };
+
+        let idx = peer_infos
+            .iter()
+            .position(|peer| core::ptr::eq(peer, selected))
+            .expect("the selected peer is one of the given ones");
+
+        let Some(cursor_id) = selected.cursor_id else {
+            return Err(ListPeersError::PeerCursorNotInProject(
+                selected.handle.clone(),
+            ));
+        };
+
+        sessions[idx]
+            .project_access
+            .with(async move |proj, ctx| {
+                Jump::jump_to(proj, cursor_id, ctx).await
+            })
+            .await
+            .ok_or(ListPeersError::SessionEnded)?
+            .map_err(ListPeersError::Jump)
+    }
+}
+
+impl PeerInfos {
+    fn new<Ed: CollabEditor>(peer: &RemotePeer, proj: &Project<Ed>) -> Self {
+        let cursor_id = peer.main_cursor();
+
+        let position =
+            cursor_id.and_then(|cursor_id| proj.cursor_position(cursor_id));
+
+        let open_files = peer
+            .open_files()
+            .iter()
+            .filter_map(|&file_id| proj.inner.file(file_id))
+            .map(|file| proj.root_path().concat(&file.path()))
+            .collect();
+
+        Self {
+            activity: peer.activity(),
+            handle: peer.handle.clone(),
+            id: peer.id,
+            idle_time: peer.idle_time(),
+            open_files,
+            position,
+            cursor_id,
+        }
+    }
+}
+
+impl<Ed: CollabEditor> AsyncAction<Ed> for ListPeers<Ed> {
+    const NAME: &str = "peers";
+
+    type Args = ();
+
+    async fn call(&mut self, _: Self::Args, ctx: &mut Context<Ed>) {
+        if let Err(err) = self.call_inner(ctx).await {
+            Ed::on_list_peers_error(err, ctx);
+        }
+    }
+}
+
+/// The type of error that can occur when [`ListPeers`] fails.
+#[derive(
+    cauchy::Debug, derive_more::Display, cauchy::Error, cauchy::PartialEq,
+)]
+pub enum ListPeersError<Ed: CollabEditor> {
+    /// Jumping to the selected peer failed.
+    #[display("{_0}")]
+    Jump(JumpToCursorError<Ed>),
+
+    /// There are no active sessions.
+    #[display("{}", NoActiveSessionError)]
+    NoActiveSession,
+
+    /// There are no remote peers in any of the sessions.
+    #[display("There are no other peers in any of the sessions")]
+    NoRemotePeers,
+
+    /// The selected peer doesn't have a cursor in the project.
+    #[display("{_0}'s cursor is not in the project")]
+    PeerCursorNotInProject(PeerHandle),
+
+    /// The session the selected peer was in ended before we could jump to
+    /// them.
+    #[display("The session ended before jumping to the selected peer")]
+    SessionEnded,
+}
+
+impl<Ed: CollabEditor> From<&Collab<Ed>> for ListPeers<Ed> {
+    fn from(collab: &Collab<Ed>) -> Self {
+        Self { sessions: collab.sessions.clone() }
+    }
+}
+
+impl<Ed: CollabEditor> ToCompletionFn<Ed> for ListPeers<Ed> {
+    fn to_completion_fn(&self) {}
+}
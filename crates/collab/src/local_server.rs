@@ -0,0 +1,150 @@
+//! Contains the collab server started by the
+//! [`HostLocal`](crate::host_local::HostLocal) action, which runs inside the
+//! editor's process.
+//!
+//! The server's only executor is the one in `collab-server`'s `test` feature,
+//! so the server is only built with the opt-in `host-local` feature, and
+//! [`serve`] returns an error otherwise. Without it, the
+//! [`HostLocal`](crate::host_local::HostLocal) action isn't registered
+//! either.
+
+use core::net::SocketAddr;
+use std::io;
+
+use editor::{Context, Editor};
+
+#[cfg(feature = "host-local")]
+use self::server::LocalServerConfig;
+use crate::host_local::LocalServer;
+
+#[cfg(feature = "host-local")]
+mod server {
+    use abs_path::NodeName;
+    use collab_types::{Peer, PeerHandle};
+    use nomad_collab_params::auth_types::JsonWebToken;
+    use nomad_collab_params::{
+        API_VERSION,
+        AuthError,
+        AuthInfos,
+        NomadParams,
+        SessionId,
+    };
+
+    /// The server's configuration.
+    #[derive(Default)]
+    pub(super) struct LocalServerConfig {
+        inner: collab_server::test::TestConfig,
+    }
+
+    /// Authenticates peers by validating the JWT they got from Nomad's auth
+    /// server, which can be done offline.
+    struct LocalAuthenticator;
+
+    impl collab_server::Config for LocalServerConfig {
+        type Authenticator = LocalAuthenticator;
+        type Executor =
+        <collab_server::test::TestConfig as collab_server::Config>::Executor;
+        type Params = NomadParams;
+        type SessionObserver = ();
+
+        fn authenticator(&self) -> &Self::Authenticator {
+            &LocalAuthenticator
+        }
+
+        fn executor(&self) -> &Self::Executor {
+            self.inner.executor()
+        }
+
+        fn new_session_id(&self) -> SessionId {
+            SessionId::from_rng(&mut rand::rng())
+        }
+
+        fn new_session_observer(
+            &self,
+            _: &Peer,
+            _: &NodeName,
+            _: &SessionId,
+        ) -> Self::SessionObserver {
+        }
+    }
+
+    impl collab_server::Authenticator for LocalAuthenticator {
+        type Infos = AuthInfos;
+        type Error = AuthError;
+
+        async fn authenticate(
+            &self,
+            auth_infos: &Self::Infos,
+        ) -> Result<PeerHandle, Self::Error> {
+            if auth_infos.api_version != API_VERSION {
+                return Err(AuthError::OutdatedClient);
+            }
+
+            JsonWebToken::from_str_on_collab_server(&auth_infos.jwt)
+                .map(|jwt| jwt.claims().username.clone())
+                .map_err(|err| AuthError::Jwt(err.to_string()))
+        }
+    }
+}
+
+/// Starts a collab server listening on the given address.
+///
+/// The server keeps running until the returned [`LocalServer`] is dropped.
+#[cfg(feature = "host-local")]
+pub(crate) async fn serve(
+    listen_addr: SocketAddr,
+    ctx: &mut Context<impl Editor>,
+) -> io::Result<LocalServer> {
+    use core::net::Ipv4Addr;
+    use core::pin::pin;
+
+    use async_net::TcpListener;
+    use futures_util::{StreamExt, future};
+
+    use crate::config;
+
+    let listener = TcpListener::bind(listen_addr).await?;
+    let port = listener.local_addr()?.port();
+
+    let server = collab_server::CollabServer::<LocalServerConfig>::default();
+
+    let ip = if listen_addr.ip().is_unspecified() {
+        Ipv4Addr::LOCALHOST.into()
+    } else {
+        listen_addr.ip()
+    };
+
+    let (local_server, stop_rx) = LocalServer::new(config::ServerAddress {
+        host: config::Host::Ip(ip),
+        port,
+        plaintext: true,
+    });
+
+    ctx.spawn_and_detach(async move |_| {
+        let connections = listener
+            .incoming()
+            .filter_map(|conn_res| future::ready(conn_res.ok()));
+
+        // Dropping the server also drops the listener and all the open
+        // connections, which frees the port for the next one.
+        future::select(
+            pin!(server.run(connections)),
+            pin!(stop_rx.recv_async()),
+        )
+        .await;
+    });
+
+    Ok(local_server)
+}
+
+/// Always fails, because Nomad was built without the `host-local` feature.
+#[cfg(not(feature = "host-local"))]
+pub(crate) async fn serve(
+    _: SocketAddr,
+    _: &mut Context<impl Editor>,
+) -> io::Result<LocalServer> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Nomad was built without the `host-local` feature",
+    ))
+}
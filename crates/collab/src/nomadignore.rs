@@ -0,0 +1,415 @@
+//! Contains the [`NomadIgnore`] filter, which keeps files out of a project
+//! based on the patterns in its `.nomadignore` file and in the
+//! [`exclude`](crate::config::Config::exclude) list of the config.
+
+use core::convert::Infallible;
+use core::mem;
+
+use abs_path::{AbsPath, AbsPathBuf, node};
+use fs::filter::Filter;
+use fs::{Metadata, ReadFileError, ReadFileToStringError};
+
+/// A [`Filter`] that filters out the nodes matching any of a list of patterns
+/// written in `.gitignore` syntax.
+///
+/// Unlike Git, only the `.nomadignore` file at the root of the project is
+/// read, and only when the filter is created.
+pub struct NomadIgnore {
+    /// The path to the root of the project. Patterns containing a `/` are
+    /// matched against the paths of the nodes relative to it.
+    root_path: AbsPathBuf,
+
+    /// The patterns, in the order they were given. The last one matching a
+    /// node decides whether it's filtered.
+    patterns: Vec<Pattern>,
+}
+
+/// A single element of a [`Pattern`]'s glob.
+enum GlobToken {
+    /// A literal character.
+    Char(char),
+
+    /// A `?`, which matches any character except `/`.
+    AnyChar,
+
+    /// A `[..]` character class, without the brackets.
+    Class(Vec<char>),
+
+    /// A `*`, which matches any sequence of characters except `/`.
+    Star,
+
+    /// A `**` making up a whole trailing path component, which matches any
+    /// sequence of characters.
+    DoubleStar,
+
+    /// A `**/` making up a whole leading or middle path component, which
+    /// matches zero or more directories.
+    AnyDirs,
+}
+
+/// A single line of a `.nomadignore` file.
+struct Pattern {
+    /// The glob, without any leading `!` or `/` or trailing `/`.
+    glob: Vec<GlobToken>,
+
+    /// Whether the glob contains a `/`, in which case it's matched against
+    /// the node's path relative to the project root instead of just its
+    /// name.
+    is_anchored: bool,
+
+    /// Whether the pattern started with a `!`, i.e. it re-includes the nodes
+    /// excluded by a previous pattern.
+    is_negated: bool,
+
+    /// Whether the pattern ended with a `/`, i.e. it only matches
+    /// directories.
+    only_dirs: bool,
+}
+
+impl NomadIgnore {
+    /// Reads the `.nomadignore` file at the root of the project, if there is
+    /// one, and creates a filter from its patterns preceded by the given
+    /// ones.
+    pub(crate) async fn read<Fs: fs::Fs>(
+        fs: &Fs,
+        root_path: &AbsPath,
+        exclude: &[String],
+    ) -> Result<Self, ReadFileToStringError<Fs>> {
+        let file_path = root_path.join(node!(".nomadignore"));
+
+        let contents = match fs.read_file_to_string(&file_path).await {
+            Ok(contents) => contents,
+            Err(ReadFileToStringError::ReadFile(
+                ReadFileError::NoNodeAtPath(_),
+            )) => String::new(),
+            Err(err) => return Err(err),
+        };
+
+        let lines = exclude.iter().map(String::as_str).chain(contents.lines());
+
+        Ok(Self::new(root_path.to_owned(), lines))
+    }
+
+    /// Creates a new filter from the given lines in `.gitignore` syntax.
+    pub fn new<'a>(
+        root_path: AbsPathBuf,
+        lines: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        let patterns = lines.into_iter().filter_map(Pattern::parse).collect();
+        Self { root_path, patterns }
+    }
+
+    /// Returns whether the node at the given path relative to the project
+    /// root (without a leading `/`) should be filtered.
+    fn is_ignored(&self, path_in_project: &str, is_dir: bool) -> bool {
+        let path = path_in_project.chars().collect::<Vec<_>>();
+
+        let name_start = path
+            .iter()
+            .rposition(|&char| char == '/')
+            .map_or(0, |slash_idx| slash_idx + 1);
+
+        // Reused across patterns to avoid allocating on every match.
+        let mut buffers = (Vec::new(), Vec::new());
+
+        self.patterns
+            .iter()
+            .rev()
+            .find(|pattern| {
+                (is_dir || !pattern.only_dirs)
+                    && if pattern.is_anchored {
+                        glob_matches(&pattern.glob, &path, &mut buffers)
+                    } else {
+                        glob_matches(
+                            &pattern.glob,
+                            &path[name_start..],
+                            &mut buffers,
+                        )
+                    }
+            })
+            .is_some_and(|pattern| !pattern.is_negated)
+    }
+}
+
+impl Pattern {
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end();
+
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (line, is_negated) = match line.strip_prefix('!') {
+            Some(rest) => (rest, true),
+            None => (line.strip_prefix('\\').unwrap_or(line), false),
+        };
+
+        let (line, only_dirs) = match line.strip_suffix('/') {
+            Some(rest) => (rest, true),
+            None => (line, false),
+        };
+
+        let is_anchored = line.contains('/');
+
+        let glob = line.strip_prefix('/').unwrap_or(line);
+
+        if glob.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            glob: tokenize(&glob.chars().collect::<Vec<_>>()),
+            is_anchored,
+            is_negated,
+            only_dirs,
+        })
+    }
+}
+
+impl<Fs: fs::Fs> Filter<Fs> for NomadIgnore {
+    type Error = Infallible;
+
+    async fn should_filter(
+        &self,
+        dir_path: &AbsPath,
+        node_meta: &impl Metadata<Fs = Fs>,
+    ) -> Result<bool, Self::Error> {
+        if self.patterns.is_empty() {
+            return Ok(false);
+        }
+
+        let Ok(node_name) = node_meta.name() else { return Ok(false) };
+
+        let Some(dir_in_project) = dir_path.strip_prefix(&self.root_path)
+        else {
+            return Ok(false);
+        };
+
+        let path_in_project = match dir_in_project.as_str().trim_matches('/') {
+            "" => node_name.as_str().to_owned(),
+            dir => format!("{dir}/{}", node_name.as_str()),
+        };
+
+        Ok(self.is_ignored(&path_in_project, node_meta.node_kind().is_dir()))
+    }
+}
+
+/// Splits the given glob into its tokens.
+///
+/// Like in Git, `**` only matches across `/` when it makes up a whole path
+/// component, i.e. when it's at the start of the glob or after a `/`, and
+/// either at the end of the glob or before a `/`. Anywhere else it's the
+/// same as a single `*`.
+fn tokenize(glob: &[char]) -> Vec<GlobToken> {
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+
+    while idx < glob.len() {
+        let (token, len) = match &glob[idx..] {
+            ['*', ..] => {
+                let num_stars =
+                    glob[idx..].iter().take_while(|&&c| c == '*').count();
+                let starts_component = idx == 0 || glob[idx - 1] == '/';
+                let next = glob.get(idx + num_stars);
+                match next {
+                    Some('/') if num_stars > 1 && starts_component => {
+                        (GlobToken::AnyDirs, num_stars + 1)
+                    },
+                    None if num_stars > 1 && starts_component => {
+                        (GlobToken::DoubleStar, num_stars)
+                    },
+                    _ => (GlobToken::Star, num_stars),
+                }
+            },
+
+            ['?', ..] => (GlobToken::AnyChar, 1),
+
+            ['[', class @ ..] => {
+                match class.iter().skip(1).position(|&c| c == ']') {
+                    Some(class_len) => (
+                        GlobToken::Class(class[..class_len + 1].to_vec()),
+                        class_len + 3,
+                    ),
+                    // An unclosed '[' is matched literally.
+                    None => (GlobToken::Char('['), 1),
+                }
+            },
+
+            ['\\', escaped, ..] => (GlobToken::Char(*escaped), 2),
+
+            [literal, ..] => (GlobToken::Char(*literal), 1),
+
+            [] => unreachable!("idx < glob.len()"),
+        };
+
+        tokens.push(token);
+        idx += len;
+    }
+
+    tokens
+}
+
+/// Returns whether the given text matches the given glob.
+///
+/// This runs in `O(glob.len() * text.len())` time by computing, from the
+/// last token to the first, which suffixes of the text are matched by the
+/// remaining tokens. The two buffers are used as scratch space.
+fn glob_matches(
+    glob: &[GlobToken],
+    text: &[char],
+    (next, curr): &mut (Vec<bool>, Vec<bool>),
+) -> bool {
+    let len = text.len();
+
+    // `next[idx]` is whether the tokens after the current one match
+    // `text[idx..]`.
+    next.clear();
+    next.resize(len + 1, false);
+    next[len] = true;
+
+    for token in glob.iter().rev() {
+        curr.clear();
+        curr.resize(len + 1, false);
+
+        // Whether there's a `/` at or after the current index which is
+        // followed by a match of the next tokens.
+        let mut has_dir_match = false;
+
+        for idx in (0..=len).rev() {
+            let char = text.get(idx).copied();
+            let not_slash = char.is_some_and(|c| c != '/');
+
+            curr[idx] = match token {
+                GlobToken::Char(literal) => {
+                    char == Some(*literal) && next[idx + 1]
+                },
+                GlobToken::AnyChar => not_slash && next[idx + 1],
+                GlobToken::Class(class) => {
+                    not_slash
+                        && char.is_some_and(|c| class_matches(class, c))
+                        && next[idx + 1]
+                },
+                GlobToken::Star => next[idx] || (not_slash && curr[idx + 1]),
+                GlobToken::DoubleStar => {
+                    next[idx] || (idx < len && curr[idx + 1])
+                },
+                GlobToken::AnyDirs => {
+                    has_dir_match |= char == Some('/') && next[idx + 1];
+                    next[idx] || has_dir_match
+                },
+            };
+        }
+
+        mem::swap(next, curr);
+    }
+
+    next[0]
+}
+
+/// Returns whether the given character is in the given character class,
+/// i.e. the contents of a `[..]` glob without the brackets.
+fn class_matches(class: &[char], char: char) -> bool {
+    let (class, is_negated) = match class {
+        ['!' | '^', rest @ ..] => (rest, true),
+        _ => (class, false),
+    };
+
+    let mut is_match = false;
+    let mut idx = 0;
+
+    while idx < class.len() {
+        if idx + 2 < class.len() && class[idx + 1] == '-' {
+            is_match |= (class[idx]..=class[idx + 2]).contains(&char);
+            idx += 3;
+        } else {
+            is_match |= class[idx] == char;
+            idx += 1;
+        }
+    }
+
+    is_match != is_negated
+}
+
+#[cfg(test)]
+mod tests {
+    use abs_path::path;
+
+    use super::*;
+
+    fn nomadignore(lines: &[&str]) -> NomadIgnore {
+        NomadIgnore::new(path!("/project").to_owned(), lines.iter().copied())
+    }
+
+    #[test]
+    fn unanchored_patterns_match_at_any_depth() {
+        let ignore = nomadignore(&[".env", "*.log"]);
+        assert!(ignore.is_ignored(".env", false));
+        assert!(ignore.is_ignored("server/.env", false));
+        assert!(ignore.is_ignored("logs/today.log", false));
+        assert!(!ignore.is_ignored("src/env.rs", false));
+    }
+
+    #[test]
+    fn anchored_patterns_match_from_the_root() {
+        let ignore = nomadignore(&["/fixtures", "tests/data/*.bin"]);
+        assert!(ignore.is_ignored("fixtures", true));
+        assert!(!ignore.is_ignored("tests/fixtures", true));
+        assert!(ignore.is_ignored("tests/data/big.bin", false));
+        assert!(!ignore.is_ignored("tests/data/nested/big.bin", false));
+    }
+
+    #[test]
+    fn double_star_matches_across_directories() {
+        let ignore = nomadignore(&["**/snapshots/**"]);
+        assert!(ignore.is_ignored("snapshots/a.snap", false));
+        assert!(ignore.is_ignored("crates/foo/snapshots/a.snap", false));
+        assert!(!ignore.is_ignored("crates/foo/a.snap", false));
+    }
+
+    #[test]
+    fn trailing_slash_only_matches_directories() {
+        let ignore = nomadignore(&["build/"]);
+        assert!(ignore.is_ignored("build", true));
+        assert!(!ignore.is_ignored("build", false));
+    }
+
+    #[test]
+    fn last_matching_pattern_wins() {
+        let ignore = nomadignore(&["*.env", "!example.env"]);
+        assert!(ignore.is_ignored("prod.env", false));
+        assert!(!ignore.is_ignored("example.env", false));
+    }
+
+    #[test]
+    fn comments_and_blank_lines_are_skipped() {
+        let ignore = nomadignore(&["# secrets", "", "\\#notes"]);
+        assert!(!ignore.is_ignored("# secrets", false));
+        assert!(ignore.is_ignored("#notes", false));
+    }
+
+    #[test]
+    fn double_star_inside_a_component_is_a_single_star() {
+        let ignore = nomadignore(&["foo**bar"]);
+        assert!(ignore.is_ignored("foobar", false));
+        assert!(ignore.is_ignored("foo_bar", false));
+        assert!(!ignore.is_ignored("foo/bar", false));
+    }
+
+    #[test]
+    fn pathological_patterns_dont_backtrack() {
+        let ignore = nomadignore(&["a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*b"]);
+        assert!(!ignore.is_ignored(&"a".repeat(200), false));
+
+        let ignore = nomadignore(&["**/a/**/a/**/a/**/a/**/a/**/b"]);
+        assert!(!ignore.is_ignored(&["a"; 100].join("/"), false));
+    }
+
+    #[test]
+    fn character_classes() {
+        let ignore = nomadignore(&["data[0-9].csv", "[!a]bc"]);
+        assert!(ignore.is_ignored("data7.csv", false));
+        assert!(!ignore.is_ignored("datax.csv", false));
+        assert!(ignore.is_ignored("xbc", false));
+        assert!(!ignore.is_ignored("abc", false));
+    }
+}
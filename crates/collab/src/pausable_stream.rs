@@ -27,6 +27,7 @@ pub(crate) struct Remote {
 #[derive(Default)]
 struct RemoteInner {
     is_paused: Cell<bool>,
+    num_buffered: Cell<usize>,
     waker: Cell<Option<Waker>>,
 }
 
@@ -46,6 +47,15 @@ impl<S: Stream> PausableStream<S> {
         self.remote.clone()
     }
 
+    /// Replaces the inner stream with the given one, keeping any items that
+    /// were buffered from the previous stream while paused.
+    pub(crate) fn replace_inner(&mut self, stream: S)
+    where
+        S: Unpin,
+    {
+        self.inner = stream;
+    }
+
     /// Returns whether the stream is currently paused.
     #[cfg(test)]
     fn is_paused(&self) -> bool {
@@ -72,10 +82,17 @@ impl Remote {
         was_paused
     }
 
-    fn is_paused(&self) -> bool {
+    /// Returns whether the associated [`PausableStream`] is paused.
+    pub(crate) fn is_paused(&self) -> bool {
         self.inner.is_paused.get()
     }
 
+    /// Returns the number of items the associated [`PausableStream`] has
+    /// buffered while paused, and that it hasn't yielded yet.
+    pub(crate) fn num_buffered(&self) -> usize {
+        self.inner.num_buffered.get()
+    }
+
     fn new() -> Self {
         Self { inner: Rc::default() }
     }
@@ -93,6 +110,7 @@ impl<S: FusedStream> Stream for PausableStream<S> {
         let is_paused = this.remote.is_paused();
 
         if !is_paused && let Some(item) = this.buffer.pop_front() {
+            this.remote.inner.num_buffered.set(this.buffer.len());
             return Poll::Ready(Some(item));
         }
 
@@ -105,7 +123,10 @@ impl<S: FusedStream> Stream for PausableStream<S> {
         loop {
             match ready!(this.inner.as_mut().poll_next(ctx)) {
                 Some(item) if !is_paused => return Poll::Ready(Some(item)),
-                Some(item) => this.buffer.push_back(item),
+                Some(item) => {
+                    this.buffer.push_back(item);
+                    this.remote.inner.num_buffered.set(this.buffer.len());
+                },
                 None if !is_paused => return Poll::Ready(None),
                 None => return Poll::Pending,
             }
@@ -123,6 +144,7 @@ impl fmt::Debug for Remote {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Remote")
             .field("is_paused", &self.is_paused())
+            .field("num_buffered", &self.num_buffered())
             .finish_non_exhaustive()
     }
 }
@@ -142,4 +164,29 @@ mod tests {
         let collected = stream.collect::<Vec<_>>().now_or_never().unwrap();
         assert_eq!(collected, iter.collect::<Vec<_>>());
     }
+
+    #[test]
+    fn remote_counts_buffered_items() {
+        let mut stream = PausableStream::new(stream::iter(vec![0, 1]).fuse());
+        let remote = stream.remote();
+        remote.pause();
+        assert!(stream.next().now_or_never().is_none());
+        assert_eq!(remote.num_buffered(), 2);
+        remote.resume();
+        assert_eq!(stream.next().now_or_never(), Some(Some(0)));
+        assert_eq!(remote.num_buffered(), 1);
+    }
+
+    #[test]
+    fn replacing_inner_stream_keeps_buffered_items() {
+        let mut stream = PausableStream::new(stream::iter(vec![0, 1]).fuse());
+        let remote = stream.remote();
+        remote.pause();
+        // The stream is paused, so the items are buffered.
+        assert!(stream.next().now_or_never().is_none());
+        stream.replace_inner(stream::iter(vec![2, 3]).fuse());
+        remote.resume();
+        let collected = stream.collect::<Vec<_>>().now_or_never().unwrap();
+        assert_eq!(collected, [0, 1, 2, 3]);
+    }
 }
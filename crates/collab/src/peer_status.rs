@@ -0,0 +1,62 @@
+//! Contains the [`PeerStatus`] function, which returns the
+//! [`PeerActivity`] of a remote peer.
+
+use collab_types::{GitHubHandle, PeerHandle};
+use editor::context::Borrowed;
+use editor::module::Action;
+use editor::{Context, command};
+
+use crate::collab::Collab;
+use crate::editors::CollabEditor;
+use crate::peers::PeerActivity;
+use crate::session::Sessions;
+
+/// A function that returns whether the remote peer with the given GitHub
+/// handle is active, idle or away, or `nil` if there's no such peer in any of
+/// the sessions.
+#[derive(cauchy::Clone)]
+pub struct PeerStatus<Ed: CollabEditor> {
+    sessions: Sessions<Ed>,
+}
+
+impl<Ed: CollabEditor> PeerStatus<Ed> {
+    pub(crate) fn call_inner(
+        &self,
+        peer_handle: PeerHandle,
+    ) -> Option<PeerActivity> {
+        let mut activity = None;
+
+        self.sessions.find(|sesh| {
+            match sesh.remote_peers.find(|peer| peer.handle == peer_handle) {
+                Some(peer) => {
+                    activity = Some(peer.activity());
+                    true
+                },
+                None => false,
+            }
+        });
+
+        activity
+    }
+}
+
+impl<Ed: CollabEditor> Action<Ed> for PeerStatus<Ed> {
+    const NAME: &str = "peer_status";
+
+    type Args<'args> = command::Parse<GitHubHandle>;
+    type Return = Option<PeerActivity>;
+
+    fn call(
+        &mut self,
+        command::Parse(github_handle): Self::Args<'_>,
+        _: &mut Context<Ed, Borrowed<'_>>,
+    ) -> Self::Return {
+        self.call_inner(PeerHandle::GitHub(github_handle))
+    }
+}
+
+impl<Ed: CollabEditor> From<&Collab<Ed>> for PeerStatus<Ed> {
+    fn from(collab: &Collab<Ed>) -> Self {
+        Self { sessions: collab.sessions.clone() }
+    }
+}
@@ -2,18 +2,32 @@
 //! [`Project`](crate::project::Project).
 
 use core::ops::Deref;
+use core::time::Duration;
 use std::collections::hash_map;
+use std::time::Instant;
 
+use abs_path::AbsPathBuf;
 use collab_project::text::CursorId;
-use collab_types::{Peer, PeerId};
+use collab_types::{Peer, PeerId, puff};
 use editor::{Access, AccessMut, Shared};
 use fxhash::FxHashMap;
+use puff::file::LocalFileId;
+use smallvec::SmallVec;
+
+/// How often a running session checks whether any of the remote peers went
+/// [`Idle`](PeerActivity::Idle) or [`Away`](PeerActivity::Away).
+pub(crate) const ACTIVITY_CHECK_INTERVAL: Duration = Duration::from_secs(10);
 
 /// TODO: docs.
 #[derive(Debug, Default, Clone)]
 pub struct RemotePeers {
     /// A map of all the remote peers currently in a session.
     inner: Shared<FxHashMap<PeerId, RemotePeer>>,
+
+    /// The IDs of all the peers that have been part of the project's history,
+    /// including the ones that have left and the ones from the sessions it
+    /// was [resumed](crate::snapshot) from.
+    history: Shared<Vec<PeerId>>,
 }
 
 /// TODO: docs.
@@ -24,6 +38,47 @@ pub struct RemotePeer {
     // - when a remote cursor is moved;
     // - when a remote cursor is removed;
     main_cursor_id: Option<CursorId>,
+    is_followed: bool,
+    /// The files the peer has had a cursor in, with the most recently
+    /// focused one first.
+    open_files: SmallVec<[LocalFileId; 4]>,
+    /// The last time we received an edit, cursor or selection from the peer.
+    last_active_at: Instant,
+    /// The peer's activity as of the last call to
+    /// [`update_activity`](RemotePeer::update_activity).
+    activity: PeerActivity,
+    /// The position of the peer's main cursor as of the last call to
+    /// [`RemotePeers::update_positions`].
+    position: Option<PeerPosition>,
+}
+
+/// The position of a remote peer's main cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerPosition {
+    /// The path of the file the cursor is in.
+    pub file_path: AbsPathBuf,
+
+    /// The index of the line the cursor is on.
+    pub line_idx: usize,
+}
+
+/// How recently a [`RemotePeer`] has interacted with the project.
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum PeerActivity {
+    /// The peer has edited the project or moved their cursor or selections
+    /// in the last [`IDLE_AFTER`](Self::IDLE_AFTER).
+    Active,
+
+    /// The peer has been inactive for more than
+    /// [`IDLE_AFTER`](Self::IDLE_AFTER), e.g. because they're reading.
+    Idle,
+
+    /// The peer has been inactive for more than
+    /// [`AWAY_AFTER`](Self::AWAY_AFTER), and has probably stepped away.
+    Away,
 }
 
 impl RemotePeers {
@@ -41,9 +96,95 @@ impl RemotePeers {
             .into_iter()
             .map(|peer| RemotePeer::new(peer, proj))
             .map(|remote_peer| (remote_peer.id, remote_peer))
-            .collect();
+            .collect::<FxHashMap<_, _>>();
+
+        let history = map.keys().copied().collect();
+
+        Self { inner: Shared::new(map), history: Shared::new(history) }
+    }
+
+    /// Adds the given IDs to the [`history`](Self::history).
+    pub(crate) fn extend_history(
+        &self,
+        peer_ids: impl IntoIterator<Item = PeerId>,
+    ) {
+        self.history.with_mut(|history| {
+            for peer_id in peer_ids {
+                if !history.contains(&peer_id) {
+                    history.push(peer_id);
+                }
+            }
+        });
+    }
+
+    /// Returns the IDs of all the peers that have been part of the project's
+    /// history, including the ones that have left.
+    pub(crate) fn history(&self) -> Vec<PeerId> {
+        self.history.with(Clone::clone)
+    }
+
+    /// Returns the [`RemotePeer`] currently being
+    /// [`Follow`](crate::follow::Follow)ed, if any.
+    pub fn followed(&self) -> Option<RemotePeer> {
+        self.find(|peer| peer.is_followed)
+    }
+
+    /// Starts following the peer with the given ID, or stops following anyone
+    /// if `None`, returning the peer that was previously followed.
+    pub fn set_followed(&self, peer_id: Option<PeerId>) -> Option<RemotePeer> {
+        self.inner.with_mut(|inner| {
+            let mut previously_followed = None;
+            for peer in inner.values_mut() {
+                if peer.is_followed {
+                    previously_followed = Some(peer.clone());
+                }
+                peer.is_followed = Some(peer.id) == peer_id;
+            }
+            previously_followed
+        })
+    }
+
+    /// Records that the peer with the given ID just interacted with the
+    /// project, returning the peer if that made it
+    /// [`Active`](PeerActivity::Active) again.
+    pub(crate) fn mark_active(&self, peer_id: PeerId) -> Option<RemotePeer> {
+        self.inner.with_mut(|inner| {
+            let peer = inner.get_mut(&peer_id)?;
+            let now = Instant::now();
+            peer.last_active_at = now;
+            peer.update_activity(now).then(|| peer.clone())
+        })
+    }
+
+    /// Recomputes the activity of all the remote peers as of the given
+    /// instant, returning the ones whose activity has changed since the last
+    /// update.
+    pub(crate) fn update_activities(
+        &self,
+        now: Instant,
+    ) -> SmallVec<[RemotePeer; 2]> {
+        self.inner.with_mut(|inner| {
+            inner
+                .values_mut()
+                .filter_map(|peer| {
+                    peer.update_activity(now).then(|| peer.clone())
+                })
+                .collect()
+        })
+    }
 
-        Self { inner: Shared::new(map) }
+    /// Updates the cached [`position`](RemotePeer::position) of all the
+    /// remote peers, using the given function to resolve the position of
+    /// their main cursor.
+    pub(crate) fn update_positions(
+        &self,
+        mut fun: impl FnMut(CursorId) -> Option<PeerPosition>,
+    ) {
+        self.inner.with_mut(|inner| {
+            for peer in inner.values_mut() {
+                peer.position = peer.main_cursor_id.and_then(&mut fun);
+            }
+        });
     }
 
     /// Calls the given function on all the remote peers.
@@ -74,6 +215,7 @@ impl RemotePeers {
 
     #[track_caller]
     pub(crate) fn insert(&self, peer: Peer, proj: &collab_project::Project) {
+        self.extend_history([peer.id]);
         self.inner.with_mut(|inner| match inner.entry(peer.id) {
             hash_map::Entry::Vacant(vacant) => {
                 vacant.insert(RemotePeer::new(peer, proj));
@@ -121,11 +263,62 @@ impl RemotePeer {
             .min()
     }
 
+    /// Returns the peer's activity, based on how long ago they last
+    /// interacted with the project.
+    ///
+    /// This is refreshed every few seconds by the session's event loop, and
+    /// every time the peer interacts with the project.
+    pub fn activity(&self) -> PeerActivity {
+        self.activity
+    }
+
+    /// Returns how long it's been since the peer last edited the project or
+    /// moved their cursor or selections.
+    pub fn idle_time(&self) -> Duration {
+        self.last_active_at.elapsed()
+    }
+
+    /// Returns the position of the peer's main cursor, or `None` if the
+    /// peer doesn't have a cursor in the project.
+    ///
+    /// This is refreshed by the session's event loop, so it can lag behind
+    /// the project by one message.
+    pub fn position(&self) -> Option<&PeerPosition> {
+        self.position.as_ref()
+    }
+
+    /// Returns whether the local user is currently
+    /// [`Follow`](crate::follow::Follow)ing this peer.
+    pub fn is_followed(&self) -> bool {
+        self.is_followed
+    }
+
     #[cfg_attr(not(feature = "neovim"), expect(unused))]
     pub(crate) fn into_inner(self) -> Peer {
         self.inner
     }
 
+    /// Returns the IDs of the files the peer has open, with the most recently
+    /// focused one first.
+    ///
+    /// This is derived from the peer's cursors: a file is considered open
+    /// once the peer has had a cursor in it. Closing a buffer doesn't produce
+    /// any message, so files are never dropped from the list, and some of
+    /// the IDs may refer to files that have since been deleted.
+    pub fn open_files(&self) -> &[LocalFileId] {
+        &self.open_files
+    }
+
+    /// Moves the file with the given ID to the front of the peer's
+    /// [`open_files`](Self::open_files), inserting it if it wasn't there.
+    pub(crate) fn focus_file(&mut self, file_id: LocalFileId) {
+        if let Some(idx) = self.open_files.iter().position(|&id| id == file_id)
+        {
+            self.open_files.remove(idx);
+        }
+        self.open_files.insert(0, file_id);
+    }
+
     pub(crate) fn remove_main_cursor(&mut self) {
         self.main_cursor_id = None;
     }
@@ -136,10 +329,59 @@ impl RemotePeer {
         self.main_cursor_id = Some(new_id);
     }
 
+    /// Recomputes the peer's [`activity`](Self::activity) as of the given
+    /// instant, returning whether it has changed since the last update.
+    fn update_activity(&mut self, now: Instant) -> bool {
+        let activity = PeerActivity::from_idle_time(
+            now.saturating_duration_since(self.last_active_at),
+        );
+        let has_changed = activity != self.activity;
+        self.activity = activity;
+        has_changed
+    }
+
     fn new(peer: Peer, proj: &collab_project::Project) -> Self {
+        let open_files = proj
+            .cursors()
+            .filter(|cur| cur.owner() == peer.id)
+            .map(|cur| cur.file().local_id())
+            .fold(SmallVec::new(), |mut files, file_id| {
+                if !files.contains(&file_id) {
+                    files.push(file_id);
+                }
+                files
+            });
+
         Self {
             main_cursor_id: Self::get_main_cursor(peer.id, proj),
             inner: peer,
+            is_followed: false,
+            open_files,
+            last_active_at: Instant::now(),
+            activity: PeerActivity::Active,
+            position: None,
+        }
+    }
+}
+
+impl PeerActivity {
+    /// How long a peer has to be inactive for to be considered
+    /// [`Idle`](Self::Idle).
+    pub const IDLE_AFTER: Duration = Duration::from_secs(60);
+
+    /// How long a peer has to be inactive for to be considered
+    /// [`Away`](Self::Away).
+    pub const AWAY_AFTER: Duration = Duration::from_secs(5 * 60);
+
+    /// Returns the activity of a peer that has been inactive for the given
+    /// amount of time.
+    pub fn from_idle_time(idle_time: Duration) -> Self {
+        if idle_time >= Self::AWAY_AFTER {
+            Self::Away
+        } else if idle_time >= Self::IDLE_AFTER {
+            Self::Idle
+        } else {
+            Self::Active
         }
     }
 }
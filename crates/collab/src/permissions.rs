@@ -0,0 +1,94 @@
+//! Contains the permission model used to restrict which peers in a session
+//! can modify the project.
+
+use editor::Context;
+
+use crate::editors::{ActionForSelectedSession, CollabEditor};
+use crate::session::{NoActiveSessionError, SessionInfos, Sessions};
+
+/// The permissions of the peers in a session.
+///
+/// Read-only is enforced on the read-only peer's side: its buffers are made
+/// non-modifiable, and its agents can't edit the project. The other peers
+/// integrate every message they receive, so that all the replicas stay in
+/// sync.
+#[derive(Debug, Default)]
+pub struct Permissions {
+    /// Whether the local peer has made itself read-only.
+    is_read_only: bool,
+}
+
+/// The type of error that can occur when changing the local peer's
+/// permissions fails.
+#[derive(
+    cauchy::Debug, derive_more::Display, cauchy::Error, cauchy::PartialEq,
+)]
+pub enum PermissionsError<Ed: CollabEditor> {
+    /// There are no active sessions.
+    #[display("{}", NoActiveSessionError)]
+    NoActiveSession,
+
+    /// The local peer is already read-only in the session.
+    #[display("You're already read-only in {}", _0.proj_name())]
+    AlreadyReadOnly(SessionInfos<Ed>),
+
+    /// The local peer is already read-write in the session.
+    #[display("You can already edit {}", _0.proj_name())]
+    AlreadyReadWrite(SessionInfos<Ed>),
+}
+
+impl Permissions {
+    /// Returns whether the local peer has made itself read-only.
+    pub fn is_read_only(&self) -> bool {
+        self.is_read_only
+    }
+
+    /// Marks the local peer as read-only or read-write, returning whether
+    /// that changed its permissions.
+    pub(crate) fn set_read_only(&mut self, read_only: bool) -> bool {
+        let changed = self.is_read_only != read_only;
+        self.is_read_only = read_only;
+        changed
+    }
+}
+
+impl<Ed: CollabEditor> From<NoActiveSessionError> for PermissionsError<Ed> {
+    fn from(_: NoActiveSessionError) -> Self {
+        Self::NoActiveSession
+    }
+}
+
+/// Marks the local peer as read-only or read-write in the selected session.
+///
+/// The other peers can't be made read-only, because there's no way to let
+/// every replica agree on their permissions.
+pub(crate) async fn set_read_only<Ed: CollabEditor>(
+    sessions: &Sessions<Ed>,
+    read_only: bool,
+    action: ActionForSelectedSession,
+    ctx: &mut Context<Ed>,
+) -> Result<(), PermissionsError<Ed>> {
+    let Some(sesh) = sessions
+        .select(action, ctx)
+        .await?
+        .and_then(|(_, session_id)| sessions.get(session_id))
+    else {
+        return Ok(());
+    };
+
+    if !sesh.permissions.with_mut(|perms| perms.set_read_only(read_only)) {
+        return Err(if read_only {
+            PermissionsError::AlreadyReadOnly(sesh)
+        } else {
+            PermissionsError::AlreadyReadWrite(sesh)
+        });
+    }
+
+    sesh.project_access
+        .with(async move |proj, ctx| {
+            proj.set_buffers_read_only(read_only, ctx)
+        })
+        .await;
+
+    Ok(())
+}
@@ -1,31 +1,48 @@
 //! TODO: docs.
 
 use core::iter;
+use core::ops::Range;
 use std::sync::Arc;
+use std::time::Instant;
 
 use abs_path::{AbsPath, AbsPathBuf};
 use collab_project::fs::{File, FileMut, FsOp, Node, NodeMut};
 use collab_project::text::{CursorId, SelectionId, TextReplacement};
 use collab_types::{Message, Peer, PeerId, binary, crop, puff, text};
-use editor::{Access, AccessMut, AgentId, Buffer, Context, Editor};
+use editor::{Access, AccessMut, AgentId, Buffer, Context, Editor, Shared};
 use fs::{File as _, Fs as _, Symlink as _};
 use futures_util::FutureExt;
 use fxhash::FxHashMap;
 use puff::directory::LocalDirectoryId;
 use puff::file::{GlobalFileId, LocalFileId};
 use puff::ops::Rename;
-use smallvec::SmallVec;
+use smallvec::{SmallVec, smallvec};
 
-use crate::CollabEditor;
 use crate::convert::Convert;
 use crate::event::{self, Event};
-use crate::peers::{RemotePeer, RemotePeers};
+use crate::follow::FollowError;
+use crate::jump::Jump;
+use crate::peers::{PeerActivity, PeerPosition, RemotePeer, RemotePeers};
+use crate::permissions::Permissions;
+use crate::undo::{Revert, UndoHistory};
+use crate::{CollabEditor, SessionId};
 
 /// TODO: docs.
 pub struct Project<Ed: CollabEditor> {
     /// TODO: docs.
     pub agent_id: AgentId,
 
+    /// Map from a buffer's ID to the authorship annotations displayed in it
+    /// by the [`Blame`](crate::blame::Blame) action.
+    pub blames: FxHashMap<Ed::BufferId, Ed::BufferBlame>,
+
+    /// The ID of the session's current host.
+    ///
+    /// This is shared with the session's
+    /// [`SessionInfos`](crate::session::SessionInfos), and it's updated when
+    /// the host leaves and a new one is elected among the remaining peers.
+    pub host_id: Shared<PeerId>,
+
     /// Contains various mappings between editor IDs and project IDs.
     pub id_maps: IdMaps<Ed>,
 
@@ -43,11 +60,28 @@ pub struct Project<Ed: CollabEditor> {
     /// displayed in the editor.
     pub peer_selections: FxHashMap<SelectionId, Ed::PeerSelection>,
 
+    /// The permissions of the peers in the session.
+    ///
+    /// This is shared with the session's
+    /// [`SessionInfos`](crate::session::SessionInfos), and it's updated by
+    /// the [`ReadOnly`](crate::read_only::ReadOnly) and
+    /// [`ReadWrite`](crate::read_write::ReadWrite) actions.
+    pub permissions: Shared<Permissions>,
+
     /// The remote peers currently in the session.
     pub remote_peers: RemotePeers,
 
     /// The path to the root of the project.
     pub root_path: AbsPathBuf,
+
+    /// The ID of the session the project belongs to, or `None` if the
+    /// project is being replayed from a
+    /// [`Recording`](crate::recording::Recording).
+    pub session_id: Option<SessionId<Ed>>,
+
+    /// The history of the edits made by the local peer, used to undo them
+    /// without touching the ones made by the remote peers.
+    pub undo_history: UndoHistory,
 }
 
 #[derive(cauchy::Default)]
@@ -58,7 +92,7 @@ pub struct IdMaps<Ed: Editor> {
     pub(crate) file2buffer: FxHashMap<LocalFileId, Ed::BufferId>,
     pub(crate) node2dir:
         FxHashMap<<Ed::Fs as fs::Fs>::NodeId, LocalDirectoryId>,
-    pub(crate) node2file: FxHashMap<<Ed::Fs as fs::Fs>::NodeId, LocalFileId>,
+    pub node2file: FxHashMap<<Ed::Fs as fs::Fs>::NodeId, LocalFileId>,
     pub(crate) selection2selection: FxHashMap<Ed::SelectionId, SelectionId>,
 }
 
@@ -70,10 +104,10 @@ pub enum IntegrateError<Ed: CollabEditor> {
     BinaryEdit(IntegrateBinaryEditError<Ed::Fs>),
 
     /// TODO: docs..
-    CreateBuffer(Ed::CreateBufferError),
+    FsOp(IntegrateFsOpError<Ed::Fs>),
 
     /// TODO: docs..
-    FsOp(IntegrateFsOpError<Ed::Fs>),
+    TextEdit(IntegrateTextEditError<Ed>),
 }
 
 /// The type of error that can occcur when integrating a
@@ -127,6 +161,19 @@ pub enum IntegrateFsOpError<Fs: fs::Fs> {
     WriteFile(<Fs::File as fs::File>::WriteError),
 }
 
+/// The type of error that can occcur when integrating a
+/// [`text::TextEdit`].
+#[derive(cauchy::Debug, derive_more::Display, cauchy::Error)]
+#[display("{_0}")]
+pub enum IntegrateTextEditError<Ed: CollabEditor> {
+    /// It wasn't possible to create a buffer for the edited file.
+    CreateBuffer(Ed::CreateBufferError),
+
+    /// It wasn't possible to write the new contents of the edited file to
+    /// disk.
+    WriteToDisk(IntegrateBinaryEditError<Ed::Fs>),
+}
+
 /// TODO: docs.
 #[derive(cauchy::Debug, derive_more::Display, cauchy::Error)]
 #[display("{_0}")]
@@ -164,7 +211,13 @@ enum FsNodeContents {
 }
 
 impl<Ed: CollabEditor> Project<Ed> {
-    pub(crate) fn drop(self, ctx: &mut Context<Ed>) {
+    /// Drops the project, removing all the remote peers' cursors and
+    /// selections and the authorship annotations from the editor.
+    pub fn drop(self, ctx: &mut Context<Ed>) {
+        for blame in self.blames.into_values() {
+            Ed::remove_buffer_blame(blame, ctx);
+        }
+
         for tooltip in self.peer_cursors.into_values() {
             Ed::remove_peer_tooltip(tooltip, ctx);
         }
@@ -174,16 +227,29 @@ impl<Ed: CollabEditor> Project<Ed> {
         }
     }
 
-    /// TODO: docs.
+    /// Integrates a message received from a remote peer, returning the
+    /// messages that should be sent back in response.
     #[allow(clippy::too_many_lines)]
-    pub(crate) async fn integrate(
+    pub async fn integrate(
         &mut self,
         message: Message,
         ctx: &mut Context<Ed>,
     ) -> Result<impl IntoIterator<Item = Message>, IntegrateError<Ed>> {
+        if let Some(author_id) = message_author(&message) {
+            self.mark_peer_active(author_id, ctx);
+        }
+
         match message {
             Message::CreatedCursor(cursor_creation) => {
+                let prev_followed_cursor = self.followed_cursor();
                 self.integrate_cursor_creation(cursor_creation, ctx);
+                // Only jump if the new cursor became the followed peer's main
+                // cursor.
+                if let Some(cursor_id) = self.followed_cursor()
+                    && Some(cursor_id) != prev_followed_cursor
+                {
+                    self.jump_to_followed(cursor_id, ctx).await;
+                }
                 Ok(Messages::None)
             },
 
@@ -226,12 +292,17 @@ impl<Ed: CollabEditor> Project<Ed> {
             Message::EditedText(text_edit) => {
                 self.integrate_text_edit(text_edit, ctx)
                     .await
-                    .map_err(IntegrateError::CreateBuffer)?;
+                    .map_err(IntegrateError::TextEdit)?;
                 Ok(Messages::None)
             },
 
             Message::MovedCursor(cursor_movement) => {
-                self.integrate_cursor_move(cursor_movement, ctx);
+                if let Some(cursor_id) =
+                    self.integrate_cursor_move(cursor_movement, ctx)
+                    && self.followed_cursor() == Some(cursor_id)
+                {
+                    self.jump_to_followed(cursor_id, ctx).await;
+                }
                 Ok(Messages::None)
             },
 
@@ -268,11 +339,7 @@ impl<Ed: CollabEditor> Project<Ed> {
             },
 
             Message::ProjectRequest(request) => {
-                Ok(Messages::project_response(collab_types::ProjectResponse {
-                    peers: self.peers(),
-                    encoded_project: self.inner.encode(),
-                    response_id: request.request_id.into(),
-                }))
+                Ok(Messages::project_response(self.project_response(request)))
             },
 
             Message::ProjectResponse(_) => {
@@ -306,28 +373,179 @@ impl<Ed: CollabEditor> Project<Ed> {
         }
     }
 
+    /// Reconciles the state of the project with the one received from the
+    /// server after reconnecting to a session, returning the messages that
+    /// should be sent to the other peers.
+    ///
+    /// Any remote peer that left while we were offline is removed, and any
+    /// peer that joined in the meantime is added.
+    ///
+    /// The server assigns us a new [`PeerId`] when re-joining, and the other
+    /// peers see us leave with the old one and join with the new one. To
+    /// mirror that, the project is forked under the new ID, the cursors and
+    /// selections owned by the old one are removed, and they're created again
+    /// under the new one.
+    pub fn integrate_reconnection(
+        &mut self,
+        local_peer: Peer,
+        mut other_peers: Vec<Peer>,
+        server_host_id: PeerId,
+        ctx: &mut Context<Ed>,
+    ) -> SmallVec<[Message; 2]> {
+        let old_local_id = self.local_peer.id;
+
+        // The edits made under the old ID are still part of the project.
+        self.remote_peers.extend_history([old_local_id]);
+
+        // The server may not have noticed that our old connection was closed
+        // yet.
+        other_peers.retain(|peer| peer.id != old_local_id);
+
+        self.local_peer = local_peer;
+
+        let old_host_id = self.host_id.copied();
+
+        let new_host_id =
+            current_host_id(server_host_id, &other_peers, &self.local_peer);
+
+        // Update the host first, so that removing the peers that left doesn't
+        // trigger a new election.
+        self.host_id.set(new_host_id);
+
+        let left_peers = self.remote_peers.with(|map| {
+            map.keys()
+                .filter(|&&id| other_peers.iter().all(|peer| peer.id != id))
+                .copied()
+                .collect::<SmallVec<[_; 4]>>()
+        });
+
+        for peer_id in left_peers {
+            self.integrate_peer_left(peer_id, ctx);
+        }
+
+        for peer in other_peers {
+            if self.remote_peers.get(peer.id).is_none() {
+                self.integrate_peer_joined(peer, ctx);
+            }
+        }
+
+        if new_host_id != old_host_id {
+            let new_host = self
+                .peers::<SmallVec<[_; 4]>>()
+                .into_iter()
+                .find(|peer| peer.id == new_host_id)
+                .expect("the host is always in the session");
+            Ed::on_host_changed(&new_host, self, ctx);
+        }
+
+        if self.local_peer.id == old_local_id {
+            SmallVec::new()
+        } else {
+            self.change_local_id(old_local_id)
+        }
+    }
+
     /// Returns the project's name.
-    #[cfg(feature = "neovim")]
     pub(crate) fn name(&self) -> &abs_path::NodeName {
         self.root_path()
             .node_name()
             .expect("project can't be rooted at fs root")
     }
 
+    /// Returns the response to the given request for the project.
+    pub(crate) fn project_response(
+        &self,
+        request: collab_types::ProjectRequest,
+    ) -> collab_types::ProjectResponse {
+        collab_types::ProjectResponse {
+            peers: self.peers(),
+            encoded_project: self.inner.encode(),
+            response_id: request.request_id.into(),
+        }
+    }
+
+    /// Reverts the last step in the local peer's undo or redo history of the
+    /// text file backing the buffer with the given ID.
+    ///
+    /// Returns the message to send to the other peers, `Some(None)` if there's
+    /// nothing to revert, or `None` if the buffer is not backed by a text file
+    /// in the project.
+    pub(crate) async fn revert_local_edit(
+        &mut self,
+        buffer_id: Ed::BufferId,
+        revert: Revert,
+        ctx: &mut Context<Ed>,
+    ) -> Option<Option<Message>> {
+        let &file_id = self.id_maps.buffer2file.get(&buffer_id)?;
+
+        let Some(FileMut::Text(mut file_mut)) = self.inner.file_mut(file_id)
+        else {
+            return None;
+        };
+
+        let local_id = self.inner.peer_id();
+
+        let Some((text_edit, replacements)) =
+            self.undo_history.revert(&mut file_mut, local_id, revert)
+        else {
+            return Some(None);
+        };
+
+        ctx.with_borrowed(|ctx| {
+            ctx.buffer(buffer_id.clone())
+                .expect("buffer exists")
+                .schedule_edit(
+                    replacements.into_iter().map(Convert::convert),
+                    self.agent_id,
+                )
+                .boxed_local()
+        })
+        .await;
+
+        let Some(File::Text(file)) = self.inner.file(file_id) else {
+            unreachable!("we know this ID maps to a text file");
+        };
+
+        Self::update_cursors(&file, &mut self.peer_cursors, ctx);
+        Self::update_selections(&file, &mut self.peer_selections, ctx);
+
+        if let Some(blame) = self.blames.remove(&buffer_id) {
+            Ed::remove_buffer_blame(blame, ctx);
+        }
+
+        Some(Some(Message::EditedText(text_edit)))
+    }
+
     /// Returns the project root's path.
     pub(crate) fn root_path(&self) -> &AbsPath {
         &self.root_path
     }
 
-    /// TODO: docs.
-    pub(crate) async fn synchronize(
+    /// Makes all the buffers backed by a file in the project read-only if
+    /// `read_only` is true, or modifiable otherwise.
+    pub(crate) fn set_buffers_read_only(
+        &self,
+        read_only: bool,
+        ctx: &mut Context<Ed>,
+    ) {
+        for buffer_id in self.id_maps.buffer2file.keys() {
+            Ed::set_buffer_read_only(buffer_id.clone(), read_only, ctx);
+        }
+    }
+
+    /// Synchronizes the project with an event emitted by the editor,
+    /// returning the message to send to the other peers, if any.
+    pub async fn synchronize(
         &mut self,
         event: Event<Ed>,
         ctx: &mut Context<Ed>,
     ) -> Result<Option<Message>, SynchronizeError<Ed>> {
         match event {
             Event::Buffer(event) => Ok(self.synchronize_buffer(event, ctx)),
-            Event::Cursor(event) => Ok(Some(self.synchronize_cursor(event))),
+            Event::Cursor(event) => {
+                self.stop_following_if_moved_by_user(&event, ctx);
+                Ok(Some(self.synchronize_cursor(event)))
+            },
             Event::Directory(event) => {
                 self.synchronize_directory(event, ctx).await
             },
@@ -338,6 +556,46 @@ impl<Ed: CollabEditor> Project<Ed> {
         }
     }
 
+    /// Recomputes the activity of all the remote peers as of the given
+    /// instant, updating the tooltips of the ones whose activity changed
+    /// since the last call.
+    pub fn update_peer_activities(
+        &mut self,
+        now: Instant,
+        ctx: &mut Context<Ed>,
+    ) {
+        for peer in self.remote_peers.update_activities(now) {
+            Self::render_peer_activity(&peer, &mut self.peer_cursors, ctx);
+        }
+    }
+
+    /// Refreshes the cached [`position`](RemotePeer::position) of all the
+    /// remote peers.
+    pub(crate) fn update_peer_positions(&self) {
+        self.remote_peers
+            .update_positions(|cursor_id| self.cursor_position(cursor_id));
+    }
+
+    /// Returns the position of the cursor with the given ID, or `None` if
+    /// there's no such cursor in the project.
+    pub(crate) fn cursor_position(
+        &self,
+        cursor_id: CursorId,
+    ) -> Option<PeerPosition> {
+        let cursor = self.inner.cursor(cursor_id)?;
+        let file = cursor.file();
+        Some(PeerPosition {
+            file_path: self.root_path.concat(&file.path()),
+            line_idx: file.contents().line_of_byte(cursor.offset()),
+        })
+    }
+
+    /// Returns whether the local peer has made itself read-only, in which
+    /// case neither its buffers nor its agents can modify the project.
+    pub(crate) fn is_read_only(&self) -> bool {
+        self.permissions.with(Permissions::is_read_only)
+    }
+
     /// Returns the [`text::CursorMut`] corresponding to the cursor with the
     /// given ID.
     #[track_caller]
@@ -363,6 +621,77 @@ impl<Ed: CollabEditor> Project<Ed> {
         }
     }
 
+    /// Forks the project under the local peer's current ID after it changed
+    /// from the given one, re-creating the local cursors and selections so
+    /// that they're owned by the new ID.
+    fn change_local_id(
+        &mut self,
+        old_local_id: PeerId,
+    ) -> SmallVec<[Message; 2]> {
+        let cursors = self
+            .id_maps
+            .cursor2cursor
+            .iter()
+            .filter_map(|(editor_id, &cursor_id)| {
+                let cursor = self.inner.cursor(cursor_id)?;
+                let file_id = cursor.file().local_id();
+                Some((editor_id.clone(), file_id, cursor.offset()))
+            })
+            .collect::<SmallVec<[_; 2]>>();
+
+        let selections = self
+            .id_maps
+            .selection2selection
+            .iter()
+            .filter_map(|(editor_id, &selection_id)| {
+                let selection = self.inner.selection(selection_id)?;
+                let file_id = selection.file()?.local_id();
+                Some((editor_id.clone(), file_id, selection.offset_range()))
+            })
+            .collect::<SmallVec<[_; 2]>>();
+
+        // Forking preserves the local IDs of files and directories, so the
+        // ID maps are still valid after this.
+        self.inner = self.inner.fork(self.local_peer.id);
+
+        // The other peers have already removed them when the server told them
+        // that the old ID left.
+        let _ = self.inner.integrate_peer_disconnection(old_local_id);
+
+        self.id_maps.cursor2cursor.clear();
+        self.id_maps.selection2selection.clear();
+
+        let mut messages = SmallVec::new();
+
+        for (editor_id, file_id, offset) in cursors {
+            let Some(FileMut::Text(mut file)) = self.inner.file_mut(file_id)
+            else {
+                continue;
+            };
+            let (cursor_id, creation) = file.create_cursor(offset);
+            self.id_maps.cursor2cursor.insert(editor_id, cursor_id);
+            messages.push(Message::CreatedCursor(creation));
+        }
+
+        for (editor_id, file_id, offset_range) in selections {
+            let Some(FileMut::Text(mut file)) = self.inner.file_mut(file_id)
+            else {
+                continue;
+            };
+            let (selection_id, creation) = file.create_selection(offset_range);
+            self.id_maps.selection2selection.insert(editor_id, selection_id);
+            messages.push(Message::CreatedSelection(creation));
+        }
+
+        messages
+    }
+
+    /// Returns the ID of the main cursor of the remote peer that's currently
+    /// being followed, if any.
+    fn followed_cursor(&self) -> Option<CursorId> {
+        self.remote_peers.followed()?.main_cursor()
+    }
+
     async fn integrate_binary_edit(
         &mut self,
         edit: binary::BinaryEdit,
@@ -376,6 +705,16 @@ impl<Ed: CollabEditor> Project<Ed> {
         let file_path = self.root_path.clone().concat(file.path());
         let new_contents = file.contents().to_owned();
 
+        Self::write_to_disk(file_path, new_contents, ctx).await
+    }
+
+    /// Overwrites the contents of the file at the given path with the given
+    /// contents.
+    async fn write_to_disk(
+        file_path: AbsPathBuf,
+        new_contents: impl AsRef<[u8]> + Send + 'static,
+        ctx: &mut Context<Ed>,
+    ) -> Result<(), IntegrateBinaryEditError<Ed::Fs>> {
         let fs = ctx.fs();
 
         ctx.spawn_background(async move {
@@ -418,6 +757,10 @@ impl<Ed: CollabEditor> Project<Ed> {
         let mut try_block = || {
             let cursor = self.inner.integrate_cursor_creation(creation)?;
 
+            if let Some(peer) = self.remote_peers.mark_active(cursor.owner()) {
+                Self::render_peer_activity(&peer, &mut self.peer_cursors, ctx);
+            }
+
             let cursor_owner = self.remote_peers.with_mut(|map| {
                 let peer = map.get_mut(&cursor.owner())?;
 
@@ -427,6 +770,8 @@ impl<Ed: CollabEditor> Project<Ed> {
                     peer.set_main_cursor(cursor.id());
                 }
 
+                peer.focus_file(cursor.file().local_id());
+
                 Some(peer.clone())
             })?;
 
@@ -462,6 +807,8 @@ impl<Ed: CollabEditor> Project<Ed> {
                 Ed::remove_peer_tooltip(tooltip, ctx);
             }
 
+            self.mark_peer_active(cursor_id.owner(), ctx);
+
             // Update the cursor owner's main cursor.
             self.remote_peers.with_mut(|map| {
                 let Some(owner) = map.get_mut(&cursor_id.owner()) else {
@@ -485,19 +832,20 @@ impl<Ed: CollabEditor> Project<Ed> {
         try_block();
     }
 
+    /// Integrates the movement of a remote cursor, returning its ID if the
+    /// movement was integrated successfully.
     fn integrate_cursor_move(
         &mut self,
         movement: text::CursorMove,
         ctx: &mut Context<Ed>,
-    ) {
-        let mut try_block = || {
-            let cursor = self.inner.integrate_cursor_move(movement)?;
-            let tooltip = self.peer_cursors.get_mut(&cursor.id())?;
-            Ed::move_peer_tooltip(tooltip, cursor.offset(), ctx);
-            Some(())
-        };
-
-        try_block();
+    ) -> Option<CursorId> {
+        let cursor = self.inner.integrate_cursor_move(movement)?;
+        let (cursor_id, cursor_offset) = (cursor.id(), cursor.offset());
+        self.mark_peer_active(cursor_id.owner(), ctx);
+        if let Some(tooltip) = self.peer_cursors.get_mut(&cursor_id) {
+            Ed::move_peer_tooltip(tooltip, cursor_offset, ctx);
+        }
+        Some(cursor_id)
     }
 
     fn integrate_file_save(
@@ -560,11 +908,26 @@ impl<Ed: CollabEditor> Project<Ed> {
     }
 
     fn integrate_peer_joined(&self, peer: Peer, ctx: &mut Context<Ed>) {
+        // Agents are added as soon as they connect, which is before the
+        // server tells us they joined.
+        if peer.id == self.local_peer.id
+            || self.remote_peers.get(peer.id).is_some()
+        {
+            return;
+        }
+
         Ed::on_peer_joined(&peer, self, ctx);
         self.remote_peers.insert(peer, &self.inner);
     }
 
-    fn integrate_peer_left(&mut self, peer_id: PeerId, ctx: &mut Context<Ed>) {
+    /// Integrates a remote peer leaving the session, removing all its cursors
+    /// and selections and electing a new host if the peer was the current
+    /// one.
+    pub fn integrate_peer_left(
+        &mut self,
+        peer_id: PeerId,
+        ctx: &mut Context<Ed>,
+    ) {
         let peer = self.remote_peers.remove(peer_id);
 
         let (cursor_ids, selection_ids) =
@@ -584,6 +947,12 @@ impl<Ed: CollabEditor> Project<Ed> {
         }
 
         Ed::on_peer_left(&peer, self, ctx);
+
+        if peer_id == self.host_id.copied() {
+            let new_host = self.elect_host();
+            self.host_id.set(new_host.id);
+            Ed::on_host_changed(&new_host, self, ctx);
+        }
     }
 
     fn integrate_selection_creation(
@@ -594,6 +963,11 @@ impl<Ed: CollabEditor> Project<Ed> {
         let mut try_block = || {
             let selection =
                 self.inner.integrate_selection_creation(creation)?;
+            if let Some(peer) =
+                self.remote_peers.mark_active(selection.owner())
+            {
+                Self::render_peer_activity(&peer, &mut self.peer_cursors, ctx);
+            }
             let file_id = selection.file()?.local_id();
             let buffer_id = self.id_maps.file2buffer.get(&file_id)?;
             let selection_owner = self.remote_peers.get(selection.owner())?;
@@ -617,6 +991,7 @@ impl<Ed: CollabEditor> Project<Ed> {
     ) {
         let mut try_block = || {
             let sel_id = self.inner.integrate_selection_removal(deletion)?;
+            self.mark_peer_active(sel_id.owner(), ctx);
             let peer_selection = self.peer_selections.remove(&sel_id)?;
             Ed::remove_peer_selection(peer_selection, ctx);
             Some(())
@@ -632,6 +1007,11 @@ impl<Ed: CollabEditor> Project<Ed> {
     ) {
         let mut try_block = || {
             let selection = self.inner.integrate_selection_move(movement)?;
+            if let Some(peer) =
+                self.remote_peers.mark_active(selection.owner())
+            {
+                Self::render_peer_activity(&peer, &mut self.peer_cursors, ctx);
+            }
             let peer_selection =
                 self.peer_selections.get_mut(&selection.id())?;
             Ed::move_peer_selection(
@@ -654,7 +1034,7 @@ impl<Ed: CollabEditor> Project<Ed> {
         &mut self,
         edit: text::TextEdit,
         ctx: &mut Context<Ed>,
-    ) -> Result<Option<Ed::BufferId>, Ed::CreateBufferError> {
+    ) -> Result<Option<Ed::BufferId>, IntegrateTextEditError<Ed>> {
         let Some((file, replacements)) = self.inner.integrate_text_edit(edit)
         else {
             return Ok(None);
@@ -663,13 +1043,23 @@ impl<Ed: CollabEditor> Project<Ed> {
         let file_id = file.local_id();
 
         // If there's already an open buffer for the edited file we can just
-        // apply the replacements to it. If not, we have to first create one.
+        // apply the replacements to it. If not, we have to first create one,
+        // unless the editor wants the edit to be written straight to disk.
         let buffer_id = match self.id_maps.file2buffer.get(&file_id) {
             Some(buffer_id) => buffer_id.clone(),
             None => {
                 let file_path = self.root_path.clone().concat(file.path());
-                let buffer_id =
-                    ctx.create_buffer(&file_path, self.agent_id).await?;
+                if !Ed::should_remote_edit_create_buffer(&file_path, ctx) {
+                    let new_contents = file.as_file().contents().to_string();
+                    return Self::write_to_disk(file_path, new_contents, ctx)
+                        .await
+                        .map(|()| None)
+                        .map_err(IntegrateTextEditError::WriteToDisk);
+                }
+                let buffer_id = ctx
+                    .create_buffer(&file_path, self.agent_id)
+                    .await
+                    .map_err(IntegrateTextEditError::CreateBuffer)?;
                 self.synchronize_buffer_created(
                     buffer_id.clone(),
                     &file_path,
@@ -697,9 +1087,36 @@ impl<Ed: CollabEditor> Project<Ed> {
         Self::update_cursors(&file, &mut self.peer_cursors, ctx);
         Self::update_selections(&file, &mut self.peer_selections, ctx);
 
+        if let Some(blame) = self.blames.remove(&buffer_id) {
+            Ed::remove_buffer_blame(blame, ctx);
+        }
+
         Ok(Some(buffer_id))
     }
 
+    /// Picks the new host of the session among the peers that are currently
+    /// in it.
+    ///
+    /// See [`elect_host`] for how the new host is picked.
+    fn elect_host(&self) -> Peer {
+        let peers = self.peers::<SmallVec<[_; 4]>>();
+        elect_host(&peers)
+            .cloned()
+            .expect("the local peer is always in the session")
+    }
+
+    /// Jumps to the given cursor, which is the main cursor of the remote peer
+    /// that's currently being followed.
+    async fn jump_to_followed(
+        &self,
+        cursor_id: CursorId,
+        ctx: &mut Context<Ed>,
+    ) {
+        if let Err(err) = Jump::jump_to(self, cursor_id, ctx).await {
+            Ed::on_follow_error(FollowError::Jump(err), ctx);
+        }
+    }
+
     fn map_peers<T, Collector: FromIterator<T>>(
         &self,
         fun: impl FnMut(&Peer) -> T,
@@ -714,6 +1131,14 @@ impl<Ed: CollabEditor> Project<Ed> {
         })
     }
 
+    /// Records that the remote peer with the given ID just interacted with
+    /// the project.
+    fn mark_peer_active(&mut self, peer_id: PeerId, ctx: &mut Context<Ed>) {
+        if let Some(peer) = self.remote_peers.mark_active(peer_id) {
+            Self::render_peer_activity(&peer, &mut self.peer_cursors, ctx);
+        }
+    }
+
     fn peers<Collector: FromIterator<Peer>>(&self) -> Collector {
         self.map_peers(Clone::clone)
     }
@@ -740,6 +1165,21 @@ impl<Ed: CollabEditor> Project<Ed> {
         }
     }
 
+    /// Updates the tooltips of all the given peer's cursors to reflect their
+    /// current activity.
+    fn render_peer_activity(
+        peer: &RemotePeer,
+        peer_cursors: &mut FxHashMap<CursorId, Ed::PeerTooltip>,
+        ctx: &mut Context<Ed>,
+    ) {
+        let activity = peer.activity();
+        for (cursor_id, tooltip) in peer_cursors {
+            if cursor_id.owner() == peer.id {
+                Ed::set_peer_tooltip_activity(tooltip, activity, ctx);
+            }
+        }
+    }
+
     /// Returns the [`text::SelectionMut`] corresponding to the selection with
     /// the given ID.
     #[track_caller]
@@ -773,6 +1213,30 @@ impl<Ed: CollabEditor> Project<Ed> {
         }
     }
 
+    /// Stops following the followed remote peer (if any) if the given event
+    /// was caused by the local user creating or moving one of their cursors.
+    fn stop_following_if_moved_by_user(
+        &self,
+        event: &event::CursorEvent<Ed>,
+        ctx: &mut Context<Ed>,
+    ) {
+        if matches!(event.kind, event::CursorEventKind::Removed) {
+            return;
+        }
+
+        // Cursor movements caused by jumping to the followed peer or by
+        // integrating remote edits shouldn't stop following.
+        if event.agent_id == self.agent_id
+            || event.agent_id == Jump::agent_id(ctx)
+        {
+            return;
+        }
+
+        if let Some(peer) = self.remote_peers.set_followed(None) {
+            Ed::on_stopped_following(&peer, ctx);
+        }
+    }
+
     /// Synchronizes the project's state with the given buffer event.
     pub fn synchronize_buffer(
         &mut self,
@@ -821,17 +1285,25 @@ impl<Ed: CollabEditor> Project<Ed> {
         self.id_maps.buffer2file.insert(buffer_id.clone(), file.local_id());
         self.id_maps.file2buffer.insert(file.local_id(), buffer_id.clone());
 
+        if self.is_read_only() {
+            Ed::set_buffer_read_only(buffer_id.clone(), true, ctx);
+        }
+
         // Display the cursors of the remote peers in the buffer.
         for cursor in file.cursors() {
             let Some(owner) = self.remote_peers.get(cursor.owner()) else {
                 continue;
             };
-            let tooltip = Ed::create_peer_tooltip(
+            let activity = owner.activity();
+            let mut tooltip = Ed::create_peer_tooltip(
                 owner.into(),
                 cursor.offset(),
                 buffer_id.clone(),
                 ctx,
             );
+            if activity != PeerActivity::Active {
+                Ed::set_peer_tooltip_activity(&mut tooltip, activity, ctx);
+            }
             self.peer_cursors.insert(cursor.id(), tooltip);
         }
 
@@ -859,6 +1331,8 @@ impl<Ed: CollabEditor> Project<Ed> {
 
         self.id_maps.file2buffer.remove(&file_id);
 
+        self.blames.remove(&buffer_id);
+
         let Some(File::Text(file)) = self.inner.file(file_id) else {
             unreachable!("we know this ID maps to a text file");
         };
@@ -878,12 +1352,20 @@ impl<Ed: CollabEditor> Project<Ed> {
         replacements: impl IntoIterator<Item = editor::Replacement>,
         ctx: &mut Context<Ed>,
     ) -> Option<Message> {
-        let mut file_mut = self.text_file_of_buffer(&buffer_id);
+        let Some(&file_id) = self.id_maps.buffer2file.get(&buffer_id) else {
+            panic!("unknown buffer ID: {buffer_id:?}");
+        };
 
-        let text_edit =
-            file_mut.edit(replacements.into_iter().map(Convert::convert));
+        let Some(FileMut::Text(mut file_mut)) = self.inner.file_mut(file_id)
+        else {
+            panic!("buffer ID {buffer_id:?} doesn't map to a text file")
+        };
 
-        let file_id = file_mut.local_id();
+        let text_edit = self.undo_history.edit(
+            &mut file_mut,
+            replacements.into_iter().map(Convert::convert),
+            Instant::now(),
+        );
 
         let Some(File::Text(file)) = self.inner.file(file_id) else {
             unreachable!("we know this ID maps to a text file");
@@ -892,6 +1374,10 @@ impl<Ed: CollabEditor> Project<Ed> {
         Self::update_cursors(&file, &mut self.peer_cursors, ctx);
         Self::update_selections(&file, &mut self.peer_selections, ctx);
 
+        if let Some(blame) = self.blames.remove(&buffer_id) {
+            Ed::remove_buffer_blame(blame, ctx);
+        }
+
         Some(Message::EditedText(text_edit))
     }
 
@@ -1024,7 +1510,13 @@ impl<Ed: CollabEditor> Project<Ed> {
                     (*lhs != *rhs).then_some(FileDiff::Binary(rhs))
                 },
                 (FileContents::Text(lhs), FsNodeContents::Text(rhs)) => {
-                    text_diff(lhs, &rhs).map(FileDiff::Text)
+                    let lhs = lhs.to_string();
+                    text_diff(&lhs, &rhs).map(|(deleted_range, text)| {
+                        FileDiff::Text(smallvec![TextReplacement {
+                            deleted_range,
+                            inserted_text: text.into(),
+                        }])
+                    })
                 },
                 _ => None,
             })
@@ -1287,6 +1779,40 @@ impl<Ed: CollabEditor> Project<Ed> {
     }
 }
 
+/// Deterministically picks the host of a session among the given peers.
+///
+/// Every peer receives the same sequence of joins and leaves from the server,
+/// and the server assigns [`PeerId`]s in increasing order, so picking the peer
+/// with the smallest ID guarantees that all of them will agree on the host
+/// without having to exchange any additional messages. This also holds for
+/// peers joining after an election: the elected host has a smaller ID than
+/// every peer that joined after it was elected, so running the election over
+/// the peers that are in the session when joining picks the same host.
+pub fn elect_host<'a>(
+    peers: impl IntoIterator<Item = &'a Peer>,
+) -> Option<&'a Peer> {
+    peers.into_iter().min_by_key(|peer| peer.id)
+}
+
+/// Returns the ID of the current host of a session we've just (re-)joined,
+/// given the host known to the server and the other peers in the session.
+///
+/// The server only knows about the peer that started the session, so if that
+/// peer has left we run the same election the other peers ran when it did.
+pub(crate) fn current_host_id(
+    server_host_id: PeerId,
+    other_peers: &[Peer],
+    local_peer: &Peer,
+) -> PeerId {
+    if other_peers.iter().any(|peer| peer.id == server_host_id) {
+        return server_host_id;
+    }
+
+    elect_host(other_peers.iter().chain(iter::once(local_peer)))
+        .expect("the local peer is always in the session")
+        .id
+}
+
 mod impl_integrate_fs_op {
     //! Contains the various types, free-standing functions and methods used in
     //! the implementation of [`ProjectHandle::integrate_fs_op`].
@@ -1716,11 +2242,70 @@ trait FsExt: fs::Fs {
 
 impl<Fs: fs::Fs> FsExt for Fs {}
 
-fn text_diff(
-    _lhs: crop::Rope,
-    _rhs: &str,
-) -> Option<SmallVec<[TextReplacement; 1]>> {
-    todo!();
+/// Returns the ID of the peer that created the given message, if the message
+/// modifies the project.
+fn message_author(message: &Message) -> Option<PeerId> {
+    let author_id = match message {
+        Message::CreatedDirectory(creation) => creation.performed_by(),
+        Message::CreatedFile(creation) => creation.performed_by(),
+        Message::DeletedDirectory(deletion) => deletion.performed_by(),
+        Message::DeletedFile(deletion) => deletion.performed_by(),
+        Message::MovedDirectory(movement) => movement.performed_by(),
+        Message::MovedFile(movement) => movement.performed_by(),
+        Message::RenamedFsNode(rename) => rename.performed_by(),
+        Message::EditedBinary(edit) => return Some(edit.timestamp.edited_by),
+        Message::EditedText(edit) => edit
+            .insertions
+            .first()
+            .map(|(insertion, _)| insertion.text().inserted_by())
+            .or_else(|| {
+                edit.deletions.first().map(|deletion| deletion.deleted_by())
+            })?,
+        _ => return None,
+    };
+
+    Some(PeerId::new(author_id))
+}
+
+/// Returns the single replacement that turns `old` into `new`, or `None` if
+/// they're equal.
+///
+/// The replacement only spans the bytes between the longest common prefix
+/// and the longest common suffix of the two strings.
+pub(crate) fn text_diff<'new>(
+    old: &str,
+    new: &'new str,
+) -> Option<(Range<usize>, &'new str)> {
+    if old == new {
+        return None;
+    }
+
+    let mut prefix_len =
+        old.bytes().zip(new.bytes()).take_while(|(l, r)| l == r).count();
+
+    while !old.is_char_boundary(prefix_len)
+        || !new.is_char_boundary(prefix_len)
+    {
+        prefix_len -= 1;
+    }
+
+    let mut suffix_len = old.as_bytes()[prefix_len..]
+        .iter()
+        .rev()
+        .zip(new.as_bytes()[prefix_len..].iter().rev())
+        .take_while(|(l, r)| l == r)
+        .count();
+
+    while !old.is_char_boundary(old.len() - suffix_len)
+        || !new.is_char_boundary(new.len() - suffix_len)
+    {
+        suffix_len -= 1;
+    }
+
+    Some((
+        prefix_len..old.len() - suffix_len,
+        &new[prefix_len..new.len() - suffix_len],
+    ))
 }
 
 impl Messages {
@@ -1746,3 +2331,38 @@ impl Iterator for Messages {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_diff_equal() {
+        assert_eq!(text_diff("foo", "foo"), None);
+    }
+
+    #[test]
+    fn text_diff_insertion() {
+        assert_eq!(
+            text_diff("hello world", "hello, world"),
+            Some((5..5, ","))
+        );
+    }
+
+    #[test]
+    fn text_diff_deletion() {
+        assert_eq!(text_diff("hello world", "hello"), Some((5..11, "")));
+    }
+
+    #[test]
+    fn text_diff_repeated_chars() {
+        // The common prefix and suffix must not overlap.
+        assert_eq!(text_diff("aaa", "aaaa"), Some((3..3, "a")));
+    }
+
+    #[test]
+    fn text_diff_multibyte_chars() {
+        // 'é' and 'è' share their first byte, which must not be split.
+        assert_eq!(text_diff("café", "cafè"), Some((3..5, "è")));
+    }
+}
@@ -0,0 +1,292 @@
+//! Contains read-only functions exposing the state of the current sessions,
+//! so that other plugins (statuslines, pickers, dashboards, etc.) can query
+//! it.
+//!
+//! Unlike the other actions, these are only available as functions and not
+//! as commands.
+
+use collab_project::fs::{Directory, File, Node};
+use collab_types::PeerId;
+use editor::context::Borrowed;
+use editor::module::Action;
+use editor::{Access, Context, command};
+
+use crate::collab::Collab;
+use crate::editors::{CollabEditor, SessionId};
+use crate::peers::{PeerActivity, RemotePeer};
+use crate::project::Project;
+use crate::session::{SessionInfos, Sessions};
+
+/// A function returning a [`SessionSummary`] for each of the current
+/// sessions.
+#[derive(cauchy::Clone)]
+pub struct QuerySessions<Ed: CollabEditor> {
+    sessions: Sessions<Ed>,
+}
+
+/// A function returning a [`PeerSummary`] for each of the remote peers in the
+/// session with the given ID, or `nil` if there's no such session.
+#[derive(cauchy::Clone)]
+pub struct QueryPeers<Ed: CollabEditor> {
+    sessions: Sessions<Ed>,
+}
+
+/// A function returning a [`FileSummary`] for each of the files in the
+/// project of the session with the given ID, or `nil` if there's no such
+/// session.
+#[derive(cauchy::Clone)]
+pub struct QueryFiles<Ed: CollabEditor> {
+    sessions: Sessions<Ed>,
+}
+
+/// A session as returned by [`QuerySessions`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct SessionSummary {
+    /// The session's ID.
+    pub id: String,
+
+    /// The path to the root of the session's project.
+    pub project_root: String,
+
+    /// The handle of the session's current host.
+    pub host: String,
+
+    /// The handle of the local peer.
+    pub local_peer: String,
+
+    /// The handles of the remote peers in the session.
+    pub peers: Vec<String>,
+}
+
+/// A remote peer as returned by [`QueryPeers`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct PeerSummary {
+    /// The peer's handle.
+    pub handle: String,
+
+    /// The peer's ID.
+    pub id: u64,
+
+    /// Whether the peer is the session's current host.
+    pub is_host: bool,
+
+    /// Whether the peer is active, idle or away.
+    pub activity: PeerActivity,
+
+    /// The number of seconds since the peer last interacted with the project.
+    pub idle_secs: u64,
+
+    /// The path of the file the peer's main cursor is in, or `None` if the
+    /// peer doesn't have a cursor in the project.
+    pub file: Option<String>,
+
+    /// The 1-based line the peer's main cursor is on, or `None` if the peer
+    /// doesn't have a cursor in the project.
+    pub line: Option<usize>,
+}
+
+/// A file as returned by [`QueryFiles`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct FileSummary {
+    /// The path of the file.
+    pub path: String,
+
+    /// The kind of the file.
+    pub kind: FileKind,
+}
+
+/// The kind of a [`FileSummary`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileKind {
+    /// A binary file.
+    Binary,
+
+    /// A symbolic link.
+    Symlink,
+
+    /// A text file.
+    Text,
+}
+
+impl<Ed: CollabEditor> QuerySessions<Ed> {
+    pub(crate) fn call_inner(&self) -> Vec<SessionSummary> {
+        let mut summaries = Vec::new();
+        self.sessions.for_each(|infos| {
+            summaries.push(SessionSummary::new(infos));
+        });
+        summaries.sort_by(|lhs, rhs| lhs.project_root.cmp(&rhs.project_root));
+        summaries
+    }
+}
+
+impl<Ed: CollabEditor> QueryPeers<Ed> {
+    pub(crate) fn call_inner(
+        &self,
+        session_id: SessionId<Ed>,
+    ) -> Option<Vec<PeerSummary>> {
+        let infos = self.sessions.get(session_id)?;
+        let host_id = infos.host_id();
+        let mut summaries = infos.remote_peers.with(|map| {
+            map.values()
+                .map(|peer| PeerSummary::new(peer, host_id))
+                .collect::<Vec<_>>()
+        });
+        summaries.sort_by(|lhs, rhs| lhs.handle.cmp(&rhs.handle));
+        Some(summaries)
+    }
+}
+
+impl<Ed: CollabEditor> QueryFiles<Ed> {
+    pub(crate) fn call_inner(
+        &self,
+        session_id: SessionId<Ed>,
+    ) -> Option<Vec<FileSummary>> {
+        let infos = self.sessions.get(session_id)?;
+        Some(infos.files.with(Clone::clone))
+    }
+}
+
+impl SessionSummary {
+    fn new<Ed: CollabEditor>(infos: &SessionInfos<Ed>) -> Self {
+        let host_id = infos.host_id();
+
+        let host = if host_id == infos.local_peer.id {
+            Some(infos.local_peer.handle.as_str().to_owned())
+        } else {
+            infos
+                .remote_peers
+                .get(host_id)
+                .map(|host| host.handle.as_str().to_owned())
+        };
+
+        let mut peers = Vec::new();
+        infos.remote_peers.for_each(|peer| {
+            peers.push(peer.handle.as_str().to_owned());
+        });
+        peers.sort();
+
+        Self {
+            id: infos.id().to_string(),
+            project_root: infos.project_root_path.to_string(),
+            host: host.unwrap_or_default(),
+            local_peer: infos.local_peer.handle.as_str().to_owned(),
+            peers,
+        }
+    }
+}
+
+impl PeerSummary {
+    fn new(peer: &RemotePeer, host_id: PeerId) -> Self {
+        let position = peer.position();
+
+        Self {
+            handle: peer.handle.as_str().to_owned(),
+            id: peer.id.into_u64(),
+            is_host: peer.id == host_id,
+            activity: peer.activity(),
+            idle_secs: peer.idle_time().as_secs(),
+            file: position.map(|pos| pos.file_path.to_string()),
+            line: position.map(|pos| pos.line_idx + 1),
+        }
+    }
+}
+
+impl<Ed: CollabEditor> Action<Ed> for QuerySessions<Ed> {
+    const NAME: &str = "sessions";
+
+    type Args<'args> = ();
+    type Return = Vec<SessionSummary>;
+
+    fn call(
+        &mut self,
+        (): Self::Args<'_>,
+        _: &mut Context<Ed, Borrowed<'_>>,
+    ) -> Self::Return {
+        self.call_inner()
+    }
+}
+
+impl<Ed: CollabEditor> Action<Ed> for QueryPeers<Ed> {
+    const NAME: &str = "peers";
+
+    type Args<'args> = command::Parse<SessionId<Ed>>;
+    type Return = Option<Vec<PeerSummary>>;
+
+    fn call(
+        &mut self,
+        command::Parse(session_id): Self::Args<'_>,
+        _: &mut Context<Ed, Borrowed<'_>>,
+    ) -> Self::Return {
+        self.call_inner(session_id)
+    }
+}
+
+impl<Ed: CollabEditor> Action<Ed> for QueryFiles<Ed> {
+    const NAME: &str = "files";
+
+    type Args<'args> = command::Parse<SessionId<Ed>>;
+    type Return = Option<Vec<FileSummary>>;
+
+    fn call(
+        &mut self,
+        command::Parse(session_id): Self::Args<'_>,
+        _: &mut Context<Ed, Borrowed<'_>>,
+    ) -> Self::Return {
+        self.call_inner(session_id)
+    }
+}
+
+impl<Ed: CollabEditor> From<&Collab<Ed>> for QuerySessions<Ed> {
+    fn from(collab: &Collab<Ed>) -> Self {
+        Self { sessions: collab.sessions.clone() }
+    }
+}
+
+impl<Ed: CollabEditor> From<&Collab<Ed>> for QueryPeers<Ed> {
+    fn from(collab: &Collab<Ed>) -> Self {
+        Self { sessions: collab.sessions.clone() }
+    }
+}
+
+impl<Ed: CollabEditor> From<&Collab<Ed>> for QueryFiles<Ed> {
+    fn from(collab: &Collab<Ed>) -> Self {
+        Self { sessions: collab.sessions.clone() }
+    }
+}
+
+/// Returns a [`FileSummary`] for each of the files in the given project.
+pub(crate) fn file_summaries<Ed: CollabEditor>(
+    proj: &Project<Ed>,
+) -> Vec<FileSummary> {
+    let mut summaries = Vec::new();
+    push_files(proj, proj.inner.root(), &mut summaries);
+    summaries
+}
+
+/// Pushes the paths and kinds of all the files under the given directory.
+fn push_files<Ed: CollabEditor>(
+    proj: &Project<Ed>,
+    dir: Directory<'_>,
+    summaries: &mut Vec<FileSummary>,
+) {
+    for node in dir.children() {
+        let file = match node {
+            Node::Directory(dir) => {
+                push_files(proj, dir, summaries);
+                continue;
+            },
+            Node::File(file) => file,
+        };
+
+        let kind = match &file {
+            File::Binary(_) => FileKind::Binary,
+            File::Symlink(_) => FileKind::Symlink,
+            File::Text(_) => FileKind::Text,
+        };
+
+        let path = proj.root_path().concat(&file.path());
+
+        summaries.push(FileSummary { path: path.to_string(), kind });
+    }
+}
@@ -0,0 +1,57 @@
+//! TODO: docs.
+
+use editor::Context;
+use editor::command::ToCompletionFn;
+use editor::module::AsyncAction;
+
+use crate::collab::Collab;
+use crate::editors::{ActionForSelectedSession, CollabEditor};
+use crate::permissions::{self, PermissionsError};
+use crate::session::Sessions;
+
+/// The `Action` used to make the local peer read-only in a session, e.g. to
+/// follow it as a spectator without risking modifying the project.
+///
+/// The buffers of the project are made non-modifiable until the
+/// [`ReadWrite`](crate::read_write::ReadWrite) action is called.
+#[derive(cauchy::Clone)]
+pub struct ReadOnly<Ed: CollabEditor> {
+    sessions: Sessions<Ed>,
+}
+
+impl<Ed: CollabEditor> ReadOnly<Ed> {
+    pub(crate) async fn call_inner(
+        &self,
+        ctx: &mut Context<Ed>,
+    ) -> Result<(), PermissionsError<Ed>> {
+        permissions::set_read_only(
+            &self.sessions,
+            true,
+            ActionForSelectedSession::ReadOnly,
+            ctx,
+        )
+        .await
+    }
+}
+
+impl<Ed: CollabEditor> AsyncAction<Ed> for ReadOnly<Ed> {
+    const NAME: &str = "read-only";
+
+    type Args = ();
+
+    async fn call(&mut self, _: Self::Args, ctx: &mut Context<Ed>) {
+        if let Err(err) = self.call_inner(ctx).await {
+            Ed::on_permissions_error(err, ctx);
+        }
+    }
+}
+
+impl<Ed: CollabEditor> From<&Collab<Ed>> for ReadOnly<Ed> {
+    fn from(collab: &Collab<Ed>) -> Self {
+        Self { sessions: collab.sessions.clone() }
+    }
+}
+
+impl<Ed: CollabEditor> ToCompletionFn<Ed> for ReadOnly<Ed> {
+    fn to_completion_fn(&self) {}
+}
@@ -0,0 +1,54 @@
+//! TODO: docs.
+
+use editor::Context;
+use editor::command::ToCompletionFn;
+use editor::module::AsyncAction;
+
+use crate::collab::Collab;
+use crate::editors::{ActionForSelectedSession, CollabEditor};
+use crate::permissions::{self, PermissionsError};
+use crate::session::Sessions;
+
+/// The `Action` used to let the local peer modify the project again after
+/// making itself [`ReadOnly`](crate::read_only::ReadOnly).
+#[derive(cauchy::Clone)]
+pub struct ReadWrite<Ed: CollabEditor> {
+    sessions: Sessions<Ed>,
+}
+
+impl<Ed: CollabEditor> ReadWrite<Ed> {
+    pub(crate) async fn call_inner(
+        &self,
+        ctx: &mut Context<Ed>,
+    ) -> Result<(), PermissionsError<Ed>> {
+        permissions::set_read_only(
+            &self.sessions,
+            false,
+            ActionForSelectedSession::ReadWrite,
+            ctx,
+        )
+        .await
+    }
+}
+
+impl<Ed: CollabEditor> AsyncAction<Ed> for ReadWrite<Ed> {
+    const NAME: &str = "read-write";
+
+    type Args = ();
+
+    async fn call(&mut self, _: Self::Args, ctx: &mut Context<Ed>) {
+        if let Err(err) = self.call_inner(ctx).await {
+            Ed::on_permissions_error(err, ctx);
+        }
+    }
+}
+
+impl<Ed: CollabEditor> From<&Collab<Ed>> for ReadWrite<Ed> {
+    fn from(collab: &Collab<Ed>) -> Self {
+        Self { sessions: collab.sessions.clone() }
+    }
+}
+
+impl<Ed: CollabEditor> ToCompletionFn<Ed> for ReadWrite<Ed> {
+    fn to_completion_fn(&self) {}
+}
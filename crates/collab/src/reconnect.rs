@@ -0,0 +1,163 @@
+//! Contains the machinery used to re-establish a session's connection to the
+//! collab server after it's been lost.
+
+use core::time::Duration;
+
+use auth::JsonWebToken;
+use collab_server::client;
+use collab_types::{Peer, PeerId};
+use editor::Context;
+use futures_util::AsyncReadExt;
+
+use crate::config::ServerAddress;
+use crate::editors::{CollabEditor, SessionId, Welcome};
+
+/// The delay before the first reconnection attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// The maximum delay between two consecutive reconnection attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// The maximum number of reconnection attempts before giving up on the
+/// session.
+const MAX_ATTEMPTS: u32 = 10;
+
+/// Reconnects to the collab server and re-joins the same session, using an
+/// exponential backoff between failed attempts.
+#[derive(cauchy::Clone, cauchy::Debug)]
+pub(crate) struct Reconnector<Ed: CollabEditor, Tx, Rx> {
+    /// The credentials used to authenticate with the server.
+    #[debug(skip)]
+    jwt: JsonWebToken,
+
+    /// The address of the server the session was started on.
+    #[debug(skip)]
+    server_addr: ServerAddress<'static>,
+
+    /// The ID of the session to re-join.
+    session_id: SessionId<Ed>,
+
+    /// The function used to get the message sender and receiver from the
+    /// [`Welcome`] received after re-joining the session.
+    #[debug(skip)]
+    split_welcome: fn(Welcome<Ed>) -> (Tx, Rx),
+}
+
+/// The state of the session received from the server after successfully
+/// reconnecting.
+pub(crate) struct Reconnected<Tx, Rx> {
+    /// The local peer, as seen by the server after reconnecting.
+    pub(crate) local_peer: Peer,
+
+    /// The remote peers that are currently in the session.
+    pub(crate) other_peers: Vec<Peer>,
+
+    /// The ID of the session's host, as known by the server.
+    ///
+    /// This is the peer that started the session, which may have left since
+    /// then (see [`current_host_id`](crate::project::current_host_id)).
+    pub(crate) host_id: PeerId,
+
+    /// The new message sender.
+    pub(crate) tx: Tx,
+
+    /// The new message receiver.
+    pub(crate) rx: Rx,
+}
+
+/// The type of error that can occur when a single reconnection attempt fails.
+#[derive(cauchy::Debug, derive_more::Display)]
+#[display("{_0:?}")]
+pub(crate) enum ReconnectError<Ed: CollabEditor> {
+    /// Connecting to the server failed.
+    ConnectToServer(Ed::ConnectToServerError),
+
+    /// Re-joining the session failed.
+    Knock(client::KnockError<Ed::ServerParams>),
+}
+
+impl<Ed: CollabEditor, Tx, Rx> Reconnector<Ed, Tx, Rx> {
+    /// Creates a new [`Reconnector`].
+    pub(crate) fn new(
+        jwt: JsonWebToken,
+        server_addr: ServerAddress<'static>,
+        session_id: SessionId<Ed>,
+        split_welcome: fn(Welcome<Ed>) -> (Tx, Rx),
+    ) -> Self {
+        Self { jwt, server_addr, session_id, split_welcome }
+    }
+
+    /// Tries to reconnect to the server and re-join the session, waiting an
+    /// exponentially increasing amount of time between failed attempts.
+    ///
+    /// Returns `None` if all the attempts failed.
+    pub(crate) async fn reconnect(
+        &self,
+        ctx: &mut Context<Ed>,
+    ) -> Option<Reconnected<Tx, Rx>> {
+        let mut backoff = INITIAL_BACKOFF;
+
+        for attempt in 1..=MAX_ATTEMPTS {
+            Ed::sleep(backoff, ctx).await;
+
+            match self.try_reconnect(ctx).await {
+                Ok(reconnected) => return Some(reconnected),
+                Err(err) => {
+                    tracing::warn!(
+                        attempt,
+                        "couldn't reconnect to {}: {err}",
+                        self.server_addr,
+                    );
+                },
+            }
+
+            // Add some jitter to avoid all the peers in a session hammering
+            // the server at the same time after a server-side outage.
+            let jitter = backoff.mul_f64(fastrand::f64() * 0.25);
+            backoff = (backoff * 2 + jitter).min(MAX_BACKOFF);
+        }
+
+        None
+    }
+
+    /// Connects to the server and joins the same session as a new peer,
+    /// without closing the existing connection.
+    ///
+    /// This is used to let agents take part in the session as separate
+    /// peers (see [`agents`](crate::agents)).
+    pub(crate) async fn join_as_new_peer(
+        &self,
+        ctx: &mut Context<Ed>,
+    ) -> Result<Reconnected<Tx, Rx>, ReconnectError<Ed>> {
+        self.try_reconnect(ctx).await
+    }
+
+    async fn try_reconnect(
+        &self,
+        ctx: &mut Context<Ed>,
+    ) -> Result<Reconnected<Tx, Rx>, ReconnectError<Ed>> {
+        let (reader, writer) =
+            Ed::connect_to_server(self.server_addr.clone(), ctx)
+                .await
+                .map_err(ReconnectError::ConnectToServer)?
+                .split();
+
+        let knock = client::Knock::<Ed::ServerParams> {
+            auth_infos: self.jwt.clone().into(),
+            session_intent: client::SessionIntent::JoinExisting(
+                self.session_id,
+            ),
+        };
+
+        let welcome = client::knock(reader, writer, knock)
+            .await
+            .map_err(ReconnectError::Knock)?;
+
+        let local_peer = welcome.peer.clone();
+        let other_peers = welcome.other_peers.as_slice().to_vec();
+        let host_id = welcome.host_id;
+        let (tx, rx) = (self.split_welcome)(welcome);
+
+        Ok(Reconnected { local_peer, other_peers, host_id, tx, rx })
+    }
+}
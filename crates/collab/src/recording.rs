@@ -0,0 +1,577 @@
+//! Contains the machinery used to record the messages exchanged during a
+//! session, and to replay them later.
+//!
+//! A recording is made of newline-delimited JSON. The first line is a
+//! [`RecordingHeader`] holding the state of the project when the recording
+//! started, and each of the following lines is a [`RecordedMessage`].
+//!
+//! Recordings are append-only: every message is serialized once when it's
+//! recorded, and saving a recording only appends the lines recorded since the
+//! last save.
+//!
+//! Replaying a recording integrates the messages received and produced by
+//! the peer that recorded it in the same order they were integrated or
+//! produced, which makes it possible to reproduce CRDT divergence issues, or
+//! to simply watch a session back.
+
+use core::fmt;
+use core::str::FromStr;
+use core::time::Duration;
+use std::time::{Instant, SystemTime};
+
+use abs_path::{AbsPathBuf, NodeNameBuf};
+use collab_types::{Message, Peer, PeerId};
+use editor::{Context, Shared};
+use fs::{Directory as _, File as _, Fs};
+use futures_util::StreamExt;
+use fxhash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::editors::CollabEditor;
+use crate::join::{self, WriteProjectError};
+use crate::peers::RemotePeers;
+use crate::project::{IntegrateError, Project};
+use crate::undo::UndoHistory;
+
+/// How often a running session appends the newly recorded messages to its
+/// recording on disk.
+pub(crate) const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The version of the recording format, which is bumped every time the
+/// format changes in a backwards-incompatible way.
+const VERSION: u32 = 2;
+
+/// A recording of the messages exchanged during a session.
+#[derive(Debug, Clone)]
+pub struct Recording {
+    /// The state of the session when the recording started.
+    pub header: RecordingHeader,
+
+    /// The recorded messages, in the order they were integrated or produced.
+    pub messages: Vec<RecordedMessage>,
+}
+
+/// The state of a session when its recording started.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingHeader {
+    /// The version of the recording format.
+    pub version: u32,
+
+    /// The number of milliseconds since the Unix epoch at which the
+    /// recording started.
+    pub started_at: u64,
+
+    /// The name of the project's root directory.
+    pub project_name: String,
+
+    /// The peer that recorded the session.
+    pub local_peer: Peer,
+
+    /// The other peers that were in the session when the recording started.
+    pub remote_peers: Vec<Peer>,
+
+    /// The ID of the session's host when the recording started.
+    pub host_id: PeerId,
+
+    /// The encoded project, as returned by
+    /// [`collab_project::Project::encode`].
+    #[serde(with = "hex")]
+    pub encoded_project: Vec<u8>,
+}
+
+/// A message received or sent by the peer that recorded the session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    /// The time elapsed between the start of the recording and when the
+    /// message was integrated or produced.
+    #[serde(rename = "at_ms", with = "millis")]
+    pub at: Duration,
+
+    /// Whether the message was received or sent.
+    pub direction: Direction,
+
+    /// The message.
+    pub message: Message,
+}
+
+/// Whether a [`RecordedMessage`] was received or sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// The message was received from another peer.
+    Incoming,
+
+    /// The message was produced by the peer that recorded the session in
+    /// response to a local event, like an edit made in the editor.
+    Outgoing,
+
+    /// The message was produced by the peer that recorded the session while
+    /// integrating another message (e.g. to resolve a naming conflict).
+    ///
+    /// These messages are not replayed, since integrating the message that
+    /// caused them produces them again.
+    Derived,
+}
+
+/// How fast to replay a recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pace {
+    /// Integrate all the messages as fast as possible.
+    AsFastAsPossible,
+
+    /// Wait between messages for the same amount of time that passed
+    /// between them while recording.
+    RealTime,
+}
+
+/// The type of error that can occur when parsing a [`Recording`].
+#[derive(Debug, derive_more::Display, cauchy::Error)]
+pub enum ParseRecordingError {
+    /// The recording is empty.
+    #[display("the recording is empty")]
+    Empty,
+
+    /// A line is not valid JSON, or it doesn't match the expected format.
+    #[display("invalid line {_0}: {_1}")]
+    InvalidLine(usize, serde_json::Error),
+
+    /// The recording was created with an unsupported version of the format.
+    #[display("unsupported recording version {_0} (expected {VERSION})")]
+    UnsupportedVersion(u32),
+}
+
+/// The type of error that can occur when saving a recording fails.
+#[derive(cauchy::Debug, derive_more::Display, cauchy::Error)]
+#[display("{_0}")]
+pub enum SaveRecordingError<Ed: CollabEditor> {
+    /// The directory containing the recordings couldn't be created.
+    CreateDirectories(<Ed::Fs as Fs>::CreateDirectoriesError),
+
+    /// The recording file couldn't be created.
+    CreateFile(<<Ed::Fs as Fs>::Directory as fs::Directory>::CreateFileError),
+
+    /// Getting the node at the recording's path failed.
+    NodeAtPath(<Ed::Fs as Fs>::NodeAtPathError),
+
+    /// The node at the recording's path is not a file.
+    #[display("the node at {_0} is not a file")]
+    NotAFile(AbsPathBuf),
+
+    /// The recording file couldn't be appended to.
+    WriteFile(<<Ed::Fs as Fs>::File as fs::File>::WriteError),
+}
+
+/// The type of error that can occur when replaying a recording fails.
+#[derive(cauchy::Debug, derive_more::Display, cauchy::Error)]
+#[display("{_0}")]
+pub enum ReplayError<Ed: CollabEditor> {
+    /// The recorded project couldn't be decoded.
+    Decode(collab_project::DecodeError),
+
+    /// The message at the given index couldn't be integrated.
+    #[display("couldn't integrate message #{_0}: {_1}")]
+    Integrate(usize, IntegrateError<Ed>),
+
+    /// There's already a file or a non-empty directory at the path the
+    /// project would be written to, which replaying would delete.
+    #[display("{_0} already exists and is not an empty directory")]
+    ProjectRootExists(AbsPathBuf),
+
+    /// The contents of the directory the project would be written to
+    /// couldn't be listed.
+    ListProjectRoot(<<Ed::Fs as Fs>::Directory as fs::Directory>::ListError),
+
+    /// The node at the path the project would be written to couldn't be
+    /// read.
+    ReadProjectRoot(<Ed::Fs as Fs>::NodeAtPathError),
+
+    /// The recorded project couldn't be written to disk.
+    WriteProject(WriteProjectError<Ed::Fs>),
+}
+
+/// Records the messages exchanged during a session, periodically appending
+/// them to a file in the [`recordings_dir`](CollabEditor::recordings_dir).
+#[derive(Clone)]
+pub(crate) struct Recorder {
+    inner: Shared<RecorderInner>,
+}
+
+struct RecorderInner {
+    /// Whether the recording is currently being saved.
+    is_saving: bool,
+
+    /// The path of the file the recording is saved to.
+    path: AbsPathBuf,
+
+    /// When the recording started.
+    started_at: Instant,
+
+    /// The lines recorded since the last save, which will be appended to
+    /// the file the next time the recording is saved.
+    unsaved_lines: String,
+}
+
+impl Recorder {
+    /// Starts recording the session the given project belongs to.
+    pub(crate) async fn new<Ed: CollabEditor>(
+        project: &Project<Ed>,
+        ctx: &mut Context<Ed>,
+    ) -> Result<Self, Ed::RecordingsDirError> {
+        let recordings_dir = Ed::recordings_dir(ctx).await?;
+
+        let started_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        let file_name = format!("{}-{started_at}.jsonl", project.name())
+            .parse::<NodeNameBuf>()
+            .expect("project name and digits are a valid node name");
+
+        let mut remote_peers = Vec::new();
+        project
+            .remote_peers
+            .for_each(|peer| remote_peers.push(Peer::clone(peer)));
+
+        let header = RecordingHeader {
+            version: VERSION,
+            started_at,
+            project_name: project.name().to_string(),
+            local_peer: project.local_peer.clone(),
+            remote_peers,
+            host_id: project.host_id.copied(),
+            encoded_project: project.inner.encode(),
+        };
+
+        let mut unsaved_lines = String::new();
+        push_line(&header, &mut unsaved_lines);
+
+        Ok(Self {
+            inner: Shared::new(RecorderInner {
+                is_saving: false,
+                path: recordings_dir.join(&file_name),
+                started_at: Instant::now(),
+                unsaved_lines,
+            }),
+        })
+    }
+
+    /// Records the given message.
+    pub(crate) fn record(&self, direction: Direction, message: &Message) {
+        self.inner.with_mut(|inner| {
+            let recorded = RecordedMessage {
+                at: inner.started_at.elapsed(),
+                direction,
+                message: message.clone(),
+            };
+            push_line(&recorded, &mut inner.unsaved_lines);
+        });
+    }
+
+    /// Appends the lines recorded since the last save to the recording on
+    /// disk, creating the file if it doesn't exist yet.
+    ///
+    /// If another save is already in progress, the lines are appended by
+    /// that one instead.
+    pub(crate) async fn save<Ed: CollabEditor>(
+        &self,
+        ctx: &mut Context<Ed>,
+    ) -> Result<(), SaveRecordingError<Ed>> {
+        // Appends from concurrent saves could be interleaved, so if another
+        // save is in progress we let it append our lines too.
+        let Some(path) = self.inner.with_mut(|inner| {
+            (!inner.is_saving).then(|| {
+                inner.is_saving = true;
+                inner.path.clone()
+            })
+        }) else {
+            return Ok(());
+        };
+
+        let res = loop {
+            let lines = self
+                .inner
+                .with_mut(|inner| core::mem::take(&mut inner.unsaved_lines));
+
+            if lines.is_empty() {
+                break Ok(());
+            }
+
+            if let Err(err) = self.append(&path, &lines, ctx).await {
+                // Put the lines back so that they're appended by the next
+                // save.
+                self.inner.with_mut(|inner| {
+                    inner.unsaved_lines.insert_str(0, &lines)
+                });
+                break Err(err);
+            }
+        };
+
+        self.inner.with_mut(|inner| inner.is_saving = false);
+
+        res
+    }
+
+    /// Returns the path of the file the recording is saved to.
+    pub(crate) fn path(&self) -> AbsPathBuf {
+        self.inner.with(|inner| inner.path.clone())
+    }
+
+    async fn append<Ed: CollabEditor>(
+        &self,
+        path: &AbsPathBuf,
+        lines: &str,
+        ctx: &mut Context<Ed>,
+    ) -> Result<(), SaveRecordingError<Ed>> {
+        let fs = ctx.fs();
+
+        let mut file = match fs
+            .node_at_path(path)
+            .await
+            .map_err(SaveRecordingError::NodeAtPath)?
+        {
+            Some(fs::Node::File(file)) => file,
+            Some(_) => return Err(SaveRecordingError::NotAFile(path.clone())),
+            None => {
+                let parent_path =
+                    path.parent().expect("recording is not the fs root");
+
+                let file_name =
+                    path.node_name().expect("recording is not the fs root");
+
+                fs.create_all_missing_directories(parent_path)
+                    .await
+                    .map_err(SaveRecordingError::CreateDirectories)?
+                    .create_file(file_name)
+                    .await
+                    .map_err(SaveRecordingError::CreateFile)?
+            },
+        };
+
+        file.append(lines).await.map_err(SaveRecordingError::WriteFile)
+    }
+}
+
+/// Replays the given recording by writing the recorded project under the
+/// given root, and then integrating all the recorded messages into it.
+///
+/// The returned project contains the cursors and selections of all the
+/// peers in the recorded session (including the one that recorded it), and
+/// it should be [dropped](Project::drop) once the caller is done with it.
+pub async fn replay<Ed: CollabEditor>(
+    recording: &Recording,
+    project_root: AbsPathBuf,
+    pace: Pace,
+    ctx: &mut Context<Ed>,
+) -> Result<Project<Ed>, ReplayError<Ed>> {
+    let header = &recording.header;
+
+    // The ID of the peer replaying the recording must be different from the
+    // IDs of all the peers in the recorded session, or the operations they
+    // created would be mistaken for local ones.
+    let replay_peer_id = PeerId::new(u64::MAX);
+
+    let inner = collab_project::Project::decode(
+        &header.encoded_project,
+        replay_peer_id,
+    )
+    .map_err(ReplayError::Decode)?;
+
+    // Writing the project deletes whatever is at its root, so refuse to
+    // replay over anything that's not an empty directory.
+    match ctx
+        .fs()
+        .node_at_path(&project_root)
+        .await
+        .map_err(ReplayError::ReadProjectRoot)?
+    {
+        None => {},
+        Some(fs::Node::Directory(dir)) => {
+            let mut children = dir
+                .list_metas()
+                .await
+                .map_err(ReplayError::ListProjectRoot)?;
+            if children.next().await.is_some() {
+                return Err(ReplayError::ProjectRootExists(project_root));
+            }
+        },
+        Some(_) => return Err(ReplayError::ProjectRootExists(project_root)),
+    }
+
+    let (project_root, _, id_maps) =
+        join::write_project(&inner, project_root, ctx)
+            .await
+            .map_err(ReplayError::WriteProject)?;
+
+    // All the peers in the recorded session are remote from the point of
+    // view of the one replaying it.
+    let remote_peers = RemotePeers::new(
+        header.remote_peers.iter().cloned().chain([header.local_peer.clone()]),
+        &inner,
+    );
+
+    let mut project = Project {
+        agent_id: ctx.new_agent_id(),
+        blames: FxHashMap::default(),
+        host_id: Shared::new(header.host_id),
+        id_maps: id_maps.into(),
+        inner,
+        local_peer: Peer {
+            id: replay_peer_id,
+            handle: header.local_peer.handle.clone(),
+        },
+        peer_cursors: FxHashMap::default(),
+        peer_selections: FxHashMap::default(),
+        permissions: Shared::default(),
+        remote_peers,
+        root_path: project_root.path().to_owned(),
+        session_id: None,
+        undo_history: UndoHistory::default(),
+    };
+
+    let mut prev_at = Duration::ZERO;
+
+    for (idx, recorded) in recording.messages.iter().enumerate() {
+        if pace == Pace::RealTime {
+            Ed::sleep(recorded.at.saturating_sub(prev_at), ctx).await;
+            prev_at = recorded.at;
+        }
+
+        // Project requests and responses are only used to join the session,
+        // and don't change the state of the project.
+        if recorded.direction == Direction::Derived
+            || matches!(
+                recorded.message,
+                Message::ProjectRequest(_) | Message::ProjectResponse(_)
+            )
+        {
+            continue;
+        }
+
+        // Any message produced by integrating this one was recorded as
+        // derived, and the project we return already contains its effects.
+        let _ = project
+            .integrate(recorded.message.clone(), ctx)
+            .await
+            .map_err(|err| ReplayError::Integrate(idx, err))?;
+    }
+
+    Ok(project)
+}
+
+/// Serializes the given value as a line of JSON, and appends it to the given
+/// string.
+fn push_line<T: Serialize>(value: &T, lines: &mut String) {
+    match serde_json::to_string(value) {
+        Ok(line) => {
+            lines.push_str(&line);
+            lines.push('\n');
+        },
+        Err(err) => {
+            tracing::error!("couldn't serialize recording line: {err}")
+        },
+    }
+}
+
+impl fmt::Display for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let header =
+            serde_json::to_string(&self.header).map_err(|_| fmt::Error)?;
+        writeln!(f, "{header}")?;
+        for message in &self.messages {
+            let line =
+                serde_json::to_string(message).map_err(|_| fmt::Error)?;
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Recording {
+    type Err = ParseRecordingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line))
+            .filter(|(_, line)| !line.trim().is_empty());
+
+        let (header_line, header) =
+            lines.next().ok_or(ParseRecordingError::Empty)?;
+
+        let header = serde_json::from_str::<RecordingHeader>(header).map_err(
+            |err| ParseRecordingError::InvalidLine(header_line, err),
+        )?;
+
+        if header.version != VERSION {
+            return Err(ParseRecordingError::UnsupportedVersion(
+                header.version,
+            ));
+        }
+
+        let messages = lines
+            .map(|(line_num, line)| {
+                serde_json::from_str(line).map_err(|err| {
+                    ParseRecordingError::InvalidLine(line_num, err)
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { header, messages })
+    }
+}
+
+/// (De)serializes byte vectors as lowercase hex strings.
+mod hex {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        bytes: &[u8],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let hex =
+            bytes.iter().map(|byte| format!("{byte:02x}")).collect::<String>();
+        serializer.serialize_str(&hex)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        let hex = <&str>::deserialize(deserializer)?;
+
+        if hex.len() % 2 != 0 {
+            return Err(D::Error::custom("odd number of hex digits"));
+        }
+
+        (0..hex.len())
+            .step_by(2)
+            .map(|idx| {
+                hex.get(idx..idx + 2)
+                    .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                    .ok_or_else(|| D::Error::custom("invalid hex digit"))
+            })
+            .collect()
+    }
+}
+
+/// (De)serializes durations as an integer number of milliseconds.
+mod millis {
+    use core::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        duration: &Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
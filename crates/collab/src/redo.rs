@@ -0,0 +1,48 @@
+//! TODO: docs.
+
+use editor::Context;
+use editor::command::ToCompletionFn;
+use editor::module::AsyncAction;
+
+use crate::collab::Collab;
+use crate::editors::CollabEditor;
+use crate::session::Sessions;
+use crate::undo::{self, Revert, UndoError};
+
+/// An `Action` that redoes the last edit undone by the
+/// [`Undo`](crate::undo::Undo) action in the focused buffer.
+#[derive(cauchy::Clone)]
+pub struct Redo<Ed: CollabEditor> {
+    sessions: Sessions<Ed>,
+}
+
+impl<Ed: CollabEditor> Redo<Ed> {
+    pub(crate) async fn call_inner(
+        &self,
+        ctx: &mut Context<Ed>,
+    ) -> Result<(), UndoError> {
+        undo::revert(&self.sessions, Revert::Redo, ctx).await
+    }
+}
+
+impl<Ed: CollabEditor> AsyncAction<Ed> for Redo<Ed> {
+    const NAME: &str = "redo";
+
+    type Args = ();
+
+    async fn call(&mut self, _: Self::Args, ctx: &mut Context<Ed>) {
+        if let Err(err) = self.call_inner(ctx).await {
+            Ed::on_undo_error(err, ctx);
+        }
+    }
+}
+
+impl<Ed: CollabEditor> From<&Collab<Ed>> for Redo<Ed> {
+    fn from(collab: &Collab<Ed>) -> Self {
+        Self { sessions: collab.sessions.clone() }
+    }
+}
+
+impl<Ed: CollabEditor> ToCompletionFn<Ed> for Redo<Ed> {
+    fn to_completion_fn(&self) {}
+}
@@ -5,10 +5,16 @@ use fs::{Directory, File, MetadataNameError, Symlink};
 use futures_util::select;
 use futures_util::stream::{self, StreamExt};
 
+use crate::config::RootPrecedence;
+
 pub struct FindRootArgs<'a, M> {
     /// The marker used to determine if a directory is the root.
     pub(super) marker: M,
 
+    /// Which directory is picked as the root when several of the ancestors
+    /// of `start_from` contain a marker.
+    pub(super) precedence: RootPrecedence,
+
     /// The path to the first directory to search for markers in.
     ///
     /// If this points to a file, the search will start from its parent.
@@ -16,12 +22,14 @@ pub struct FindRootArgs<'a, M> {
 
     /// The path to the last directory to search for markers in, if any.
     ///
-    /// If set and no root marker is found within it, the search is cut
-    /// short instead of continuing with its parent.
+    /// If set, the search is cut short once this directory has been
+    /// searched instead of continuing with its parent.
     pub(super) stop_at: Option<&'a AbsPath>,
 }
 
-pub struct GitDirectory;
+/// A [`RootMarker`] matching any file or directory whose name is one of the
+/// given ones.
+pub struct NodeNames(pub(super) Vec<String>);
 
 pub trait RootMarker<Fs: fs::Fs> {
     type Error: Error;
@@ -101,17 +109,26 @@ impl<M> FindRootArgs<'_, M> {
             }
         };
 
+        let mut outermost_root = None;
+
         loop {
             if self.contains_marker(&dir).await? {
-                return Ok(Some(dir.path().to_owned()));
+                match self.precedence {
+                    RootPrecedence::Nearest => {
+                        return Ok(Some(dir.path().to_owned()));
+                    },
+                    RootPrecedence::Outermost => {
+                        outermost_root = Some(dir.path().to_owned());
+                    },
+                }
             }
             if self.stop_at == Some(dir.path()) {
-                return Ok(None);
+                return Ok(outermost_root);
             }
             let Some(parent) =
                 dir.parent().await.map_err(FindRootError::DirParent)?
             else {
-                return Ok(None);
+                return Ok(outermost_root);
             };
             dir = parent;
         }
@@ -159,7 +176,7 @@ impl<M> FindRootArgs<'_, M> {
     }
 }
 
-impl<Fs: fs::Fs> RootMarker<Fs> for GitDirectory {
+impl<Fs: fs::Fs> RootMarker<Fs> for NodeNames {
     type Error = core::convert::Infallible;
 
     async fn matches(
@@ -168,7 +185,7 @@ impl<Fs: fs::Fs> RootMarker<Fs> for GitDirectory {
     ) -> Result<bool, Self::Error> {
         use fs::Metadata;
         Ok(match metadata.name() {
-            Ok(name) => name == ".git" && metadata.node_kind().is_dir(),
+            Ok(name) => self.0.iter().any(|marker| name.as_str() == marker),
             Err(MetadataNameError::Invalid(_, _)) => false,
             Err(MetadataNameError::NotUtf8(_)) => false,
             Err(MetadataNameError::MetadataIsForRoot) => false,
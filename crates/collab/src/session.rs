@@ -6,6 +6,7 @@ use core::task::{self, Poll, ready};
 use std::collections::{VecDeque, hash_map};
 use std::io;
 use std::rc::Rc;
+use std::time::Instant;
 
 use abs_path::{AbsPathBuf, NodeName};
 use collab_server::client::{self, MessageFragment};
@@ -17,20 +18,28 @@ use futures_util::{FutureExt, select_biased};
 use fxhash::FxHashMap;
 use smallvec::SmallVec;
 
+use crate::agents::Agents;
 use crate::editors::ActionForSelectedSession;
 use crate::event_stream::{EventError, EventStream};
+use crate::host_local::LocalServer;
 use crate::leave::StopRequest;
-use crate::peers::RemotePeers;
+use crate::pausable_stream::{self, PausableStream};
+use crate::peers::{self, RemotePeers};
+use crate::permissions::Permissions;
 use crate::project::{IntegrateError, Project, SynchronizeError};
-use crate::{CollabEditor, SessionId, pausable_stream};
+use crate::query::{self, FileSummary};
+use crate::reconnect::Reconnector;
+use crate::recording::{self, Direction, Recorder};
+use crate::{CollabEditor, SessionId, snapshot};
 
 /// The type-erased version of the async callbacks given to
-/// [`ProjectAccess::with()`].
+/// [`ProjectAccess::with()`] and [`ProjectAccess::with_mut()`].
 type ProjectAccessCallback<Ed> = Box<
     dyn for<'a> FnOnce(
-        &'a Project<Ed>,
+        &'a mut Project<Ed>,
         &'a mut Context<Ed>,
-    ) -> Pin<Box<dyn Future<Output = ()> + 'a>>,
+    )
+        -> Pin<Box<dyn Future<Output = Vec<Message>> + 'a>>,
 >;
 
 /// TODO: docs.
@@ -43,8 +52,21 @@ pub struct Sessions<Ed: CollabEditor> {
 #[derive(cauchy::Debug, cauchy::Clone)]
 #[allow(dead_code)]
 pub struct SessionInfos<Ed: CollabEditor> {
-    /// The [`PeerId`] of the host of the session.
-    pub(crate) host_id: PeerId,
+    /// The files in the project.
+    ///
+    /// This is refreshed by the session's event loop every time a file or
+    /// directory is created, deleted, moved or renamed.
+    pub(crate) files: Shared<Vec<FileSummary>>,
+
+    /// The [`PeerId`] of the current host of the session.
+    ///
+    /// This is shared with the session's [`Project`], which updates it when
+    /// the host leaves.
+    pub(crate) host_id: Shared<PeerId>,
+
+    /// Whether the session is currently connected to the server, or is
+    /// trying to reconnect after the connection dropped.
+    pub(crate) is_connected: Shared<bool>,
 
     /// TODO: docs..
     pub(crate) local_peer: Peer,
@@ -52,6 +74,12 @@ pub struct SessionInfos<Ed: CollabEditor> {
     /// TODO: docs..
     pub(crate) remote_peers: RemotePeers,
 
+    /// The permissions of the peers in the session.
+    ///
+    /// This is shared with the session's [`Project`], which uses it to make
+    /// the local peer's buffers non-modifiable when it's read-only.
+    pub(crate) permissions: Shared<Permissions>,
+
     /// The remote used to pause/resume receiving [`Message`]s.
     pub(crate) pause_remote: pausable_stream::Remote,
 
@@ -90,11 +118,18 @@ pub enum SessionError<Ed: CollabEditor> {
 
 /// TODO: docs.
 pub(crate) struct Session<Ed: CollabEditor, Tx, Rx> {
+    /// The agents connected to the session.
+    pub(crate) agents: Agents,
+
     /// TODO: docs.
     pub(crate) event_stream: EventStream<Ed>,
 
+    /// The collab server the session is hosted on if it runs inside the
+    /// editor's process, which is shut down when the session ends.
+    pub(crate) local_server: Option<LocalServer>,
+
     /// TODO: docs.
-    pub(crate) message_rx: Rx,
+    pub(crate) message_rx: PausableStream<Rx>,
 
     /// TODO: docs.
     pub(crate) message_tx: Tx,
@@ -105,9 +140,21 @@ pub(crate) struct Session<Ed: CollabEditor, Tx, Rx> {
     /// TODO: docs.
     pub(crate) project: Project<Ed>,
 
+    /// Whether to record the messages exchanged during the session.
+    pub(crate) record: bool,
+
+    /// Whether to periodically save a [`snapshot`] of the project. This is
+    /// only true for the sessions started by the local peer, since those are
+    /// the only ones that can be resumed.
+    pub(crate) save_snapshots: bool,
+
     /// TODO: docs.
     pub(crate) stop_rx: flume::Receiver<StopRequest>,
 
+    /// The reconnector used to re-join the session if the connection to the
+    /// server is lost.
+    pub(crate) reconnector: Reconnector<Ed, Tx, Rx>,
+
     /// TODO: docs.
     pub(crate) remove_on_drop: RemoveOnDrop<Ed>,
 }
@@ -227,6 +274,11 @@ impl<Ed: CollabEditor> Sessions<Ed> {
 }
 
 impl<Ed: CollabEditor> SessionInfos<Ed> {
+    /// Returns the [`PeerId`] of the session's current host.
+    pub fn host_id(&self) -> PeerId {
+        self.host_id.copied()
+    }
+
     /// Returns the session's ID.
     pub fn id(&self) -> SessionId<Ed> {
         self.session_id
@@ -243,15 +295,93 @@ impl<Ed: CollabEditor> SessionInfos<Ed> {
 impl<Ed, Tx, Rx> Session<Ed, Tx, Rx>
 where
     Ed: CollabEditor,
-    Tx: Sink<Message, Error = io::Error> + Unpin,
+    Tx: Sink<Message, Error = io::Error> + Unpin + 'static,
     Rx: FusedStream<Item = Result<MessageFragment, client::ReceiveError>>
-        + Unpin,
+        + Unpin
+        + 'static,
 {
     pub(crate) async fn run(mut self, ctx: &mut Context<Ed>) {
-        let result = self.run_event_loop(ctx).await;
+        // Snapshots are also disabled if the editor doesn't have a directory
+        // to save them in.
+        let snapshots_enabled =
+            self.save_snapshots && Ed::snapshots_dir(ctx).await.is_ok();
+
+        if snapshots_enabled {
+            let project_access = self.project_access.clone();
+            ctx.spawn_and_detach(async move |ctx| {
+                loop {
+                    Ed::sleep(snapshot::SAVE_INTERVAL, ctx).await;
+                    // Stop once the session's event loop has ended.
+                    if project_access
+                        .with(async |proj, ctx| save_snapshot(proj, ctx).await)
+                        .await
+                        .is_none()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+
+        let project_access = self.project_access.clone();
+        ctx.spawn_and_detach(async move |ctx| {
+            loop {
+                Ed::sleep(peers::ACTIVITY_CHECK_INTERVAL, ctx).await;
+                // Stop once the session's event loop has ended.
+                if project_access
+                    .with_mut(async |proj, ctx| {
+                        proj.update_peer_activities(Instant::now(), ctx)
+                    })
+                    .await
+                    .is_none()
+                {
+                    break;
+                }
+            }
+        });
+
+        let recorder = if self.record {
+            match Recorder::new(&self.project, ctx).await {
+                Ok(recorder) => Some(recorder),
+                Err(err) => {
+                    tracing::error!(
+                        "couldn't start recording session: {err:?}"
+                    );
+                    None
+                },
+            }
+        } else {
+            None
+        };
+
+        if let Some(recorder) = recorder.clone() {
+            let project_access = self.project_access.clone();
+            ctx.spawn_and_detach(async move |ctx| {
+                loop {
+                    Ed::sleep(recording::SAVE_INTERVAL, ctx).await;
+                    // Stop once the session's event loop has ended.
+                    if project_access.with(async |_, _| ()).await.is_none() {
+                        break;
+                    }
+                    save_recording(&recorder, ctx).await;
+                }
+            });
+        }
+
+        self.agents.listen(&self.reconnector, &self.project_access, ctx);
+
+        let result = self.run_event_loop(recorder.as_ref(), ctx).await;
 
         let Self { project, remove_on_drop, .. } = self;
 
+        if snapshots_enabled {
+            save_snapshot(&project, ctx).await;
+        }
+
+        if let Some(recorder) = &recorder {
+            save_recording(recorder, ctx).await;
+        }
+
         project.drop(ctx);
 
         match result {
@@ -271,67 +401,339 @@ where
     /// occurs.
     async fn run_event_loop(
         &mut self,
+        recorder: Option<&Recorder>,
         ctx: &mut Context<Ed>,
     ) -> Result<SessionEndReason, SessionError<Ed>> {
         let Self {
+            agents,
             event_stream,
+            local_server: _,
             message_rx,
             message_tx,
             project,
             project_access,
+            record: _,
+            save_snapshots: _,
             stop_rx,
-            ..
+            reconnector,
+            remove_on_drop,
         } = self;
 
+        // Whether the project's file tree may have changed since the last
+        // time we refreshed the session's `files`.
+        let file_tree_changed = Cell::new(true);
+
+        // Whether the position of any remote peer's main cursor may have
+        // changed since the last time we refreshed them.
+        let peer_positions_changed = Cell::new(true);
+
+        let record = |direction, message: &Message| {
+            if let Some(recorder) = recorder {
+                recorder.record(direction, message);
+            }
+            if changes_file_tree(message) {
+                file_tree_changed.set(true);
+            }
+            if changes_peer_positions(direction, message) {
+                peer_positions_changed.set(true);
+            }
+        };
+
+        let mut agent_request_stream =
+            agents.request_rx().clone().into_stream();
         let mut callback_stream = project_access.callback_stream();
         let mut stop_stream = stop_rx.stream();
 
+        // The local messages that haven't been sent to the server yet. They're
+        // only removed from the queue once they've been sent successfully, so
+        // that they can be re-sent after reconnecting.
+        let mut unsent = VecDeque::<Message>::new();
+
         loop {
-            select_biased! {
-                event_res = event_stream.next(ctx).fuse() => {
-                    if let Some(message) =
-                        project.synchronize(event_res?, ctx).await?
-                    {
-                        message_tx.send(message).await?;
-                    }
-                },
-                maybe_message_res = message_rx.next() => {
-                    let Some(fragment_res) = maybe_message_res else {
-                        return Ok(SessionEndReason::MessageReceiverExhausted);
-                    };
+            let connection_error: SessionError<Ed> = 'connected: {
+                // Refresh the state exposed by the query functions before
+                // waiting for the next event.
+                if peer_positions_changed.replace(false) {
+                    project.update_peer_positions();
+                }
+                if file_tree_changed.replace(false) {
+                    let files = query::file_summaries(project);
+                    remove_on_drop.with_infos(|infos| infos.files.set(files));
+                }
 
-                    let Some(message) = fragment_res?.message else {
-                        continue;
-                    };
+                // Flush the queue of unsent messages before waiting for the
+                // next event.
+                if let Err(err) = send_all(&mut unsent, message_tx).await {
+                    break 'connected err.into();
+                }
 
-                    for message in project.integrate(message, ctx).await? {
-                        message_tx.send(message).await?;
-                    }
-                },
-                callback = callback_stream.select_next_some() => {
-                    callback(project, ctx).await;
-                },
+                select_biased! {
+                    event_res = event_stream.next(ctx).fuse() => {
+                        if let Some(message) =
+                            project.synchronize(event_res?, ctx).await?
+                        {
+                            record(Direction::Outgoing, &message);
+                            agents.integrate(None, &message);
+                            unsent.push_back(message);
+                        }
+                    },
+                    maybe_message_res = message_rx.next() => {
+                        let Some(fragment_res) = maybe_message_res else {
+                            return Ok(
+                                SessionEndReason::MessageReceiverExhausted
+                            );
+                        };
+
+                        let fragment = match fragment_res {
+                            Ok(fragment) => fragment,
+                            Err(err) => break 'connected err.into(),
+                        };
+
+                        if let Some(message) = fragment.message {
+                            record(Direction::Incoming, &message);
+                            agents.integrate(None, &message);
+                            let messages =
+                                project.integrate(message, ctx).await?;
+                            for message in messages {
+                                record(Direction::Derived, &message);
+                                agents.integrate(None, &message);
+                                unsent.push_back(message);
+                            }
+                        }
+                    },
+                    agent_request = agent_request_stream.select_next_some() => {
+                        let (messages, derived) =
+                            agents.handle(agent_request, project, ctx).await?;
+                        // Agents are remote peers, so their cursors count.
+                        peer_positions_changed.set(true);
+                        for message in &messages {
+                            record(Direction::Outgoing, message);
+                        }
+                        for message in &derived {
+                            record(Direction::Derived, message);
+                        }
+                        unsent.extend(messages);
+                        unsent.extend(derived);
+                    },
+                    callback = callback_stream.select_next_some() => {
+                        for message in callback(project, ctx).await {
+                            record(Direction::Outgoing, &message);
+                            agents.integrate(None, &message);
+                            unsent.push_back(message);
+                        }
+                    },
+                    stop_request = stop_stream.select_next_some() => {
+                        stop_request.send_stopped();
+                        return Ok(SessionEndReason::UserLeft);
+                    },
+                }
+
+                continue;
+            };
+
+            remove_on_drop.with_infos(|infos| {
+                infos.is_connected.set(false);
+                Ed::on_session_disconnected(infos, ctx);
+            });
+
+            // Keep listening for stop requests while reconnecting, so that
+            // the user can still leave the session.
+            let maybe_reconnected = select_biased! {
                 stop_request = stop_stream.select_next_some() => {
                     stop_request.send_stopped();
                     return Ok(SessionEndReason::UserLeft);
                 },
+                maybe_reconnected = reconnector.reconnect(ctx).fuse() => {
+                    maybe_reconnected
+                },
+            };
+
+            let Some(reconnected) = maybe_reconnected else {
+                return Err(connection_error);
+            };
+
+            *message_tx = reconnected.tx;
+            message_rx.replace_inner(reconnected.rx);
+
+            remove_on_drop.with_infos_mut(|infos| {
+                infos.local_peer = reconnected.local_peer.clone();
+            });
+
+            let old_local_id = project.local_peer.id;
+
+            let messages = project.integrate_reconnection(
+                reconnected.local_peer,
+                reconnected.other_peers,
+                reconnected.host_id,
+                ctx,
+            );
+
+            // The cursors and selections created or moved while we were
+            // offline were owned by our old ID, and they've just been
+            // re-created under the new one.
+            if project.local_peer.id != old_local_id {
+                unsent.retain(|message| !is_cursor_or_selection(message));
             }
+
+            for message in messages {
+                record(Direction::Outgoing, &message);
+                agents.integrate(None, &message);
+                unsent.push_back(message);
+            }
+
+            peer_positions_changed.set(true);
+
+            // Any message produced while we were offline will be re-sent at
+            // the start of the next iteration.
+            remove_on_drop.with_infos(|infos| {
+                infos.is_connected.set(true);
+                Ed::on_session_reconnected(infos, ctx);
+            });
         }
     }
 }
 
+/// Returns whether integrating the given message may add, remove or move
+/// files in the project.
+fn changes_file_tree(message: &Message) -> bool {
+    matches!(
+        message,
+        Message::CreatedDirectory(_)
+            | Message::CreatedFile(_)
+            | Message::DeletedDirectory(_)
+            | Message::DeletedFile(_)
+            | Message::MovedDirectory(_)
+            | Message::MovedFile(_)
+            | Message::RenamedFsNode(_)
+    )
+}
+
+/// Returns whether recording the given message may change the position of a
+/// remote peer's main cursor.
+fn changes_peer_positions(direction: Direction, message: &Message) -> bool {
+    match message {
+        // Edits and file tree changes can move any peer's cursor.
+        Message::EditedText(_) => true,
+        _ if changes_file_tree(message) => true,
+        // The cursor messages we produce are about our own cursors.
+        Message::CreatedCursor(_)
+        | Message::MovedCursor(_)
+        | Message::RemovedCursor(_)
+        | Message::PeerDisconnected(_)
+        | Message::PeerJoined(_)
+        | Message::PeerLeft(_) => direction == Direction::Incoming,
+        _ => false,
+    }
+}
+
+/// Returns whether the given message creates, moves or removes a cursor or a
+/// selection.
+fn is_cursor_or_selection(message: &Message) -> bool {
+    matches!(
+        message,
+        Message::CreatedCursor(_)
+            | Message::CreatedSelection(_)
+            | Message::MovedCursor(_)
+            | Message::MovedSelection(_)
+            | Message::RemovedCursor(_)
+            | Message::RemovedSelection(_)
+    )
+}
+
+/// Sends all the messages in the given queue in order, removing each one as
+/// soon as it's been written to the sink.
+///
+/// Messages are only flushed once they've all been written. If flushing
+/// fails, the ones that were already written are not put back in the queue:
+/// they may have reached the server, and sending them again after
+/// reconnecting could deliver them twice.
+async fn send_all<Tx>(
+    queue: &mut VecDeque<Message>,
+    message_tx: &mut Tx,
+) -> Result<(), io::Error>
+where
+    Tx: Sink<Message, Error = io::Error> + Unpin,
+{
+    while let Some(message) = queue.front() {
+        message_tx.feed(message.clone()).await?;
+        queue.pop_front();
+    }
+    message_tx.flush().await
+}
+
+/// Saves the recording of the session to disk.
+async fn save_recording<Ed: CollabEditor>(
+    recorder: &Recorder,
+    ctx: &mut Context<Ed>,
+) {
+    if let Err(err) = recorder.save(ctx).await {
+        tracing::error!(
+            "couldn't save recording to {}: {err}",
+            recorder.path()
+        );
+    }
+}
+
+/// Saves a snapshot of the given project, so that it can be resumed by a
+/// later session.
+async fn save_snapshot<Ed: CollabEditor>(
+    project: &Project<Ed>,
+    ctx: &mut Context<Ed>,
+) {
+    let mut peer_ids = project.remote_peers.history();
+    peer_ids.push(project.local_peer.id);
+
+    if let Err(err) =
+        snapshot::save(&project.inner, &project.root_path, &peer_ids, ctx)
+            .await
+    {
+        tracing::error!(
+            "couldn't save snapshot of {}: {err}",
+            project.root_path
+        );
+    }
+}
+
 impl<Ed: CollabEditor> ProjectAccess<Ed> {
     /// TODO: docs.
     pub(crate) async fn with<R: 'static>(
         &self,
         fun: impl AsyncFnOnce(&Project<Ed>, &mut Context<Ed>) -> R + 'static,
+    ) -> Option<R> {
+        self.with_mut(async move |project, ctx| fun(project, ctx).await).await
+    }
+
+    /// Same as [`with()`](Self::with), but gives the callback exclusive
+    /// access to the project.
+    pub(crate) async fn with_mut<R: 'static>(
+        &self,
+        fun: impl AsyncFnOnce(&mut Project<Ed>, &mut Context<Ed>) -> R + 'static,
+    ) -> Option<R> {
+        self.with_mut_and_send(async move |project, ctx| {
+            (fun(project, ctx).await, Vec::new())
+        })
+        .await
+    }
+
+    /// Same as [`with_mut()`](Self::with_mut), but the callback also returns
+    /// the messages generated by modifying the project, which are sent to
+    /// the other peers in the session.
+    pub(crate) async fn with_mut_and_send<R: 'static>(
+        &self,
+        fun: impl AsyncFnOnce(
+            &mut Project<Ed>,
+            &mut Context<Ed>,
+        ) -> (R, Vec<Message>)
+        + 'static,
     ) -> Option<R> {
         let (tx, rx) = flume::bounded(1);
 
         let callback: ProjectAccessCallback<Ed> =
             Box::new(move |project, ctx| {
                 Box::pin(async move {
-                    let _ = tx.send(fun(project, ctx).await);
+                    let (out, messages) = fun(project, ctx).await;
+                    let _ = tx.send(out);
+                    messages
                 })
             });
 
@@ -410,6 +812,19 @@ impl<Ed: CollabEditor> RemoveOnDrop<Ed> {
             f(maybe_infos.expect("session is alive, so infos must exist"))
         })
     }
+
+    /// Like [`with_infos`](Self::with_infos), but with an exclusive reference
+    /// to the infos.
+    fn with_infos_mut<R>(
+        &self,
+        f: impl FnOnce(&mut SessionInfos<Ed>) -> R,
+    ) -> R {
+        self.sessions.inner.with_mut(|inner| {
+            f(inner
+                .get_mut(&self.session_id)
+                .expect("session is alive, so infos must exist"))
+        })
+    }
 }
 
 impl<Ed: CollabEditor> Access<FxHashMap<SessionId<Ed>, SessionInfos<Ed>>>
@@ -0,0 +1,447 @@
+//! Contains the machinery used to persist a project to disk and to resume it
+//! in a later session.
+//!
+//! A snapshot contains the encoded [`Project`], which already knows the path
+//! of every file and directory in it, together with the ID of the peer that
+//! saved it and the IDs of all the peers that may have edited it. The [`IdMaps`](crate::project::IdMaps) are not
+//! saved because they map between IDs that only live as long as the editor,
+//! and they're rebuilt from those paths when the snapshot is loaded.
+//!
+//! Snapshots are only saved for the sessions started by the local peer,
+//! since those are the only ones that are resumed by a later
+//! [`Start`](crate::start::Start).
+
+use core::time::Duration;
+
+use abs_path::{AbsPath, AbsPathBuf, NodeNameBuf};
+use collab_project::fs::{
+    Directory,
+    DirectoryMut,
+    File,
+    FileMut,
+    Node,
+    NodeMut,
+};
+use collab_project::{PeerId, Project};
+use collab_types::puff;
+use editor::Context;
+use fs::{Directory as _, File as _, Fs};
+use fxhash::FxHashMap;
+use puff::directory::LocalDirectoryId;
+use puff::file::LocalFileId;
+
+use crate::editors::CollabEditor;
+use crate::project::text_diff;
+
+/// How often a running session saves a snapshot of its project.
+pub(crate) const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The bytes every snapshot file starts with, used to detect snapshots saved
+/// in a different format.
+const MAGIC: &[u8; 8] = b"nomad\0s4";
+
+/// A project loaded from a snapshot.
+pub(crate) struct Snapshot {
+    /// The project, decoded under the local peer's ID.
+    pub(crate) project: Project,
+
+    /// The IDs of all the peers that may have edited the project in the
+    /// sessions it was saved from.
+    pub(crate) peer_ids: Vec<PeerId>,
+}
+
+/// Maps the IDs of the nodes in a project read from disk to the IDs of the
+/// same nodes in the snapshot it was [reconciled](reconcile) with.
+#[derive(Default)]
+pub(crate) struct ReconciledIds {
+    pub(crate) dirs: FxHashMap<LocalDirectoryId, LocalDirectoryId>,
+    pub(crate) files: FxHashMap<LocalFileId, LocalFileId>,
+}
+
+/// The type of error that can occur when saving or loading a snapshot fails.
+#[derive(cauchy::Debug, derive_more::Display, cauchy::Error)]
+#[display("{_0}")]
+pub enum SnapshotError<Ed: CollabEditor> {
+    /// The directory containing the snapshots couldn't be created.
+    CreateDirectories(<Ed::Fs as Fs>::CreateDirectoriesError),
+
+    /// The snapshot file couldn't be created.
+    CreateFile(<<Ed::Fs as Fs>::Directory as fs::Directory>::CreateFileError),
+
+    /// The snapshot file contained an invalid project.
+    Decode(collab_project::DecodeError),
+
+    /// The snapshot file ended before the peer IDs did.
+    #[display("the snapshot is truncated")]
+    Truncated,
+
+    /// Getting the node at the snapshot's path failed.
+    NodeAtPath(<Ed::Fs as Fs>::NodeAtPathError),
+
+    /// The node at the snapshot's path is not a file.
+    #[display("the node at {_0} is not a file")]
+    NotAFile(AbsPathBuf),
+
+    /// The snapshot file couldn't be read.
+    ReadFile(<<Ed::Fs as Fs>::File as fs::File>::ReadError),
+
+    /// The directory containing the snapshots couldn't be determined.
+    #[display("{_0:?}")]
+    SnapshotsDir(Ed::SnapshotsDirError),
+
+    /// The snapshot file couldn't be written.
+    WriteFile(<<Ed::Fs as Fs>::File as fs::File>::WriteError),
+}
+
+/// Loads the snapshot of the project rooted at the given path, if one was
+/// saved by a previous session.
+///
+/// Returns `None` if there's no snapshot, or if the local peer's ID is the ID
+/// of another peer in the snapshot's history: resuming from it would make the
+/// edits of two different peers indistinguishable, and corrupt the project.
+///
+/// Having the same ID as the peer that saved the snapshot is fine, and it's
+/// common since the server hands out small IDs that get reused across
+/// sessions. The snapshot contains all the edits that peer made, so resuming
+/// from it is the same as that peer picking up where it left off.
+pub(crate) async fn load<Ed: CollabEditor>(
+    project_root: &AbsPath,
+    local_id: PeerId,
+    ctx: &mut Context<Ed>,
+) -> Result<Option<Snapshot>, SnapshotError<Ed>> {
+    let snapshot_path = snapshot_path(project_root, ctx).await?;
+
+    let file = match ctx
+        .fs()
+        .node_at_path(&snapshot_path)
+        .await
+        .map_err(SnapshotError::NodeAtPath)?
+    {
+        Some(fs::Node::File(file)) => file,
+        Some(_) => return Err(SnapshotError::NotAFile(snapshot_path)),
+        None => return Ok(None),
+    };
+
+    let contents = file.read().await.map_err(SnapshotError::ReadFile)?;
+
+    let Some(contents) = contents.strip_prefix(MAGIC) else {
+        tracing::warn!("ignoring snapshot at {snapshot_path} in old format");
+        return Ok(None);
+    };
+
+    let (saved_by, contents) =
+        contents.split_first_chunk::<8>().ok_or(SnapshotError::Truncated)?;
+
+    let saved_by = PeerId::new(u64::from_le_bytes(*saved_by));
+
+    let (peer_ids, encoded_project) =
+        decode_peer_ids(contents).ok_or(SnapshotError::Truncated)?;
+
+    if local_id != saved_by && peer_ids.contains(&local_id) {
+        tracing::warn!(
+            "ignoring snapshot at {snapshot_path}: it contains edits made by \
+             a peer with the same ID as the local one ({local_id:?})"
+        );
+        return Ok(None);
+    }
+
+    let project = Project::decode(encoded_project, local_id)
+        .map_err(SnapshotError::Decode)?;
+
+    Ok(Some(Snapshot { project, peer_ids }))
+}
+
+/// Reconciles the given snapshot with the state of the same project as it
+/// currently is on disk, returning a map from the IDs of the nodes in the
+/// latter to the IDs of the same nodes in the snapshot.
+///
+/// The snapshot is updated by applying the smallest set of operations that
+/// makes it match `on_disk`, which means that all the nodes that didn't
+/// change since the snapshot was saved keep their history.
+pub(crate) fn reconcile(
+    snapshot: &mut Project,
+    on_disk: &Project,
+) -> ReconciledIds {
+    // Start by deleting the nodes that don't exist on disk anymore, or
+    // that changed kind, so that the paths are free to be re-created.
+    let mut stale_paths = Vec::new();
+    collect_stale_paths(snapshot.root(), on_disk, &mut stale_paths);
+
+    for path in stale_paths {
+        match snapshot.node_at_path_mut(&path) {
+            Some(NodeMut::Directory(dir)) => {
+                if dir.delete().is_err() {
+                    unreachable!("dir is not the project root");
+                }
+            },
+            Some(NodeMut::File(file)) => {
+                file.delete();
+            },
+            // Already deleted together with its parent.
+            None => {},
+        }
+    }
+
+    let mut ids = ReconciledIds::default();
+    ids.dirs.insert(on_disk.root().id(), snapshot.root().id());
+    reconcile_directory(on_disk.root(), snapshot, &mut ids);
+    ids
+}
+
+/// Saves a snapshot of the given project to disk, overwriting the previous
+/// one for the same project root (if any).
+pub async fn save<Ed: CollabEditor>(
+    project: &Project,
+    project_root: &AbsPath,
+    peer_ids: &[PeerId],
+    ctx: &mut Context<Ed>,
+) -> Result<(), SnapshotError<Ed>> {
+    let snapshot_path = snapshot_path(project_root, ctx).await?;
+
+    let mut contents = MAGIC.to_vec();
+    contents.extend_from_slice(&project.peer_id().into_u64().to_le_bytes());
+    encode_peer_ids(peer_ids, &mut contents);
+    project.encode_into(&mut contents);
+
+    let fs = ctx.fs();
+
+    let mut file = match fs
+        .node_at_path(&snapshot_path)
+        .await
+        .map_err(SnapshotError::NodeAtPath)?
+    {
+        Some(fs::Node::File(file)) => file,
+        Some(_) => return Err(SnapshotError::NotAFile(snapshot_path)),
+        None => {
+            let parent_path =
+                snapshot_path.parent().expect("snapshot is not the fs root");
+
+            let file_name = snapshot_path
+                .node_name()
+                .expect("snapshot is not the fs root");
+
+            fs.create_all_missing_directories(parent_path)
+                .await
+                .map_err(SnapshotError::CreateDirectories)?
+                .create_file(file_name)
+                .await
+                .map_err(SnapshotError::CreateFile)?
+        },
+    };
+
+    file.write(contents).await.map_err(SnapshotError::WriteFile)
+}
+
+/// Appends the given peer IDs to the buffer, prefixed by their number.
+fn encode_peer_ids(peer_ids: &[PeerId], buf: &mut Vec<u8>) {
+    let num_ids = u32::try_from(peer_ids.len()).expect("too many peers");
+    buf.extend_from_slice(&num_ids.to_le_bytes());
+    for peer_id in peer_ids {
+        buf.extend_from_slice(&peer_id.into_u64().to_le_bytes());
+    }
+}
+
+/// The inverse of [`encode_peer_ids`], returning the decoded IDs together
+/// with the rest of the buffer, or `None` if the buffer is too short.
+fn decode_peer_ids(buf: &[u8]) -> Option<(Vec<PeerId>, &[u8])> {
+    let (num_ids, mut rest) = buf.split_first_chunk::<4>()?;
+    let num_ids = u32::from_le_bytes(*num_ids);
+    let mut peer_ids = Vec::new();
+    for _ in 0..num_ids {
+        let (peer_id, new_rest) = rest.split_first_chunk::<8>()?;
+        peer_ids.push(PeerId::new(u64::from_le_bytes(*peer_id)));
+        rest = new_rest;
+    }
+    Some((peer_ids, rest))
+}
+
+/// Returns the path of the snapshot for the project rooted at the given
+/// path.
+async fn snapshot_path<Ed: CollabEditor>(
+    project_root: &AbsPath,
+    ctx: &mut Context<Ed>,
+) -> Result<AbsPathBuf, SnapshotError<Ed>> {
+    let snapshots_dir =
+        Ed::snapshots_dir(ctx).await.map_err(SnapshotError::SnapshotsDir)?;
+
+    let file_name = format!("{:016x}", fxhash::hash64(project_root.as_str()))
+        .parse::<NodeNameBuf>()
+        .expect("hex digits are a valid node name");
+
+    Ok(snapshots_dir.join(&file_name))
+}
+
+/// Pushes the paths of the nodes under the given snapshot directory that
+/// either don't exist in `on_disk`, or whose kind is different.
+fn collect_stale_paths(
+    snapshot_dir: Directory<'_>,
+    on_disk: &Project,
+    stale_paths: &mut Vec<AbsPathBuf>,
+) {
+    for snapshot_node in snapshot_dir.children() {
+        let path = snapshot_node.path();
+
+        match (snapshot_node, on_disk.node_at_path(&path)) {
+            (Node::Directory(dir), Some(Node::Directory(_))) => {
+                collect_stale_paths(dir, on_disk, stale_paths);
+            },
+            (Node::File(snapshot_file), Some(Node::File(disk_file)))
+                if is_same_kind(&snapshot_file, &disk_file) => {},
+            _ => stale_paths.push(path),
+        }
+    }
+}
+
+/// Returns whether the two files can be reconciled by editing the contents
+/// of the first one, as opposed to deleting and re-creating it.
+fn is_same_kind(lhs: &File<'_>, rhs: &File<'_>) -> bool {
+    match (lhs, rhs) {
+        (File::Binary(_), File::Binary(_))
+        | (File::Text(_), File::Text(_)) => true,
+        // Symlinks can't be edited in place, so we only consider them the
+        // same if they point to the same target.
+        (File::Symlink(lhs), File::Symlink(rhs)) => {
+            lhs.target_path() == rhs.target_path()
+        },
+        _ => false,
+    }
+}
+
+/// Creates or updates all the nodes under the given directory in the
+/// snapshot, recording their IDs in the given map.
+fn reconcile_directory(
+    disk_dir: Directory<'_>,
+    snapshot: &mut Project,
+    ids: &mut ReconciledIds,
+) {
+    for disk_node in disk_dir.children() {
+        let path = disk_node.path();
+
+        match disk_node {
+            Node::Directory(disk_dir) => {
+                let snapshot_dir_id = if let Some(Node::Directory(dir)) =
+                    snapshot.node_at_path(&path)
+                {
+                    dir.id()
+                } else {
+                    let Ok((_creation, dir)) = parent_mut(snapshot, &path)
+                        .create_directory(node_name(&path))
+                    else {
+                        unreachable!("stale nodes have been deleted");
+                    };
+                    dir.as_directory().id()
+                };
+                ids.dirs.insert(disk_dir.id(), snapshot_dir_id);
+                reconcile_directory(disk_dir, snapshot, ids);
+            },
+
+            Node::File(disk_file) => {
+                let snapshot_file_id = if let Some(NodeMut::File(file)) =
+                    snapshot.node_at_path_mut(&path)
+                {
+                    update_file(file, &disk_file)
+                } else {
+                    create_file(snapshot, &disk_file)
+                };
+                ids.files.insert(disk_file.id(), snapshot_file_id);
+            },
+        }
+    }
+}
+
+/// Creates a copy of the given file in the snapshot, returning its ID.
+fn create_file(snapshot: &mut Project, disk_file: &File<'_>) -> LocalFileId {
+    let path = disk_file.path();
+    let mut parent = parent_mut(snapshot, &path);
+    let file_name = node_name(&path);
+
+    let creation_res = match disk_file {
+        File::Binary(file) => {
+            parent.create_binary_file(file_name, file.contents().to_vec())
+        },
+        File::Symlink(file) => {
+            parent.create_symlink(file_name, file.target_path())
+        },
+        File::Text(file) => {
+            parent.create_text_file(file_name, file.contents().clone())
+        },
+    };
+
+    let Ok((_creation, file)) = creation_res else {
+        unreachable!("stale nodes have been deleted");
+    };
+
+    file.as_file().id()
+}
+
+/// Updates the contents of the given snapshot file to match the ones on
+/// disk, returning its ID.
+fn update_file(
+    mut snapshot_file: FileMut<'_>,
+    disk_file: &File<'_>,
+) -> LocalFileId {
+    let file_id = snapshot_file.as_file().id();
+
+    match (&mut snapshot_file, disk_file) {
+        (FileMut::Binary(snapshot_file), File::Binary(disk_file)) => {
+            if snapshot_file.as_file().contents() != disk_file.contents() {
+                snapshot_file.replace(disk_file.contents().to_vec());
+            }
+        },
+        (FileMut::Text(snapshot_file), File::Text(disk_file)) => {
+            let old_contents = snapshot_file.as_file().contents().to_string();
+            let new_contents = disk_file.contents().to_string();
+            if let Some((deleted_range, inserted_text)) =
+                text_diff(&old_contents, &new_contents)
+            {
+                snapshot_file.replace(deleted_range, inserted_text);
+            }
+        },
+        (FileMut::Symlink(_), File::Symlink(_)) => {},
+        _ => unreachable!("stale nodes have been deleted"),
+    }
+
+    file_id
+}
+
+/// Returns the name of the node at the given path.
+fn node_name(path: &AbsPath) -> NodeNameBuf {
+    path.node_name().expect("path is not the project root").to_owned()
+}
+
+/// Returns the parent directory of the node at the given path.
+fn parent_mut<'a>(
+    snapshot: &'a mut Project,
+    path: &AbsPath,
+) -> DirectoryMut<'a> {
+    let parent_path = path.parent().expect("path is not the project root");
+
+    match snapshot.node_at_path_mut(parent_path) {
+        Some(NodeMut::Directory(parent)) => parent,
+        _ => unreachable!("parents are reconciled before their children"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peer_ids_roundtrip() {
+        let peer_ids = [PeerId::new(1), PeerId::new(7), PeerId::new(42)];
+        let mut buf = Vec::new();
+        encode_peer_ids(&peer_ids, &mut buf);
+        buf.extend_from_slice(b"project");
+        let (decoded, rest) = decode_peer_ids(&buf).unwrap();
+        assert_eq!(decoded, peer_ids);
+        assert_eq!(rest, b"project");
+    }
+
+    #[test]
+    fn truncated_peer_ids() {
+        let mut buf = Vec::new();
+        encode_peer_ids(&[PeerId::new(1), PeerId::new(2)], &mut buf);
+        buf.truncate(buf.len() - 1);
+        assert!(decode_peer_ids(&buf).is_none());
+    }
+}
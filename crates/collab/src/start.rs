@@ -14,6 +14,7 @@ use editor::module::{AsyncAction, Module};
 use editor::shared::{MultiThreaded, Shared};
 use editor::{Access, Buffer, Context, Cursor, Editor};
 use either::Either;
+use fs::filter::{And, Filter as _};
 use fs::walk::FsExt;
 use fs::{Directory, File as _, Fs, Metadata, Node, Symlink};
 use futures_util::AsyncReadExt;
@@ -21,22 +22,31 @@ use fxhash::FxHashMap;
 use puff::directory::LocalDirectoryId;
 use puff::file::LocalFileId;
 
+use crate::agents::Agents;
 use crate::collab::Collab;
-use crate::config::Config;
+use crate::config::{Config, ProjectRoot, ServerAddress};
 use crate::editors::CollabEditor;
 use crate::event_stream::{EventStream, EventStreamBuilder};
+use crate::host_local::LocalServer;
+use crate::nomadignore::NomadIgnore;
 use crate::pausable_stream::PausableStream;
 use crate::peers::RemotePeers;
+use crate::permissions::Permissions;
 use crate::progress::{ProgressReporter, StartState};
 use crate::project::{self, IdMaps};
+use crate::reconnect::Reconnector;
 use crate::root_markers;
 use crate::session::{Session, SessionInfos, Sessions};
+use crate::snapshot::{self, ReconciledIds};
+use crate::undo::UndoHistory;
 
 /// TODO: docs.
-pub type ProjectFilter<Ed> =
-    Either<<Ed as CollabEditor>::ProjectFilter, AllButOne<<Ed as Editor>::Fs>>;
+pub type ProjectFilter<Ed> = Either<
+    And<<Ed as CollabEditor>::ProjectFilter, NomadIgnore>,
+    AllButOne<<Ed as Editor>::Fs>,
+>;
 
-type Markers = root_markers::GitDirectory;
+type Markers = root_markers::NodeNames;
 
 /// The `Action` used to start a new collaborative editing session.
 #[derive(cauchy::Clone)]
@@ -48,14 +58,21 @@ pub struct Start<Ed: CollabEditor> {
 
 impl<Ed: CollabEditor> Start<Ed> {
     /// Constructs a [`Project`] by reading the contents of the file or
-    /// directory at the given path.
-    #[allow(clippy::too_many_lines)]
+    /// directory at the given path, skipping the nodes matching the given
+    /// `exclude` patterns or the ones in the project's `.nomadignore`.
+    ///
+    /// If the project is resumed from a [snapshot](crate::snapshot), the IDs
+    /// of the peers in its history are also returned.
+    #[allow(clippy::too_many_lines, clippy::type_complexity)]
     pub async fn read_project(
         root_path: &AbsPath,
         local_id: PeerId,
+        exclude: &[String],
         ctx: &mut Context<Ed>,
-    ) -> Result<(Project, EventStream<Ed>, IdMaps<Ed>), ReadProjectError<Ed>>
-    {
+    ) -> Result<
+        (Project, EventStream<Ed>, IdMaps<Ed>, Vec<PeerId>),
+        ReadProjectError<Ed>,
+    > {
         let fs = ctx.fs();
 
         let root_node = fs
@@ -70,7 +87,10 @@ impl<Ed: CollabEditor> Start<Ed> {
             Node::Directory(dir) => {
                 let filter = Ed::project_filter(&dir, ctx)
                     .map_err(ReadProjectError::ProjectFilter)?;
-                (dir, Either::Left(filter))
+                let nomadignore = NomadIgnore::read(&fs, dir.path(), exclude)
+                    .await
+                    .map_err(ReadProjectError::NomadIgnore)?;
+                (dir, Either::Left(filter.and(nomadignore)))
             },
             // The user wants to collaborate on a single file. The root must
             // always be a directory, so we just use its parent together with a
@@ -90,7 +110,7 @@ impl<Ed: CollabEditor> Start<Ed> {
             },
         };
 
-        let (mut project, stream_builder, node_id_maps) = ctx
+        let (mut project, stream_builder, mut node_id_maps) = ctx
             .spawn_background(async move {
                 let walker = fs.walk(&project_root).filter(project_filter);
 
@@ -129,6 +149,35 @@ impl<Ed: CollabEditor> Start<Ed> {
             })
             .await?;
 
+        // If a previous session saved a snapshot of this project, resume from
+        // it and only apply the changes made on disk since then, so that the
+        // files that didn't change keep their history.
+        //
+        // TODO: peers joining the session still receive the whole encoded
+        // project, even if they have a snapshot of it themselves. Only sending
+        // them the delta requires the protocol to exchange version vectors.
+        let mut history = Vec::new();
+
+        match snapshot::load(root_path, local_id, ctx).await {
+            Ok(Some(mut snapshot)) => {
+                let reconciled_ids =
+                    snapshot::reconcile(&mut snapshot.project, &project);
+                if node_id_maps.remap(&reconciled_ids).is_some() {
+                    project = snapshot.project;
+                    history = snapshot.peer_ids;
+                } else {
+                    tracing::warn!(
+                        "couldn't resume {root_path} from its snapshot: not \
+                         all of its nodes were reconciled"
+                    );
+                }
+            },
+            Ok(None) => {},
+            Err(err) => {
+                tracing::warn!("couldn't load snapshot of {root_path}: {err}");
+            },
+        }
+
         let mut event_stream = stream_builder.build(ctx);
 
         let mut id_maps = IdMaps::default();
@@ -176,19 +225,81 @@ impl<Ed: CollabEditor> Start<Ed> {
         id_maps.node2dir = node_id_maps.node2dir;
         id_maps.node2file = node_id_maps.node2file;
 
-        Ok((project, event_stream, id_maps))
+        Ok((project, event_stream, id_maps, history))
+    }
+
+    /// Starts a new session on the server at the given address, reporting
+    /// the outcome to the user.
+    pub(crate) async fn call_and_report(
+        &self,
+        server_addr: ServerAddress<'static>,
+        ctx: &mut Context<Ed>,
+    ) {
+        let mut progress_reporter =
+            <Ed::ProgressReporter as ProgressReporter<Ed, Self>>::new(ctx);
+
+        let result = self
+            .call_with_server(server_addr, None, &mut progress_reporter, ctx)
+            .await;
+
+        Self::report(result, progress_reporter, ctx).await;
+    }
+
+    /// Reports the outcome of starting a session to the user.
+    pub(crate) async fn report(
+        result: Result<SessionInfos<Ed>, StartError<Ed>>,
+        progress_reporter: Ed::ProgressReporter,
+        ctx: &mut Context<Ed>,
+    ) {
+        match result {
+            Ok(session_infos) => {
+                ProgressReporter::<Ed, Self>::report_success(
+                    progress_reporter,
+                    (),
+                    ctx,
+                );
+                Ed::on_session_started(&session_infos, ctx).await;
+            },
+            Err(StartError::UserDidNotConfirm) => {
+                ProgressReporter::<Ed, Self>::report_cancellation(
+                    progress_reporter,
+                    ctx,
+                );
+            },
+            Err(start_error) => {
+                ProgressReporter::<Ed, Self>::report_error(
+                    progress_reporter,
+                    start_error,
+                    ctx,
+                );
+            },
+        }
     }
 
-    #[allow(clippy::too_many_lines)]
     pub(crate) async fn call_inner(
         &self,
         progress_reporter: &mut impl ProgressReporter<Ed, Self>,
         ctx: &mut Context<Ed>,
     ) -> Result<SessionInfos<Ed>, StartError<Ed>> {
-        let jwt = self
-            .auth_state
-            .with(Clone::clone)
-            .ok_or(StartError::UserNotLoggedIn)?;
+        let server_addr = self.config.with(|c| c.server_address.clone());
+        self.call_with_server(server_addr, None, progress_reporter, ctx).await
+    }
+
+    /// Same as [`call_inner`](Self::call_inner), but connects to the server
+    /// at the given address instead of the one in the [`Config`].
+    ///
+    /// If the server runs inside the editor's process, it's kept running
+    /// until the session ends.
+    pub(crate) async fn call_with_server(
+        &self,
+        server_addr: ServerAddress<'static>,
+        local_server: Option<LocalServer>,
+        progress_reporter: &mut impl ProgressReporter<Ed, Self>,
+        ctx: &mut Context<Ed>,
+    ) -> Result<SessionInfos<Ed>, StartError<Ed>> {
+        if self.auth_state.with(Option::is_none) {
+            return Err(StartError::UserNotLoggedIn);
+        }
 
         let buffer_id = ctx.with_borrowed(|ctx| {
             ctx.current_buffer()
@@ -196,9 +307,39 @@ impl<Ed: CollabEditor> Start<Ed> {
                 .ok_or(StartError::NoBufferFocused)
         })?;
 
-        let project_root = search_project_root(buffer_id, ctx)
-            .await
-            .map_err(StartError::SearchProjectRoot)?;
+        let project_root_config = self.config.with(|c| c.project_root.clone());
+
+        let project_root =
+            search_project_root(buffer_id, project_root_config, ctx)
+                .await
+                .map_err(StartError::SearchProjectRoot)?;
+
+        self.call_at_root(
+            project_root,
+            server_addr,
+            local_server,
+            progress_reporter,
+            ctx,
+        )
+        .await
+    }
+
+    /// Starts a new session on the project rooted at the given path, instead
+    /// of searching for the root of the project containing the focused
+    /// buffer.
+    #[allow(clippy::too_many_lines)]
+    pub(crate) async fn call_at_root(
+        &self,
+        project_root: AbsPathBuf,
+        server_addr: ServerAddress<'static>,
+        local_server: Option<LocalServer>,
+        progress_reporter: &mut impl ProgressReporter<Ed, Self>,
+        ctx: &mut Context<Ed>,
+    ) -> Result<SessionInfos<Ed>, StartError<Ed>> {
+        let jwt = self
+            .auth_state
+            .with(Clone::clone)
+            .ok_or(StartError::UserNotLoggedIn)?;
 
         if !Ed::confirm_start(&project_root, ctx).await {
             return Err(StartError::UserDidNotConfirm);
@@ -207,20 +348,18 @@ impl<Ed: CollabEditor> Start<Ed> {
         let project_name =
             project_root.node_name().ok_or(StartError::ProjectRootIsFsRoot)?;
 
-        let server_addr = self.config.with(|c| c.server_address.clone());
-
         progress_reporter.report_progress(
             StartState::ConnectingToServer(server_addr.borrow()),
             ctx,
         );
 
-        let (reader, writer) = Ed::connect_to_server(server_addr, ctx)
+        let (reader, writer) = Ed::connect_to_server(server_addr.clone(), ctx)
             .await
             .map_err(StartError::ConnectToServer)?
             .split();
 
         let knock = collab_client::Knock::<Ed::ServerParams> {
-            auth_infos: jwt.into(),
+            auth_infos: jwt.clone().into(),
             session_intent: collab_client::SessionIntent::StartNew(
                 project_name.to_owned(),
             ),
@@ -239,32 +378,55 @@ impl<Ed: CollabEditor> Start<Ed> {
             ctx,
         );
 
-        let (project, event_stream, id_maps) =
-            Self::read_project(&project_root, local_peer.id, ctx)
+        let exclude = self.config.with(|c| c.exclude.clone());
+
+        let (project, event_stream, id_maps, history) =
+            Self::read_project(&project_root, local_peer.id, &exclude, ctx)
                 .await
                 .map_err(StartError::ReadProject)?;
 
         let remote_peers = RemotePeers::new(welcome.other_peers, &project);
 
+        remote_peers.extend_history(history);
+
+        let host_id = Shared::new(welcome.host_id);
+
+        let permissions = Shared::<Permissions>::default();
+
         let project = project::Project {
             agent_id: event_stream.agent_id(),
+            blames: FxHashMap::default(),
+            host_id: host_id.clone(),
             id_maps,
             inner: project,
             local_peer: local_peer.clone(),
             peer_cursors: FxHashMap::default(),
             peer_selections: FxHashMap::default(),
+            permissions: permissions.clone(),
             remote_peers: remote_peers.clone(),
             root_path: project_root.clone(),
+            session_id: Some(welcome.session_id),
+            undo_history: UndoHistory::default(),
         };
 
         let message_rx = PausableStream::new(welcome.rx);
 
+        let reconnector = Reconnector::new(
+            jwt,
+            server_addr,
+            welcome.session_id,
+            |welcome| (welcome.tx, welcome.rx),
+        );
+
         let (stop_tx, stop_rx) = flume::bounded(1);
 
         let session_infos = SessionInfos {
-            host_id: welcome.host_id,
+            files: Default::default(),
+            host_id,
+            is_connected: Shared::new(true),
             local_peer,
             remote_peers,
+            permissions,
             pause_remote: message_rx.remote(),
             project_access: Default::default(),
             project_root_path: project_root,
@@ -273,12 +435,17 @@ impl<Ed: CollabEditor> Start<Ed> {
         };
 
         let session = Session {
+            agents: Agents::new(self.config.with(|c| c.agent_socket.clone())),
             event_stream,
+            local_server,
             message_rx,
             message_tx: welcome.tx,
             project,
             project_access: session_infos.project_access.clone(),
+            record: self.config.with(|c| c.record_sessions),
+            save_snapshots: true,
             stop_rx,
+            reconnector,
             remove_on_drop: self.sessions.insert(session_infos.clone()),
         };
 
@@ -299,32 +466,8 @@ impl<Ed: CollabEditor> AsyncAction<Ed> for Start<Ed> {
     type Args = ();
 
     async fn call(&mut self, _: Self::Args, ctx: &mut Context<Ed>) {
-        let mut progress_reporter =
-            <Ed::ProgressReporter as ProgressReporter<Ed, Self>>::new(ctx);
-
-        match self.call_inner(&mut progress_reporter, ctx).await {
-            Ok(session_infos) => {
-                ProgressReporter::<Ed, Self>::report_success(
-                    progress_reporter,
-                    (),
-                    ctx,
-                );
-                Ed::on_session_started(&session_infos, ctx).await;
-            },
-            Err(StartError::UserDidNotConfirm) => {
-                ProgressReporter::<Ed, Self>::report_cancellation(
-                    progress_reporter,
-                    ctx,
-                );
-            },
-            Err(start_error) => {
-                ProgressReporter::<Ed, Self>::report_error(
-                    progress_reporter,
-                    start_error,
-                    ctx,
-                );
-            },
-        }
+        let server_addr = self.config.with(|c| c.server_address.clone());
+        self.call_and_report(server_addr, ctx).await;
     }
 }
 
@@ -343,9 +486,10 @@ impl<Ed: CollabEditor> ToCompletionFn<Ed> for Start<Ed> {
 }
 
 /// Searches for the root of the project containing the buffer with the given
-/// ID.
-async fn search_project_root<Ed: CollabEditor>(
+/// ID, looking for the root markers in the given [`ProjectRoot`] config.
+pub async fn search_project_root<Ed: CollabEditor>(
     buffer_id: Ed::BufferId,
+    config: ProjectRoot,
     ctx: &mut Context<Ed>,
 ) -> Result<AbsPathBuf, SearchProjectRootError<Ed>> {
     if let Some(lsp_res) = Ed::lsp_root(buffer_id.clone(), ctx).transpose() {
@@ -360,11 +504,15 @@ async fn search_project_root<Ed: CollabEditor>(
 
     let mut fs = ctx.fs();
 
-    let maybe_home =
-        fs.home().await.map_err(SearchProjectRootError::HomeDir)?;
+    let maybe_home = if config.stop_at_home {
+        fs.home().await.map_err(SearchProjectRootError::HomeDir)?
+    } else {
+        None
+    };
 
     let args = root_markers::FindRootArgs {
-        marker: root_markers::GitDirectory,
+        marker: root_markers::NodeNames(config.markers),
+        precedence: config.precedence,
         start_from: &buffer_path,
         stop_at: maybe_home.as_ref().map(|dir| dir.path()),
     };
@@ -533,6 +681,10 @@ pub enum ReadProjectError<Ed: CollabEditor> {
     /// TODO: docs.
     GetRoot(<Ed::Fs as Fs>::NodeAtPathError),
 
+    /// The project's `.nomadignore` file couldn't be read.
+    #[display("couldn't read .nomadignore: {_0}")]
+    NomadIgnore(fs::ReadFileToStringError<Ed::Fs>),
+
     /// TODO: docs.
     #[display("no file or directory at the project root: {_0}")]
     NoNodeAtRootPath(AbsPathBuf),
@@ -595,6 +747,43 @@ struct NodeIdMaps<Fs: fs::Fs> {
     node2file: FxHashMap<Fs::NodeId, LocalFileId>,
 }
 
+impl<Fs: fs::Fs> NodeIdMaps<Fs> {
+    /// Replaces the IDs of the project's nodes with the IDs of the same nodes
+    /// in the snapshot the project was reconciled with.
+    ///
+    /// Returns `None` and leaves the maps untouched if any of the nodes is
+    /// missing from the reconciled IDs.
+    fn remap(&mut self, reconciled_ids: &ReconciledIds) -> Option<()> {
+        let file2node = self
+            .file2node
+            .iter()
+            .map(|(file_id, node_id)| {
+                Some((*reconciled_ids.files.get(file_id)?, node_id.clone()))
+            })
+            .collect::<Option<_>>()?;
+
+        let node2file = self
+            .node2file
+            .iter()
+            .map(|(node_id, file_id)| {
+                Some((node_id.clone(), *reconciled_ids.files.get(file_id)?))
+            })
+            .collect::<Option<_>>()?;
+
+        let node2dir = self
+            .node2dir
+            .iter()
+            .map(|(node_id, dir_id)| {
+                Some((node_id.clone(), *reconciled_ids.dirs.get(dir_id)?))
+            })
+            .collect::<Option<_>>()?;
+
+        *self = Self { file2node, node2dir, node2file };
+
+        Some(())
+    }
+}
+
 impl<Fs: fs::Fs> fs::filter::Filter<Fs> for AllButOne<Fs> {
     type Error = Infallible;
 
@@ -0,0 +1,106 @@
+//! Contains the [`Statusline`] function, which returns a compact summary of
+//! the current sessions to be shown in the editor's statusline.
+
+use core::fmt;
+
+use editor::context::Borrowed;
+use editor::module::Action;
+use editor::{Access, Context};
+
+use crate::collab::Collab;
+use crate::editors::CollabEditor;
+use crate::session::{SessionInfos, Sessions};
+
+/// A function returning a string summarizing the state of all the current
+/// sessions, or an empty string if there are none.
+///
+/// It only reads state that's already cached by the sessions, so it's cheap
+/// enough to be called on every redraw of the statusline.
+#[derive(cauchy::Clone)]
+pub struct Statusline<Ed: CollabEditor> {
+    sessions: Sessions<Ed>,
+}
+
+/// The state of a session as shown by the [`Statusline`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionStatus {
+    /// Whether the session is connected to the server, or is trying to
+    /// reconnect to it.
+    pub is_connected: bool,
+
+    /// Whether receiving messages from the other peers is currently
+    /// [`Pause`](crate::pause::Pause)d.
+    pub is_paused: bool,
+
+    /// The number of messages received while paused which will be integrated
+    /// once the session is resumed.
+    pub num_pending: usize,
+
+    /// The number of remote peers in the session.
+    pub num_peers: usize,
+
+    /// The name of the session's project.
+    pub project_name: String,
+}
+
+impl<Ed: CollabEditor> Statusline<Ed> {
+    pub(crate) fn call_inner(&self) -> String {
+        let mut statuses = Vec::new();
+        self.sessions.for_each(|infos| {
+            statuses.push(SessionStatus::new(infos));
+        });
+        if statuses.is_empty() {
+            return String::new();
+        }
+        statuses.sort_by(|lhs, rhs| lhs.project_name.cmp(&rhs.project_name));
+        Ed::render_statusline(&statuses)
+    }
+}
+
+impl SessionStatus {
+    fn new<Ed: CollabEditor>(infos: &SessionInfos<Ed>) -> Self {
+        Self {
+            is_connected: infos.is_connected.copied(),
+            is_paused: infos.pause_remote.is_paused(),
+            num_pending: infos.pause_remote.num_buffered(),
+            num_peers: infos.remote_peers.with(|map| map.len()),
+            project_name: infos.proj_name().as_str().to_owned(),
+        }
+    }
+}
+
+impl<Ed: CollabEditor> Action<Ed> for Statusline<Ed> {
+    const NAME: &str = "statusline";
+
+    type Args<'args> = ();
+    type Return = String;
+
+    fn call(
+        &mut self,
+        (): Self::Args<'_>,
+        _: &mut Context<Ed, Borrowed<'_>>,
+    ) -> Self::Return {
+        self.call_inner()
+    }
+}
+
+impl<Ed: CollabEditor> From<&Collab<Ed>> for Statusline<Ed> {
+    fn from(collab: &Collab<Ed>) -> Self {
+        Self { sessions: collab.sessions.clone() }
+    }
+}
+
+impl fmt::Display for SessionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = if self.num_peers == 1 { "" } else { "s" };
+        write!(f, "{}: {} peer{plural}", self.project_name, self.num_peers)?;
+
+        if !self.is_connected {
+            f.write_str(", reconnecting")?;
+        } else if self.is_paused {
+            write!(f, ", paused ({} pending)", self.num_pending)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,371 @@
+//! Contains the [`Undo`] action and the [`UndoHistory`] it's based on.
+
+use core::ops::Range;
+use core::time::Duration;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use abs_path::AbsPathBuf;
+use collab_project::PeerId;
+use collab_project::text::{
+    Anchor,
+    AnchorBias,
+    TextFile,
+    TextFileMut,
+    TextReplacement,
+};
+use collab_types::puff::file::LocalFileId;
+use collab_types::text::TextEdit;
+use editor::command::ToCompletionFn;
+use editor::module::AsyncAction;
+use editor::{Buffer, Context};
+use fxhash::FxHashMap;
+
+use crate::collab::Collab;
+use crate::editors::CollabEditor;
+use crate::session::Sessions;
+
+/// Local edits to the same file made less than this far apart are grouped
+/// into a single undo step.
+pub const GROUP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The maximum number of undo steps kept for each file. Once a file reaches
+/// it, every new step drops the oldest one.
+pub const MAX_STEPS: usize = 1000;
+
+/// An `Action` that undoes the local user's last edit in the focused buffer,
+/// leaving the edits made by the other peers untouched.
+#[derive(cauchy::Clone)]
+pub struct Undo<Ed: CollabEditor> {
+    sessions: Sessions<Ed>,
+}
+
+/// The history of the edits made by the local peer to the files in a
+/// project, used by the [`Undo`] and [`Redo`](crate::redo::Redo) actions.
+///
+/// Every step is stored as a list of operations anchored to the text, so
+/// that it can be reverted on top of any remote edit integrated after it.
+#[derive(Default)]
+pub struct UndoHistory {
+    files: FxHashMap<LocalFileId, FileHistory>,
+}
+
+/// Whether to revert the last undo step or the last redo step.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Revert {
+    /// Revert the last undo step, i.e. undo the last edit.
+    Undo,
+
+    /// Revert the last redo step, i.e. redo the last undone edit.
+    Redo,
+}
+
+#[derive(Default)]
+struct FileHistory {
+    /// When the local peer last edited the file, or `None` if the last
+    /// step was created by reverting another one.
+    last_edited_at: Option<Instant>,
+    redo_stack: Vec<UndoStep>,
+    undo_stack: VecDeque<UndoStep>,
+}
+
+/// The operations that revert an edit, in the order they were recorded.
+/// They have to be applied in reverse.
+struct UndoStep {
+    ops: Vec<UndoOp>,
+}
+
+enum UndoOp {
+    /// Deletes the text in the given range that was inserted by the local
+    /// peer.
+    Delete(Range<Anchor>),
+
+    /// Inserts the given text at the given anchor.
+    Insert(Anchor, String),
+}
+
+impl<Ed: CollabEditor> Undo<Ed> {
+    pub(crate) async fn call_inner(
+        &self,
+        ctx: &mut Context<Ed>,
+    ) -> Result<(), UndoError> {
+        revert(&self.sessions, Revert::Undo, ctx).await
+    }
+}
+
+impl UndoHistory {
+    /// Applies the given replacements made by the local peer at the given
+    /// time to the file, recording them in the file's history.
+    pub fn edit(
+        &mut self,
+        file: &mut TextFileMut<'_>,
+        replacements: impl IntoIterator<Item = TextReplacement>,
+        edited_at: Instant,
+    ) -> TextEdit {
+        let mut ops = Vec::new();
+
+        let text_edit = replacements
+            .into_iter()
+            .map(|replacement| apply(file, replacement, &mut ops))
+            .reduce(merge)
+            .unwrap_or_else(|| file.edit([]));
+
+        self.files
+            .entry(file.local_id())
+            .or_default()
+            .push(UndoStep { ops }, edited_at);
+
+        text_edit
+    }
+
+    /// Reverts the last undo or redo step of the given file, returning the
+    /// edit to send to the other peers and the replacements to apply to the
+    /// file's buffer, or `None` if there's nothing to revert.
+    pub fn revert(
+        &mut self,
+        file: &mut TextFileMut<'_>,
+        local_id: PeerId,
+        revert: Revert,
+    ) -> Option<(TextEdit, Vec<TextReplacement>)> {
+        let history = self.files.get_mut(&file.local_id())?;
+
+        loop {
+            let step = match revert {
+                Revert::Undo => history.undo_stack.pop_back()?,
+                Revert::Redo => history.redo_stack.pop()?,
+            };
+
+            let mut inverse_ops = Vec::new();
+            let mut replacements = Vec::new();
+            let mut text_edit = None;
+
+            for op in step.ops.into_iter().rev() {
+                let op_replacements =
+                    op.replacements(&file.as_file(), local_id);
+                for replacement in op_replacements {
+                    replacements.push(replacement.clone());
+                    let edit = apply(file, replacement, &mut inverse_ops);
+                    text_edit = Some(match text_edit {
+                        Some(text_edit) => merge(text_edit, edit),
+                        None => edit,
+                    });
+                }
+            }
+
+            // All the text in the step could've been deleted by other peers
+            // in the meantime. In that case we move on to the next one.
+            let Some(text_edit) = text_edit else { continue };
+
+            let inverse = UndoStep { ops: inverse_ops };
+
+            match revert {
+                Revert::Undo => history.redo_stack.push(inverse),
+                Revert::Redo => history.undo_stack.push_back(inverse),
+            }
+
+            history.last_edited_at = None;
+
+            return Some((text_edit, replacements));
+        }
+    }
+}
+
+impl<Ed: CollabEditor> AsyncAction<Ed> for Undo<Ed> {
+    const NAME: &str = "undo";
+
+    type Args = ();
+
+    async fn call(&mut self, _: Self::Args, ctx: &mut Context<Ed>) {
+        if let Err(err) = self.call_inner(ctx).await {
+            Ed::on_undo_error(err, ctx);
+        }
+    }
+}
+
+/// The type of error that can occur when [`Undo`] or
+/// [`Redo`](crate::redo::Redo) fail.
+#[derive(Debug, derive_more::Display, cauchy::Error, PartialEq)]
+pub enum UndoError {
+    /// The focused buffer is not a text file in any of the projects being
+    /// collaborated on.
+    #[display("{_0} is not a text file in any of the active sessions")]
+    BufferNotInSession(AbsPathBuf),
+
+    /// No buffer is focused.
+    #[display("No buffer is focused")]
+    NoBufferFocused,
+
+    /// There are no undone edits to redo in the focused buffer.
+    #[display("Already at newest change")]
+    NothingToRedo,
+
+    /// There are no local edits to undo in the focused buffer.
+    #[display("Already at oldest change")]
+    NothingToUndo,
+}
+
+impl FileHistory {
+    fn push(&mut self, step: UndoStep, edited_at: Instant) {
+        if step.ops.is_empty() {
+            return;
+        }
+
+        self.redo_stack.clear();
+
+        let should_group = self.last_edited_at.is_some_and(|last| {
+            edited_at.saturating_duration_since(last) < GROUP_INTERVAL
+        });
+
+        self.last_edited_at = Some(edited_at);
+
+        if should_group && let Some(last_step) = self.undo_stack.back_mut() {
+            last_step.ops.extend(step.ops);
+            return;
+        }
+
+        if self.undo_stack.len() == MAX_STEPS {
+            self.undo_stack.pop_front();
+        }
+
+        self.undo_stack.push_back(step);
+    }
+}
+
+impl UndoOp {
+    /// Returns the replacements to apply to the file to perform this
+    /// operation, in the order they should be applied.
+    fn replacements(
+        &self,
+        file: &TextFile<'_>,
+        local_id: PeerId,
+    ) -> Vec<TextReplacement> {
+        match self {
+            Self::Delete(range) => {
+                let (Some(start), Some(end)) = (
+                    file.resolve_anchor(range.start),
+                    file.resolve_anchor(range.end),
+                ) else {
+                    return Vec::new();
+                };
+
+                // Only delete the text we inserted, not the text other peers
+                // inserted in the same range after we did. The ranges are
+                // deleted back to front to not invalidate their offsets.
+                let mut deleted_ranges = file
+                    .authorship()
+                    .filter(|(_, author)| *author == local_id)
+                    .map(|(range, _)| {
+                        range.start.max(start)..range.end.min(end)
+                    })
+                    .filter(|range| range.start < range.end)
+                    .map(|deleted_range| TextReplacement {
+                        deleted_range,
+                        inserted_text: Default::default(),
+                    })
+                    .collect::<Vec<_>>();
+
+                deleted_ranges.reverse();
+                deleted_ranges
+            },
+            Self::Insert(anchor, text) => file
+                .resolve_anchor(*anchor)
+                .map(|offset| TextReplacement {
+                    deleted_range: offset..offset,
+                    inserted_text: text.as_str().into(),
+                })
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+impl<Ed: CollabEditor> From<&Collab<Ed>> for Undo<Ed> {
+    fn from(collab: &Collab<Ed>) -> Self {
+        Self { sessions: collab.sessions.clone() }
+    }
+}
+
+impl<Ed: CollabEditor> ToCompletionFn<Ed> for Undo<Ed> {
+    fn to_completion_fn(&self) {}
+}
+
+/// Reverts the last undo or redo step of the focused buffer.
+pub(crate) async fn revert<Ed: CollabEditor>(
+    sessions: &Sessions<Ed>,
+    revert: Revert,
+    ctx: &mut Context<Ed>,
+) -> Result<(), UndoError> {
+    let (buffer_id, buffer_path) = ctx.with_borrowed(|ctx| {
+        ctx.current_buffer()
+            .map(|buf| (buf.id(), buf.path().into_owned()))
+            .ok_or(UndoError::NoBufferFocused)
+    })?;
+
+    let Some(sesh) =
+        sessions.find(|sesh| buffer_path.starts_with(&sesh.project_root_path))
+    else {
+        return Err(UndoError::BufferNotInSession(buffer_path));
+    };
+
+    let nothing_to_revert = match revert {
+        Revert::Undo => UndoError::NothingToUndo,
+        Revert::Redo => UndoError::NothingToRedo,
+    };
+
+    let not_in_session = UndoError::BufferNotInSession(buffer_path.clone());
+
+    sesh.project_access
+        .with_mut_and_send(async move |proj, ctx| {
+            match proj.revert_local_edit(buffer_id, revert, ctx).await {
+                Some(Some(message)) => (Ok(()), vec![message]),
+                Some(None) => (Err(nothing_to_revert), Vec::new()),
+                None => (Err(not_in_session), Vec::new()),
+            }
+        })
+        .await
+        .unwrap_or(Err(UndoError::BufferNotInSession(buffer_path)))
+}
+
+/// Applies the given replacement to the file, pushing the operations that
+/// revert it to `inverse_ops`.
+fn apply(
+    file: &mut TextFileMut<'_>,
+    replacement: TextReplacement,
+    inverse_ops: &mut Vec<UndoOp>,
+) -> TextEdit {
+    let offset = replacement.deleted_range.start;
+
+    let inserted_len = replacement.inserted_text.len();
+
+    let deleted_text = (!replacement.deleted_range.is_empty()).then(|| {
+        file.as_file()
+            .contents()
+            .byte_slice(replacement.deleted_range.clone())
+            .to_string()
+    });
+
+    let text_edit = file.edit([replacement]);
+
+    let file = file.as_file();
+
+    if let Some(deleted_text) = deleted_text {
+        let anchor = file.create_anchor(offset, AnchorBias::Left);
+        inverse_ops.push(UndoOp::Insert(anchor, deleted_text));
+    }
+
+    if inserted_len > 0 {
+        let start = file.create_anchor(offset, AnchorBias::Right);
+        let end = file.create_anchor(offset + inserted_len, AnchorBias::Left);
+        inverse_ops.push(UndoOp::Delete(start..end));
+    }
+
+    text_edit
+}
+
+/// Merges two edits to the same file into one.
+fn merge(mut edit: TextEdit, other: TextEdit) -> TextEdit {
+    debug_assert_eq!(edit.file_id, other.file_id);
+    edit.deletions.extend(other.deletions);
+    edit.insertions.extend(other.insertions);
+    edit
+}
@@ -0,0 +1,64 @@
+//! TODO: docs.
+
+use editor::Context;
+use editor::command::ToCompletionFn;
+use editor::module::AsyncAction;
+
+use crate::collab::Collab;
+use crate::editors::CollabEditor;
+use crate::session::Sessions;
+
+/// The `Action` used to stop [`Follow`](crate::follow::Follow)ing a remote
+/// peer.
+#[derive(cauchy::Clone)]
+pub struct Unfollow<Ed: CollabEditor> {
+    sessions: Sessions<Ed>,
+}
+
+impl<Ed: CollabEditor> Unfollow<Ed> {
+    pub(crate) fn call_inner(
+        &self,
+        ctx: &mut Context<Ed>,
+    ) -> Result<(), UnfollowError> {
+        let mut was_following = false;
+
+        self.sessions.for_each(|infos| {
+            if let Some(peer) = infos.remote_peers.set_followed(None) {
+                Ed::on_stopped_following(&peer, ctx);
+                was_following = true;
+            }
+        });
+
+        if was_following { Ok(()) } else { Err(UnfollowError::NotFollowing) }
+    }
+}
+
+impl<Ed: CollabEditor> AsyncAction<Ed> for Unfollow<Ed> {
+    const NAME: &str = "unfollow";
+
+    type Args = ();
+
+    async fn call(&mut self, _: Self::Args, ctx: &mut Context<Ed>) {
+        if let Err(err) = self.call_inner(ctx) {
+            Ed::on_unfollow_error(err, ctx);
+        }
+    }
+}
+
+/// The type of error that can occur when [`Unfollow`]ing fails.
+#[derive(Debug, derive_more::Display, cauchy::Error, PartialEq)]
+pub enum UnfollowError {
+    /// The user is not following any peer.
+    #[display("You're not following anyone")]
+    NotFollowing,
+}
+
+impl<Ed: CollabEditor> From<&Collab<Ed>> for Unfollow<Ed> {
+    fn from(collab: &Collab<Ed>) -> Self {
+        Self { sessions: collab.sessions.clone() }
+    }
+}
+
+impl<Ed: CollabEditor> ToCompletionFn<Ed> for Unfollow<Ed> {
+    fn to_completion_fn(&self) {}
+}
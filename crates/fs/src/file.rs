@@ -28,6 +28,29 @@ pub trait File: Send + Sync {
     /// TODO: docs.
     type WriteError: Error + Send;
 
+    /// Appends the given chunks to the end of the file, leaving its
+    /// existing contents untouched.
+    ///
+    /// See [`write_chunks`](Self::write_chunks) for a caveat about the
+    /// returned future not being `Send`.
+    fn append_chunks<Chunks, Chunk>(
+        &mut self,
+        chunks: Chunks,
+    ) -> impl Future<Output = Result<(), Self::WriteError>> + Send
+    where
+        Chunks: IntoIterator<Item = Chunk> + Send,
+        Chunks::IntoIter: Send,
+        Chunk: AsRef<[u8]> + Send;
+
+    /// Appends the given contents to the end of the file.
+    #[inline]
+    fn append<C: AsRef<[u8]> + Send>(
+        &mut self,
+        contents: C,
+    ) -> impl Future<Output = Result<(), Self::WriteError>> + Send {
+        self.append_chunks(core::iter::once(contents))
+    }
+
     /// TODO: docs.
     #[inline]
     fn byte_len(&self) -> usize {
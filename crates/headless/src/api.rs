@@ -0,0 +1,86 @@
+//! TODO: docs.
+
+use std::collections::HashMap;
+
+use editor::ByteOffset;
+use editor::command::{CommandArgs, CommandCompletion};
+use editor::notify::Name;
+
+use crate::value::Value;
+
+/// The [`Api`](editor::Api) type used by the [`Headless`](crate::Headless)
+/// editor.
+///
+/// There's no user interface to call commands from, so only the plugin's
+/// functions and constants are kept, and they can be called from Rust via
+/// [`call_function`](Self::call_function).
+#[derive(Default)]
+pub struct HeadlessApi {
+    constants: HashMap<Name, Value>,
+    functions: HashMap<Name, Box<dyn FnMut(Value) -> Option<Value>>>,
+    submodules: HashMap<Name, Self>,
+}
+
+impl HeadlessApi {
+    /// Calls the function registered under the given name, returning `None`
+    /// if there's no such function.
+    pub fn call_function(
+        &mut self,
+        function_name: &str,
+        arg: impl Into<Value>,
+    ) -> Option<Option<Value>> {
+        self.functions.get_mut(function_name).map(|fun| fun(arg.into()))
+    }
+
+    /// Returns the constant registered under the given name, if any.
+    pub fn constant(&self, constant_name: &str) -> Option<&Value> {
+        self.constants.get(constant_name)
+    }
+
+    /// Returns the API of the submodule registered under the given name, if
+    /// any.
+    pub fn submodule(&mut self, module_name: &str) -> Option<&mut Self> {
+        self.submodules.get_mut(module_name)
+    }
+}
+
+impl editor::Api for HeadlessApi {
+    type Value = Value;
+
+    #[track_caller]
+    fn add_constant(&mut self, constant_name: Name, value: Self::Value) {
+        assert!(!self.constants.contains_key(constant_name));
+        self.constants.insert(constant_name, value);
+    }
+
+    #[track_caller]
+    fn add_function<Fun>(&mut self, function_name: Name, function: Fun)
+    where
+        Fun: FnMut(Self::Value) -> Option<Self::Value> + 'static,
+    {
+        assert!(!self.functions.contains_key(function_name));
+        self.functions.insert(function_name, Box::new(function));
+    }
+
+    #[track_caller]
+    fn add_submodule(&mut self, module_name: Name, module_api: Self) {
+        assert!(!self.submodules.contains_key(module_name));
+        self.submodules.insert(module_name, module_api);
+    }
+
+    fn add_command<Command, CompletionFn, Completions>(
+        &mut self,
+        _: Name,
+        _: Command,
+        _: CompletionFn,
+    ) where
+        Command: FnMut(CommandArgs) + 'static,
+        CompletionFn: FnMut(CommandArgs<ByteOffset>) -> Completions + 'static,
+        Completions: IntoIterator<Item = CommandCompletion>,
+    {
+    }
+
+    fn new(_: Name) -> Self {
+        Self::default()
+    }
+}
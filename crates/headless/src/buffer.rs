@@ -0,0 +1,249 @@
+//! TODO: docs.
+//!
+//! The [`Headless`] editor never has any buffers open, so the types in this
+//! module can't be constructed.
+
+use core::convert::Infallible;
+use core::marker::PhantomData;
+use core::ops::Range;
+use core::{fmt, future};
+use std::borrow::Cow;
+
+use abs_path::AbsPath;
+use editor::{AccessMut, AgentId, ByteOffset, Chunks, Edit, Editor};
+
+use crate::Headless;
+
+/// The [`Buffer`](editor::Buffer) type of the [`Headless`] editor.
+pub struct Buffer<'a> {
+    never: Infallible,
+    _lifetime: PhantomData<&'a ()>,
+}
+
+/// The [`Cursor`](editor::Cursor) type of the [`Headless`] editor.
+pub struct Cursor<'a> {
+    never: Infallible,
+    _lifetime: PhantomData<&'a ()>,
+}
+
+/// The [`Selection`](editor::Selection) type of the [`Headless`] editor.
+pub struct Selection<'a> {
+    never: Infallible,
+    _lifetime: PhantomData<&'a ()>,
+}
+
+/// The [`Chunks`] returned by [`Buffer::get_text_range`].
+#[derive(Debug)]
+pub struct NoChunks {
+    never: Infallible,
+}
+
+impl editor::Buffer for Buffer<'_> {
+    type Editor = Headless;
+
+    fn byte_len(&self) -> ByteOffset {
+        match self.never {}
+    }
+
+    fn get_text_range(&self, _: Range<ByteOffset>) -> impl Chunks {
+        NoChunks { never: self.never }
+    }
+
+    fn id(&self) -> <Self::Editor as Editor>::BufferId {
+        match self.never {}
+    }
+
+    fn for_each_cursor<Fun>(&mut self, _: Fun)
+    where
+        Fun: FnMut(<Self::Editor as Editor>::Cursor<'_>),
+    {
+        match self.never {}
+    }
+
+    fn on_edited<Fun>(
+        &mut self,
+        _: Fun,
+        _: impl AccessMut<Self::Editor> + Clone + 'static,
+    ) -> <Self::Editor as Editor>::EventHandle
+    where
+        Fun: FnMut(&<Self::Editor as Editor>::Buffer<'_>, &Edit) + 'static,
+    {
+        match self.never {}
+    }
+
+    fn on_removed<Fun>(
+        &mut self,
+        _: Fun,
+        _: impl AccessMut<Self::Editor> + Clone + 'static,
+    ) -> <Self::Editor as Editor>::EventHandle
+    where
+        Fun: FnMut(<Self::Editor as Editor>::BufferId, AgentId) + 'static,
+    {
+        match self.never {}
+    }
+
+    fn on_saved<Fun>(
+        &mut self,
+        _: Fun,
+        _: impl AccessMut<Self::Editor> + Clone + 'static,
+    ) -> <Self::Editor as Editor>::EventHandle
+    where
+        Fun: FnMut(&<Self::Editor as Editor>::Buffer<'_>, AgentId) + 'static,
+    {
+        match self.never {}
+    }
+
+    fn path(&self) -> Cow<'_, AbsPath> {
+        match self.never {}
+    }
+
+    fn schedule_edit<R>(
+        &mut self,
+        _: R,
+        _: AgentId,
+    ) -> impl Future<Output = ()> + 'static
+    where
+        R: IntoIterator<Item = editor::Replacement>,
+    {
+        future::ready::<()>(match self.never {})
+    }
+
+    fn schedule_focus(
+        &mut self,
+        _: AgentId,
+    ) -> impl Future<Output = ()> + 'static {
+        future::ready::<()>(match self.never {})
+    }
+
+    fn schedule_save(
+        &mut self,
+        _: AgentId,
+    ) -> impl Future<Output = Result<(), Infallible>> + 'static {
+        future::ready::<Result<(), Infallible>>(match self.never {})
+    }
+}
+
+impl editor::Cursor for Cursor<'_> {
+    type Editor = Headless;
+
+    fn buffer_id(&self) -> <Self::Editor as Editor>::BufferId {
+        match self.never {}
+    }
+
+    fn byte_offset(&self) -> ByteOffset {
+        match self.never {}
+    }
+
+    fn id(&self) -> <Self::Editor as Editor>::CursorId {
+        match self.never {}
+    }
+
+    fn on_moved<Fun>(
+        &mut self,
+        _: Fun,
+        _: impl AccessMut<Self::Editor> + Clone + 'static,
+    ) -> <Self::Editor as Editor>::EventHandle
+    where
+        Fun: FnMut(&<Self::Editor as Editor>::Cursor<'_>, AgentId) + 'static,
+    {
+        match self.never {}
+    }
+
+    fn on_removed<Fun>(
+        &mut self,
+        _: Fun,
+        _: impl AccessMut<Self::Editor> + Clone + 'static,
+    ) -> <Self::Editor as Editor>::EventHandle
+    where
+        Fun: FnMut(<Self::Editor as Editor>::CursorId, AgentId) + 'static,
+    {
+        match self.never {}
+    }
+
+    fn schedule_move(
+        &mut self,
+        _: ByteOffset,
+        _: AgentId,
+    ) -> impl Future<Output = ()> + 'static {
+        future::ready::<()>(match self.never {})
+    }
+}
+
+impl editor::Selection for Selection<'_> {
+    type Editor = Headless;
+
+    fn buffer_id(&self) -> <Self::Editor as Editor>::BufferId {
+        match self.never {}
+    }
+
+    fn byte_range(&self) -> Range<ByteOffset> {
+        match self.never {}
+    }
+
+    fn id(&self) -> <Self::Editor as Editor>::SelectionId {
+        match self.never {}
+    }
+
+    fn on_moved<Fun>(
+        &mut self,
+        _: Fun,
+        _: impl AccessMut<Self::Editor> + Clone + 'static,
+    ) -> <Self::Editor as Editor>::EventHandle
+    where
+        Fun:
+            FnMut(&<Self::Editor as Editor>::Selection<'_>, AgentId) + 'static,
+    {
+        match self.never {}
+    }
+
+    fn on_removed<Fun>(
+        &mut self,
+        _: Fun,
+        _: impl AccessMut<Self::Editor> + Clone + 'static,
+    ) -> <Self::Editor as Editor>::EventHandle
+    where
+        Fun: FnMut(<Self::Editor as Editor>::SelectionId, AgentId) + 'static,
+    {
+        match self.never {}
+    }
+}
+
+impl AsRef<str> for NoChunks {
+    fn as_ref(&self) -> &str {
+        match self.never {}
+    }
+}
+
+impl fmt::Display for NoChunks {
+    fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.never {}
+    }
+}
+
+impl PartialEq<&str> for NoChunks {
+    fn eq(&self, _: &&str) -> bool {
+        match self.never {}
+    }
+}
+
+/// The type of error returned by [`Headless`]'s
+/// [`create_buffer`](Editor::create_buffer), which always fails because a
+/// headless editor can't open buffers.
+#[derive(Debug)]
+pub struct CreateBufferError {
+    _priv: (),
+}
+
+impl CreateBufferError {
+    pub(crate) fn new() -> Self {
+        Self { _priv: () }
+    }
+}
+
+impl fmt::Display for CreateBufferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the headless editor can't open buffers")
+    }
+}
+
+impl std::error::Error for CreateBufferError {}
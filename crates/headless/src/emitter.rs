@@ -0,0 +1,40 @@
+//! TODO: docs.
+
+use std::io::{self, Write};
+
+use editor::notify::{self, NotificationId};
+
+/// The [`Emitter`](notify::Emitter) used by the [`Headless`](crate::Headless)
+/// editor, which prints every notification to stderr.
+#[derive(Default)]
+pub struct HeadlessEmitter {
+    next_id: u64,
+}
+
+impl notify::Emitter for HeadlessEmitter {
+    fn emit(&mut self, notification: notify::Notification) -> NotificationId {
+        let level = match notification.level {
+            notify::Level::Off => None,
+            notify::Level::Trace => Some("trace"),
+            notify::Level::Debug => Some("debug"),
+            notify::Level::Info => Some("info"),
+            notify::Level::Warn => Some("warning"),
+            notify::Level::Error => Some("error"),
+        };
+
+        if let Some(level) = level {
+            let _ = writeln!(
+                io::stderr(),
+                "{level}: [{}] {}",
+                notification.namespace.dot_separated(),
+                notification.message.as_str(),
+            );
+        }
+
+        notification.updates_prev.unwrap_or_else(|| {
+            let id = NotificationId::new(self.next_id);
+            self.next_id += 1;
+            id
+        })
+    }
+}
@@ -0,0 +1,108 @@
+//! TODO: docs.
+
+use core::future::Future;
+
+use async_task::Runnable;
+use executor::{Executor, LocalSpawner};
+use futures_lite::future::FutureExt;
+use thread_pool::ThreadPool;
+
+/// The [`Executor`] used by the [`Headless`](crate::Headless) editor.
+///
+/// Local tasks are queued up and polled by whoever is driving the executor,
+/// which is usually the [`Runner`](crate::Runner) returned by
+/// [`Headless::runner`](crate::Headless::runner).
+pub struct HeadlessExecutor {
+    local_spawner: HeadlessLocalSpawner,
+    background_spawner: ThreadPool,
+    runnable_rx: flume::Receiver<Runnable>,
+}
+
+/// The [`LocalSpawner`] of the [`HeadlessExecutor`].
+#[derive(Clone)]
+pub struct HeadlessLocalSpawner {
+    runnable_tx: flume::Sender<Runnable>,
+}
+
+impl HeadlessExecutor {
+    /// Runs the local tasks that are ready to be polled until there are none
+    /// left, then waits for more to be scheduled.
+    ///
+    /// The returned future never resolves.
+    pub(crate) fn poll_runnables(
+        &self,
+    ) -> impl Future<Output = ()> + 'static + use<> {
+        let runnable_rx = self.runnable_rx.clone();
+        async move {
+            // The sender lives in the executor, so the channel stays open for
+            // as long as anyone could schedule a task.
+            while let Ok(runnable) = runnable_rx.recv_async().await {
+                runnable.run();
+            }
+        }
+    }
+}
+
+impl Default for HeadlessExecutor {
+    fn default() -> Self {
+        let (runnable_tx, runnable_rx) = flume::unbounded();
+        Self {
+            local_spawner: HeadlessLocalSpawner { runnable_tx },
+            background_spawner: ThreadPool::default(),
+            runnable_rx,
+        }
+    }
+}
+
+impl Executor for HeadlessExecutor {
+    type LocalSpawner = HeadlessLocalSpawner;
+    type BackgroundSpawner = ThreadPool;
+
+    #[inline]
+    fn run<Fut: Future>(
+        &mut self,
+        future: Fut,
+    ) -> impl Future<Output = Fut::Output> + use<Fut> {
+        let poll_runnables = self.poll_runnables();
+        future.or(async move {
+            poll_runnables.await;
+            unreachable!("the channel is never closed");
+        })
+    }
+
+    #[inline]
+    fn local_spawner(&mut self) -> &mut Self::LocalSpawner {
+        &mut self.local_spawner
+    }
+
+    #[inline]
+    fn background_spawner(&mut self) -> &mut Self::BackgroundSpawner {
+        &mut self.background_spawner
+    }
+}
+
+impl LocalSpawner for HeadlessLocalSpawner {
+    type Task<T> = async_task::Task<T>;
+
+    #[inline]
+    fn spawn<Fut>(&mut self, future: Fut) -> Self::Task<Fut::Output>
+    where
+        Fut: Future + 'static,
+        Fut::Output: 'static,
+    {
+        let schedule = {
+            let runnable_tx = self.runnable_tx.clone();
+            move |runnable| {
+                let _ = runnable_tx.send(runnable);
+            }
+        };
+
+        let (runnable, task) = async_task::Builder::new()
+            .propagate_panic(true)
+            .spawn_local(move |()| future, schedule);
+
+        runnable.schedule();
+
+        task
+    }
+}
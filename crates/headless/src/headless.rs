@@ -0,0 +1,278 @@
+use core::convert::Infallible;
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+use abs_path::AbsPath;
+use clipboard::{FallibleInitClipboard, arboard};
+use editor::{AccessMut, AgentId, ApiValue, Editor};
+use executor::{BackgroundSpawner, Executor};
+use futures_lite::future::{self, FutureExt};
+use serde::{Deserialize, Serialize};
+use thread_pool::ThreadPool;
+
+use crate::api::HeadlessApi;
+use crate::buffer::{Buffer, CreateBufferError, Cursor, Selection};
+use crate::emitter::HeadlessEmitter;
+use crate::executor::HeadlessExecutor;
+use crate::serde::{DeserializeError, SerializeError};
+
+type HttpClient = http_client::UreqClient<ThreadPool>;
+
+/// An [`Editor`] without a user interface.
+///
+/// Its [`Executor`] is driven by the [`Runner`] returned by
+/// [`runner`](Self::runner), which keeps polling tasks until
+/// [`exit`](Self::exit) is called.
+pub struct Headless {
+    clipboard: FallibleInitClipboard<arboard::Clipboard>,
+    emitter: HeadlessEmitter,
+    executor: HeadlessExecutor,
+    exit_tx: flume::Sender<ExitCode>,
+    exit_rx: flume::Receiver<ExitCode>,
+    http_client: HttpClient,
+}
+
+/// Drives a [`Headless`] editor's executor until the editor exits.
+pub struct Runner {
+    exit_rx: flume::Receiver<ExitCode>,
+    poll_runnables: future::BoxedLocal<()>,
+}
+
+impl Headless {
+    /// Makes the editor's [`Runner`] return the given exit code, unless
+    /// `exit` has already been called.
+    #[inline]
+    pub fn exit(&mut self, exit_code: ExitCode) {
+        let _ = self.exit_tx.try_send(exit_code);
+    }
+
+    /// Creates a new `Headless` editor.
+    #[inline]
+    pub fn new() -> Self {
+        let mut executor = HeadlessExecutor::default();
+        let (exit_tx, exit_rx) = flume::bounded(1);
+        Self {
+            clipboard: Default::default(),
+            emitter: HeadlessEmitter::default(),
+            http_client: HttpClient::new(
+                ureq::Agent::new_with_defaults(),
+                executor.background_spawner().clone(),
+            ),
+            executor,
+            exit_tx,
+            exit_rx,
+        }
+    }
+
+    /// Returns the [`Runner`] driving this editor's executor.
+    ///
+    /// This should be called before the editor is handed over to a plugin,
+    /// and the runner should then be [`run`](Runner::run) on the same
+    /// thread.
+    #[inline]
+    pub fn runner(&self) -> Runner {
+        Runner {
+            exit_rx: self.exit_rx.clone(),
+            poll_runnables: self.executor.poll_runnables().boxed_local(),
+        }
+    }
+}
+
+impl Runner {
+    /// Polls the editor's tasks until [`Headless::exit`] is called, returning
+    /// the exit code it was called with.
+    #[inline]
+    pub fn run(self) -> ExitCode {
+        let Self { exit_rx, poll_runnables } = self;
+
+        let wait_exit = async move {
+            exit_rx.recv_async().await.unwrap_or(ExitCode::SUCCESS)
+        };
+
+        future::block_on(wait_exit.or(async move {
+            poll_runnables.await;
+            ExitCode::SUCCESS
+        }))
+    }
+}
+
+impl Default for Headless {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Editor for Headless {
+    type Api = HeadlessApi;
+    type Buffer<'a> = Buffer<'a>;
+    type BufferId = Infallible;
+    type Cursor<'a> = Cursor<'a>;
+    type CursorId = Infallible;
+    type Clipboard = FallibleInitClipboard<arboard::Clipboard>;
+    type Fs = real_fs::RealFs;
+    type Emitter<'this> = &'this mut HeadlessEmitter;
+    type Executor = HeadlessExecutor;
+    type EventHandle = ();
+    type HttpClient = HttpClient;
+    type Selection<'a> = Selection<'a>;
+    type SelectionId = Infallible;
+
+    type BufferSaveError = Infallible;
+    type CreateBufferError = CreateBufferError;
+    type OpenUrlError = Infallible;
+    type SerializeError = SerializeError;
+    type DeserializeError = DeserializeError;
+
+    #[inline]
+    fn buffer(&mut self, id: Self::BufferId) -> Option<Self::Buffer<'_>> {
+        match id {}
+    }
+
+    #[inline]
+    fn buffer_at_path(&mut self, _: &AbsPath) -> Option<Self::Buffer<'_>> {
+        None
+    }
+
+    #[inline]
+    async fn create_buffer(
+        _: impl AccessMut<Self>,
+        _: &AbsPath,
+        _: AgentId,
+    ) -> Result<Self::BufferId, Self::CreateBufferError> {
+        Err(CreateBufferError::new())
+    }
+
+    #[inline]
+    fn current_buffer(&mut self) -> Option<Self::Buffer<'_>> {
+        None
+    }
+
+    #[inline]
+    fn for_each_buffer<Fun>(&mut self, _: Fun)
+    where
+        Fun: FnMut(Self::Buffer<'_>),
+    {
+    }
+
+    #[inline]
+    fn cursor(&mut self, id: Self::CursorId) -> Option<Self::Cursor<'_>> {
+        match id {}
+    }
+
+    #[inline]
+    fn clipboard(&mut self) -> &mut Self::Clipboard {
+        &mut self.clipboard
+    }
+
+    #[inline]
+    fn fs(&mut self) -> Self::Fs {
+        Self::Fs::default()
+    }
+
+    #[inline]
+    fn emitter(&mut self) -> Self::Emitter<'_> {
+        &mut self.emitter
+    }
+
+    #[inline]
+    fn executor(&mut self) -> &mut Self::Executor {
+        &mut self.executor
+    }
+
+    #[inline]
+    fn http_client(&self) -> &Self::HttpClient {
+        &self.http_client
+    }
+
+    #[inline]
+    fn on_buffer_created<Fun>(
+        &mut self,
+        _: Fun,
+        _: impl AccessMut<Self> + Clone + 'static,
+    ) -> Self::EventHandle
+    where
+        Fun: FnMut(Self::Buffer<'_>, AgentId) + 'static,
+    {
+    }
+
+    #[inline]
+    fn on_cursor_created<Fun>(
+        &mut self,
+        _: Fun,
+        _: impl AccessMut<Self> + Clone + 'static,
+    ) -> Self::EventHandle
+    where
+        Fun: FnMut(Self::Cursor<'_>, AgentId) + 'static,
+    {
+    }
+
+    #[inline]
+    fn on_selection_created<Fun>(
+        &mut self,
+        _: Fun,
+        _: impl AccessMut<Self> + Clone + 'static,
+    ) -> Self::EventHandle
+    where
+        Fun: FnMut(Self::Selection<'_>, AgentId) + 'static,
+    {
+    }
+
+    #[inline]
+    fn open_url(
+        &mut self,
+        url: url::Url,
+    ) -> impl Future<Output = Result<(), Self::OpenUrlError>> + use<> {
+        // We might be running on a machine without a browser (e.g. over
+        // SSH), so always print the URL and treat failing to open it as a
+        // non-error.
+        let _ = writeln!(io::stderr(), "Open {url} in your browser");
+
+        let spawner = self.executor().background_spawner().clone();
+
+        async move {
+            let _ = spawner
+                .spawn(async move { webbrowser::open(url.as_str()) })
+                .await;
+            Ok(())
+        }
+    }
+
+    #[inline]
+    fn reinstate_panic_hook(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn remove_event(&mut self, (): Self::EventHandle) {}
+
+    #[inline]
+    fn selection(
+        &mut self,
+        id: Self::SelectionId,
+    ) -> Option<Self::Selection<'_>> {
+        match id {}
+    }
+
+    #[inline]
+    fn serialize<T>(
+        &mut self,
+        value: &T,
+    ) -> Result<ApiValue<Self>, Self::SerializeError>
+    where
+        T: ?Sized + Serialize,
+    {
+        crate::serde::serialize(value)
+    }
+
+    #[inline]
+    fn deserialize<'de, T>(
+        &mut self,
+        value: ApiValue<Self>,
+    ) -> Result<T, Self::DeserializeError>
+    where
+        T: Deserialize<'de>,
+    {
+        crate::serde::deserialize(value)
+    }
+}
@@ -0,0 +1,17 @@
+//! An [`Editor`](editor::Editor) without a user interface, used to run
+//! Nomad's modules from the command line.
+//!
+//! A `Headless` editor has no buffers, cursors or selections: all the files
+//! it knows about live on disk, and all the notifications it emits are
+//! printed to stderr.
+
+pub mod api;
+pub mod buffer;
+pub mod emitter;
+pub mod executor;
+mod headless;
+pub mod notify;
+pub mod serde;
+pub mod value;
+
+pub use headless::{Headless, Runner};
@@ -0,0 +1,37 @@
+//! TODO: docs.
+
+use core::fmt;
+use std::io::{self, Write};
+
+use editor::Context;
+use editor::context::BorrowState;
+
+use crate::Headless;
+
+/// An extension trait for `Context<Headless>` to print messages to the user.
+pub trait NotifyContextExt {
+    /// Prints the given message to stderr, prefixed by the given label.
+    fn notify(&mut self, message: impl fmt::Display, label: &str);
+
+    /// Prints the given message as an error.
+    fn notify_error(&mut self, message: impl fmt::Display) {
+        self.notify(message, "error");
+    }
+
+    /// Prints the given message as an informational message.
+    fn notify_info(&mut self, message: impl fmt::Display) {
+        self.notify(message, "info");
+    }
+
+    /// Prints the given message as a warning.
+    fn notify_warn(&mut self, message: impl fmt::Display) {
+        self.notify(message, "warning");
+    }
+}
+
+impl<Bs: BorrowState> NotifyContextExt for Context<Headless, Bs> {
+    #[inline]
+    fn notify(&mut self, message: impl fmt::Display, label: &str) {
+        let _ = writeln!(io::stderr(), "{label}: {message}");
+    }
+}
@@ -0,0 +1,57 @@
+//! TODO: docs.
+
+use editor::notify;
+use serde::{Deserialize, Serialize};
+
+use crate::value::Value;
+
+/// The type of error returned by [`Headless`](crate::Headless)'s
+/// [`serialize`](editor::Editor::serialize).
+#[derive(Debug)]
+pub struct SerializeError {
+    inner: serde_json::Error,
+}
+
+/// The type of error returned by [`Headless`](crate::Headless)'s
+/// [`deserialize`](editor::Editor::deserialize).
+#[derive(Debug)]
+pub struct DeserializeError {
+    inner: serde_json::Error,
+}
+
+pub(crate) fn serialize<T>(value: &T) -> Result<Value, SerializeError>
+where
+    T: ?Sized + Serialize,
+{
+    serde_json::to_value(value)
+        .map(Into::into)
+        .map_err(|inner| SerializeError { inner })
+}
+
+pub(crate) fn deserialize<'de, T>(value: Value) -> Result<T, DeserializeError>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(value.into_json())
+        .map_err(|inner| DeserializeError { inner })
+}
+
+impl notify::Error for SerializeError {
+    #[inline]
+    fn to_message(&self) -> (notify::Level, notify::Message) {
+        (
+            notify::Level::Error,
+            notify::Message::from_str(self.inner.to_string()),
+        )
+    }
+}
+
+impl notify::Error for DeserializeError {
+    #[inline]
+    fn to_message(&self) -> (notify::Level, notify::Message) {
+        (
+            notify::Level::Error,
+            notify::Message::from_str(self.inner.to_string()),
+        )
+    }
+}
@@ -0,0 +1,101 @@
+//! TODO: docs.
+
+use core::marker::PhantomData;
+use core::mem;
+
+use editor::notify;
+
+/// The [`Value`](editor::Value) type used by the [`Headless`](crate::Headless)
+/// editor's API.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Value {
+    inner: serde_json::Value,
+}
+
+/// The [`MapAccess`](editor::MapAccess) returned by [`Value`]'s
+/// [`map_access`](editor::Value::map_access).
+pub struct MapAccess<'a> {
+    entries: serde_json::map::IntoIter,
+    current: Option<(String, serde_json::Value)>,
+    _value: PhantomData<&'a mut Value>,
+}
+
+/// The type of error returned when trying to access a non-object [`Value`]
+/// as a map.
+pub struct MapAccessError {
+    kind: &'static str,
+}
+
+impl Value {
+    /// Returns the inner JSON value.
+    pub fn into_json(self) -> serde_json::Value {
+        self.inner
+    }
+
+    fn kind(&self) -> &'static str {
+        match &self.inner {
+            serde_json::Value::Null => "null",
+            serde_json::Value::Bool(_) => "boolean",
+            serde_json::Value::Number(_) => "number",
+            serde_json::Value::String(_) => "string",
+            serde_json::Value::Array(_) => "array",
+            serde_json::Value::Object(_) => "object",
+        }
+    }
+}
+
+impl editor::Value for Value {
+    type MapAccess<'a> = MapAccess<'a>;
+    type MapAccessError<'a> = MapAccessError;
+
+    fn map_access(
+        &mut self,
+    ) -> Result<Self::MapAccess<'_>, Self::MapAccessError<'_>> {
+        match &mut self.inner {
+            serde_json::Value::Object(map) => Ok(MapAccess {
+                entries: mem::take(map).into_iter(),
+                current: None,
+                _value: PhantomData,
+            }),
+            _ => Err(MapAccessError { kind: self.kind() }),
+        }
+    }
+}
+
+impl editor::MapAccess for MapAccess<'_> {
+    type Key<'a>
+        = &'a str
+    where
+        Self: 'a;
+    type Value = Value;
+
+    fn next_key(&mut self) -> Option<Self::Key<'_>> {
+        self.current = self.entries.next();
+        self.current.as_ref().map(|(key, _)| key.as_str())
+    }
+
+    fn take_next_value(&mut self) -> Self::Value {
+        let (_, value) =
+            self.current.as_mut().expect("already called next_key");
+        mem::take(value).into()
+    }
+}
+
+impl From<serde_json::Value> for Value {
+    fn from(inner: serde_json::Value) -> Self {
+        Self { inner }
+    }
+}
+
+impl From<Value> for serde_json::Value {
+    fn from(value: Value) -> Self {
+        value.inner
+    }
+}
+
+impl notify::Error for MapAccessError {
+    fn to_message(&self) -> (notify::Level, notify::Message) {
+        let msg = format!("expected an object, got {} instead", self.kind);
+        (notify::Level::Error, notify::Message::from_str(msg))
+    }
+}
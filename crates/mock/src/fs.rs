@@ -523,7 +523,18 @@ impl FileInner {
         Chunk: AsRef<[u8]>,
     {
         self.contents.clear();
+        self.append_chunks(chunks, now)
+    }
 
+    fn append_chunks<Chunks, Chunk>(
+        &mut self,
+        chunks: Chunks,
+        now: MockTimestamp,
+    ) -> impl Future<Output = ()> + use<Chunks, Chunk>
+    where
+        Chunks: IntoIterator<Item = Chunk>,
+        Chunk: AsRef<[u8]>,
+    {
         for chunk in chunks {
             self.contents.extend_from_slice(chunk.as_ref());
         }
@@ -824,6 +835,20 @@ impl fs::File for MockFile {
     type ReadError = GetNodeError;
     type WriteError = GetNodeError;
 
+    async fn append_chunks<Chunks, Chunk>(
+        &mut self,
+        chunks: Chunks,
+    ) -> Result<(), Self::WriteError>
+    where
+        Chunks: IntoIterator<Item = Chunk> + Send,
+        Chunks::IntoIter: Send,
+        Chunk: AsRef<[u8]> + Send,
+    {
+        let now = self.fs.now();
+        self.with_inner(|file| file.append_chunks(chunks, now))?.await;
+        Ok(())
+    }
+
     async fn delete(self) -> Result<(), Self::DeleteError> {
         self.fs.delete_node_inner(&self.path)?.await;
         Ok(())
@@ -23,6 +23,7 @@ use smol_str::format_smolstr;
 pub use crate::buffer_ext::{BufferExt, GraphemeOffsets};
 use crate::convert::Convert;
 use crate::cursor::NeovimCursor;
+use crate::option::{BufferLocalOpts, Modifiable, NeovimOption};
 use crate::oxi::{self, BufHandle, api, mlua};
 use crate::{Neovim, decoration_provider, events, utils};
 
@@ -338,6 +339,14 @@ impl<'a> editor::Buffer for NeovimBuffer<'a> {
 
             let mut buffer = api::Buffer::from(buffer_id);
 
+            // Read-only buffers aren't modifiable, but we still have to apply
+            // the edits made by the plugin (e.g. the remote peers' ones).
+            let opts = BufferLocalOpts::new(buffer.clone());
+            let was_modifiable = Modifiable.get(&opts);
+            if !was_modifiable {
+                Modifiable.set(true, &opts);
+            }
+
             for replacement in replacements {
                 if let Some(buffer_edited) = buffer_edited {
                     buffer_edited.set_agent_id(agent_id);
@@ -349,6 +358,10 @@ impl<'a> editor::Buffer for NeovimBuffer<'a> {
                     agent_id,
                 );
             }
+
+            if !was_modifiable {
+                Modifiable.set(false, &opts);
+            }
         })
     }
 
@@ -88,6 +88,9 @@ pub(crate) struct EndOfLine;
 /// The "fixendofline" option.
 pub(crate) struct FixEndOfLine;
 
+/// The "modifiable" option.
+pub(crate) struct Modifiable;
+
 /// TODO: docs.
 pub(crate) struct UneditableEndOfLine;
 
@@ -130,6 +133,12 @@ impl NeovimOption for FixEndOfLine {
     type Opts = BufferLocalOpts;
 }
 
+impl NeovimOption for Modifiable {
+    const LONG_NAME: &'static str = "modifiable";
+    type Value = bool;
+    type Opts = BufferLocalOpts;
+}
+
 impl NeovimOption for UneditableEndOfLine {
     const LONG_NAME: &'static str = unimplemented!();
     type Value = bool;
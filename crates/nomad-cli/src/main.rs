@@ -0,0 +1,287 @@
+//! The `nomad` binary, which lets machines without an editor (CI boxes,
+//! remote dev servers, etc.) take part in collaborative editing sessions by
+//! mirroring the project to a directory on disk.
+
+use core::str::FromStr;
+use std::fs;
+use std::path::{self, PathBuf};
+use std::process::ExitCode;
+
+use abs_path::AbsPathBuf;
+use auth::Auth;
+use collab::recording::{self, Pace, Recording};
+use collab::{Collab, CollabEditor, SessionId};
+use editor::Context;
+use editor::context::Borrowed;
+use editor::module::{ApiCtx, Empty, Module, PanicInfo, Plugin};
+use headless::Headless;
+use headless::notify::NotifyContextExt;
+
+#[derive(clap::Parser)]
+#[command(about)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+
+    /// The path of the Unix domain socket that local agents can connect to
+    /// to take part in the session as separate peers.
+    #[clap(long, global = true)]
+    agent_socket: Option<PathBuf>,
+
+    /// Record the messages exchanged during the session under
+    /// `$XDG_DATA_HOME/nomad/collab/recordings`, so that they can be
+    /// replayed later.
+    #[clap(long, global = true)]
+    record: bool,
+
+    /// The address of the collab server to connect to, in the
+    /// `<host>:<port>` format.
+    #[clap(long, global = true)]
+    server_address: Option<String>,
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum Command {
+    /// Join the session with the given ID, mirroring the project under a
+    /// local directory.
+    Join {
+        /// The ID of the session to join.
+        session_id: String,
+
+        /// The directory under which to store the project. Defaults to
+        /// `$XDG_DATA_HOME/nomad/collab/remote-projects`.
+        #[clap(long)]
+        dir: Option<PathBuf>,
+    },
+
+    /// Log in with GitHub, storing the credentials in the system's
+    /// credential store.
+    Login,
+
+    /// Replay a session recording, writing the recorded project and all the
+    /// changes made to it to a local directory.
+    Replay {
+        /// The path of the recording to replay.
+        recording: PathBuf,
+
+        /// The directory to write the project to, which must either not exist
+        /// or be empty.
+        #[clap(long)]
+        dir: PathBuf,
+
+        /// Wait between changes for as long as the recorded peers did.
+        #[clap(long)]
+        real_time: bool,
+    },
+
+    /// Start a new session on the project rooted at the given directory.
+    Start {
+        /// The root of the project. Defaults to the current directory.
+        dir: Option<PathBuf>,
+    },
+}
+
+/// The [`Plugin`] run by the `nomad` binary, which executes a single
+/// [`Command`] and then exits.
+struct NomadCli {
+    auth: Auth,
+    collab: Collab<Headless>,
+    command: Command,
+}
+
+impl NomadCli {
+    fn new(command: Command) -> Self {
+        let auth = Auth::default();
+        let collab = Collab::from(&auth);
+        Self { auth, collab, command }
+    }
+
+    /// Runs the command, returning `Some` exit code if the editor should exit
+    /// once it's done, or `None` if it should keep running until the session
+    /// ends.
+    async fn run(
+        auth: Auth,
+        collab: Collab<Headless>,
+        command: Command,
+        ctx: &mut Context<Headless>,
+    ) -> Result<Option<ExitCode>, String> {
+        // Make sure the stored credentials (if any) have been loaded before
+        // checking whether the user is logged in.
+        auth.state().wait_credentials_retrieved().await;
+
+        match command {
+            Command::Join { session_id, .. } => {
+                let session_id = SessionId::<Headless>::from_str(&session_id)
+                    .map_err(|err| format!("invalid session ID: {err}"))?;
+                let infos = collab
+                    .join(session_id, ctx)
+                    .await
+                    .map_err(|err| err.to_string())?;
+                <Headless as CollabEditor>::on_session_joined(&infos, ctx)
+                    .await;
+                Ok(None)
+            },
+            Command::Login => {
+                auth.login(ctx).await.map_err(|err| err.to_string())?;
+                Ok(Some(ExitCode::SUCCESS))
+            },
+            Command::Replay { recording: recording_path, dir, real_time } => {
+                let recording = fs::read_to_string(&recording_path)
+                    .map_err(|err| err.to_string())?
+                    .parse::<Recording>()
+                    .map_err(|err| format!("invalid recording: {err}"))?;
+                let project_root = path::absolute(&dir)
+                    .map_err(|err| err.to_string())
+                    .and_then(|dir| {
+                        AbsPathBuf::try_from(dir)
+                            .map_err(|err| err.to_string())
+                    })?;
+                let pace = if real_time {
+                    Pace::RealTime
+                } else {
+                    Pace::AsFastAsPossible
+                };
+                recording::replay(&recording, project_root, pace, ctx)
+                    .await
+                    .map_err(|err| err.to_string())?
+                    .drop(ctx);
+                println!(
+                    "Replayed {} messages into {}",
+                    recording.messages.len(),
+                    dir.display()
+                );
+                Ok(Some(ExitCode::SUCCESS))
+            },
+            Command::Start { dir } => {
+                let project_root = project_root(dir)?;
+                let infos = collab
+                    .start_at(project_root, ctx)
+                    .await
+                    .map_err(|err| err.to_string())?;
+                <Headless as CollabEditor>::on_session_started(&infos, ctx)
+                    .await;
+                Ok(None)
+            },
+        }
+    }
+}
+
+impl Plugin<Headless> for NomadCli {
+    fn handle_panic(
+        &self,
+        panic_info: PanicInfo,
+        ctx: &mut Context<Headless, Borrowed<'_>>,
+    ) {
+        ctx.notify_error(format_args!("{panic_info:?}"));
+        ctx.with_editor(|headless| headless.exit(ExitCode::FAILURE));
+    }
+}
+
+impl Module<Headless> for NomadCli {
+    const NAME: &str = "nomad";
+
+    type Config = Empty;
+
+    fn api(&self, ctx: &mut ApiCtx<Headless>) {
+        ctx.with_module(self.auth.clone()).with_module(self.collab.clone());
+    }
+
+    fn on_init(&self, ctx: &mut Context<Headless, Borrowed>) {
+        let auth = self.auth.clone();
+        let collab = self.collab.clone();
+        let command = self.command.clone();
+
+        ctx.spawn_and_detach(async move |ctx| {
+            let exit_code = match Self::run(auth, collab, command, ctx).await {
+                Ok(Some(exit_code)) => exit_code,
+                Ok(None) => return,
+                Err(err) => {
+                    ctx.notify_error(err);
+                    ExitCode::FAILURE
+                },
+            };
+            ctx.with_editor(|headless| headless.exit(exit_code));
+        });
+    }
+
+    fn on_new_config(
+        &self,
+        _: Self::Config,
+        _: &mut Context<Headless, Borrowed>,
+    ) {
+    }
+}
+
+fn main() -> ExitCode {
+    let args = <Args as clap::Parser>::parse();
+
+    let mut collab_config = serde_json::Map::new();
+
+    if let Some(agent_socket) = &args.agent_socket {
+        match path::absolute(agent_socket) {
+            Ok(agent_socket) => {
+                collab_config.insert(
+                    "agent_socket".into(),
+                    agent_socket.display().to_string().into(),
+                );
+            },
+            Err(err) => {
+                eprintln!(
+                    "error: invalid socket path {}: {err}",
+                    agent_socket.display()
+                );
+                return ExitCode::FAILURE;
+            },
+        }
+    }
+
+    if args.record {
+        collab_config.insert("record_sessions".into(), true.into());
+    }
+
+    if let Some(server_address) = args.server_address {
+        collab_config.insert("server_address".into(), server_address.into());
+    }
+
+    if let Command::Join { dir: Some(dir), .. } = &args.command {
+        match path::absolute(dir) {
+            Ok(dir) => {
+                collab_config.insert(
+                    "store_remote_projects_under".into(),
+                    dir.display().to_string().into(),
+                );
+            },
+            Err(err) => {
+                eprintln!("error: invalid directory {}: {err}", dir.display());
+                return ExitCode::FAILURE;
+            },
+        }
+    }
+
+    let headless = Headless::new();
+    let runner = headless.runner();
+    let mut api = Plugin::api(NomadCli::new(args.command), headless);
+
+    if !collab_config.is_empty() {
+        let config = serde_json::json!({ "collab": collab_config });
+        api.call_function(
+            <NomadCli as Plugin<Headless>>::CONFIG_FN_NAME,
+            config,
+        )
+        .expect("the config function is always registered");
+    }
+
+    runner.run()
+}
+
+/// Returns the absolute path of the project root passed to the `start`
+/// command, or of the current directory if none was given.
+fn project_root(dir: Option<PathBuf>) -> Result<AbsPathBuf, String> {
+    let dir = dir.unwrap_or_else(|| PathBuf::from("."));
+
+    let dir = dir.canonicalize().map_err(|err| {
+        format!("couldn't resolve directory {}: {err}", dir.display())
+    })?;
+
+    AbsPathBuf::try_from(dir).map_err(|err| err.to_string())
+}
@@ -7,7 +7,7 @@ use abs_path::{AbsPath, AbsPathBuf, NodeName};
 use futures_util::stream::{self, FusedStream, Stream};
 
 use crate::file_descriptor_permit::FileDescriptorPermit;
-use crate::{File, IoErrorExt, Metadata, RealFs, Symlink};
+use crate::{EventStream, File, IoErrorExt, Metadata, RealFs, Symlink};
 
 /// TODO: docs.
 #[derive(Clone)]
@@ -78,7 +78,7 @@ impl GetMetadata {
 }
 
 impl fs::Directory for Directory {
-    type EventStream = stream::Pending<fs::DirectoryEvent<RealFs>>;
+    type EventStream = EventStream<fs::DirectoryEvent<RealFs>>;
     type Fs = RealFs;
 
     type ClearError = io::Error;
@@ -199,7 +199,17 @@ impl fs::Directory for Directory {
 
     #[inline]
     fn watch(&self) -> Self::EventStream {
-        stream::pending()
+        #[cfg(feature = "watch")]
+        {
+            crate::watcher::watch_directory(
+                &self.path,
+                fs::Directory::id(self),
+            )
+        }
+        #[cfg(not(feature = "watch"))]
+        {
+            EventStream::pending()
+        }
     }
 }
 
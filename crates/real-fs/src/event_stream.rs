@@ -0,0 +1,50 @@
+//! Contains the [`EventStream`] returned by [`File::watch`] and
+//! [`Directory::watch`].
+//!
+//! [`File::watch`]: fs::File::watch
+//! [`Directory::watch`]: fs::Directory::watch
+
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use futures_util::{Stream, StreamExt};
+
+/// The [`Stream`] of events returned by [`File::watch`] and
+/// [`Directory::watch`].
+///
+/// The stream never yields any events unless the `watch` feature is enabled
+/// and the process-wide watcher has been started with
+/// [`watcher::start`](crate::watcher::start).
+///
+/// [`File::watch`]: fs::File::watch
+/// [`Directory::watch`]: fs::Directory::watch
+pub struct EventStream<T: 'static> {
+    inner: Option<Pin<Box<dyn Stream<Item = T> + Send>>>,
+}
+
+impl<T: 'static> EventStream<T> {
+    /// Returns a stream that never yields any events.
+    pub(crate) fn pending() -> Self {
+        Self { inner: None }
+    }
+
+    #[cfg(feature = "watch")]
+    pub(crate) fn new(stream: impl Stream<Item = T> + Send + 'static) -> Self {
+        Self { inner: Some(Box::pin(stream)) }
+    }
+}
+
+impl<T: 'static> Stream for EventStream<T> {
+    type Item = T;
+
+    #[inline]
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        match &mut self.inner {
+            Some(stream) => stream.poll_next_unpin(cx),
+            None => Poll::Pending,
+        }
+    }
+}
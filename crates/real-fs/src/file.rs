@@ -3,10 +3,10 @@ use core::ops::{Deref, DerefMut};
 use std::io;
 
 use abs_path::{AbsPath, AbsPathBuf};
-use futures_util::{AsyncReadExt, AsyncWriteExt, stream};
+use futures_util::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 
 use crate::file_descriptor_permit::FileDescriptorPermit;
-use crate::{Directory, IoErrorExt, Metadata, RealFs};
+use crate::{Directory, EventStream, IoErrorExt, Metadata, RealFs};
 
 /// TODO: docs.
 pub struct File {
@@ -62,6 +62,48 @@ impl File {
             }
         }
     }
+
+    /// Writes the given chunks to the file, either at the end of it or at
+    /// the current position of its handle.
+    #[inline]
+    async fn write_chunks_inner<Chunks, Chunk>(
+        &mut self,
+        chunks: Chunks,
+        append: bool,
+    ) -> io::Result<()>
+    where
+        Chunks: IntoIterator<Item = Chunk> + Send,
+        Chunks::IntoIter: Send,
+        Chunk: AsRef<[u8]> + Send,
+    {
+        self.with_inner(
+            OpenPermissions::Write,
+            async move |file, meta, path| {
+                let write = async {
+                    if append {
+                        file.seek(io::SeekFrom::End(0)).await?;
+                    }
+                    for chunk in chunks {
+                        file.write_all(chunk.as_ref()).await?;
+                    }
+                    file.sync_all().await
+                };
+
+                write.await.with_context(|| {
+                    format!("couldn't write to file at {path}")
+                })?;
+
+                let new_meta = file.metadata().await.with_context(|| {
+                    format!("couldn't get new metadata for file at {path}")
+                })?;
+
+                *meta = new_meta;
+
+                Ok(())
+            },
+        )
+        .await?
+    }
 }
 
 impl FileInner {
@@ -88,7 +130,7 @@ impl FileInner {
 }
 
 impl fs::File for File {
-    type EventStream = stream::Pending<fs::FileEvent<RealFs>>;
+    type EventStream = EventStream<fs::FileEvent<RealFs>>;
     type Fs = RealFs;
 
     type DeleteError = io::Error;
@@ -97,6 +139,19 @@ impl fs::File for File {
     type ReadError = io::Error;
     type WriteError = io::Error;
 
+    #[inline]
+    async fn append_chunks<Chunks, Chunk>(
+        &mut self,
+        chunks: Chunks,
+    ) -> Result<(), Self::WriteError>
+    where
+        Chunks: IntoIterator<Item = Chunk> + Send,
+        Chunks::IntoIter: Send,
+        Chunk: AsRef<[u8]> + Send,
+    {
+        self.write_chunks_inner(chunks, true).await
+    }
+
     #[inline]
     async fn delete(self) -> Result<(), Self::DeleteError> {
         async_fs::remove_file(self.path()).await.with_context(|| {
@@ -155,7 +210,14 @@ impl fs::File for File {
 
     #[inline]
     fn watch(&self) -> Self::EventStream {
-        stream::pending()
+        #[cfg(feature = "watch")]
+        {
+            crate::watcher::watch_file(&self.path, fs::File::id(self))
+        }
+        #[cfg(not(feature = "watch"))]
+        {
+            EventStream::pending()
+        }
     }
 
     #[inline]
@@ -168,30 +230,7 @@ impl fs::File for File {
         Chunks::IntoIter: Send,
         Chunk: AsRef<[u8]> + Send,
     {
-        self.with_inner(
-            OpenPermissions::Write,
-            async move |file, meta, path| {
-                let write = async {
-                    for chunk in chunks {
-                        file.write_all(chunk.as_ref()).await?;
-                    }
-                    file.sync_all().await
-                };
-
-                write.await.with_context(|| {
-                    format!("couldn't write to file at {path}")
-                })?;
-
-                let new_meta = file.metadata().await.with_context(|| {
-                    format!("couldn't get new metadata for file at {path}")
-                })?;
-
-                *meta = new_meta;
-
-                Ok(())
-            },
-        )
-        .await?
+        self.write_chunks_inner(chunks, false).await
     }
 }
 
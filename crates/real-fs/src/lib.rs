@@ -1,6 +1,7 @@
 //! TODO: docs.
 
 mod directory;
+mod event_stream;
 mod file;
 mod file_descriptor_permit;
 mod io_error_ext;
@@ -9,8 +10,11 @@ mod real_fs;
 mod symlink;
 #[cfg(feature = "temp")]
 mod temp;
+#[cfg(feature = "watch")]
+pub mod watcher;
 
 pub use directory::Directory;
+pub use event_stream::EventStream;
 pub use file::File;
 use io_error_ext::IoErrorExt;
 pub use metadata::Metadata;
@@ -0,0 +1,406 @@
+//! Contains the [`notify`]-based implementations of [`File::watch`] and
+//! [`Directory::watch`].
+//!
+//! All the event streams share a single process-wide watcher, which has to be
+//! [`start`]ed first, and which recursively watches the outermost directory
+//! of every watched node. This keeps the number of OS-level watchers (e.g.
+//! inotify instances on Linux) constant, no matter how many nodes are being
+//! watched.
+//!
+//! [`File::watch`]: fs::File::watch
+//! [`Directory::watch`]: fs::Directory::watch
+
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::SystemTime;
+
+use abs_path::{AbsPath, AbsPathBuf};
+use futures_util::{Stream, StreamExt, future, stream};
+use notify::event::{EventKind, ModifyKind, RenameMode};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::file_descriptor_permit::FileDescriptorPermit;
+use crate::{EventStream, Inode, RealFs};
+
+static WATCHER: OnceLock<SharedWatcher> = OnceLock::new();
+
+/// The [`Stream`] of errors returned by [`start`].
+pub struct WatchErrors {
+    inner: flume::r#async::RecvStream<'static, notify::Error>,
+}
+
+/// The process-wide watcher shared by all the event streams.
+struct SharedWatcher {
+    /// The OS-level watcher, together with the paths it's recursively
+    /// watching.
+    watcher: Mutex<(RecommendedWatcher, Vec<PathBuf>)>,
+
+    /// The callbacks registered by the event streams, which are called by
+    /// the watcher's thread.
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+
+    /// The sender of the errors returned by [`start`].
+    error_tx: flume::Sender<notify::Error>,
+}
+
+/// A callback registered by an event stream.
+struct Subscriber {
+    /// The path of the watched node.
+    path: PathBuf,
+
+    /// Whether the watched node is a directory, in which case the subscriber
+    /// is interested in the events on its children.
+    is_dir: bool,
+
+    /// Called with every event the subscriber is interested in, and returns
+    /// whether the subscriber should keep receiving events.
+    on_event: Box<dyn FnMut(&notify::Event) -> bool + Send>,
+}
+
+/// A change directly under a watched directory, as seen by the watcher's
+/// thread.
+enum DirChange {
+    Created { path: AbsPathBuf, node_id: Inode },
+    Moved { old_path: AbsPathBuf, new_path: AbsPathBuf },
+    Removed { path: AbsPathBuf },
+}
+
+/// Starts the process-wide watcher, returning a stream of the errors it
+/// encounters while running.
+///
+/// Fails if the OS-level watcher couldn't be created, or if the watcher has
+/// already been started.
+///
+/// Until this is called, the streams returned by [`File::watch`] and
+/// [`Directory::watch`] never yield any events.
+///
+/// [`File::watch`]: fs::File::watch
+/// [`Directory::watch`]: fs::Directory::watch
+pub fn start() -> notify::Result<WatchErrors> {
+    let already_started =
+        || notify::Error::generic("the watcher has already been started");
+
+    if WATCHER.get().is_some() {
+        return Err(already_started());
+    }
+
+    let (error_tx, error_rx) = flume::unbounded();
+
+    let subscribers = Arc::new(Mutex::new(Vec::<Subscriber>::new()));
+
+    let watcher = notify::recommended_watcher({
+        let subscribers = Arc::clone(&subscribers);
+        let error_tx = error_tx.clone();
+        move |res: notify::Result<notify::Event>| match res {
+            Ok(event) => dispatch(&event, &subscribers),
+            Err(err) => {
+                let _ = error_tx.send(err);
+            },
+        }
+    })?;
+
+    WATCHER
+        .set(SharedWatcher {
+            watcher: Mutex::new((watcher, Vec::new())),
+            subscribers,
+            error_tx,
+        })
+        .map_err(|_| already_started())?;
+
+    Ok(WatchErrors { inner: error_rx.into_stream() })
+}
+
+/// Starts watching the file at the given path for modifications.
+pub(crate) fn watch_file(
+    file_path: &AbsPath,
+    file_id: Inode,
+) -> EventStream<fs::FileEvent<RealFs>> {
+    let Some(watcher) = WATCHER.get() else {
+        return EventStream::pending();
+    };
+
+    let (event_tx, event_rx) = flume::unbounded();
+    let path = file_path.to_owned();
+    let mut current_id = file_id;
+
+    watcher.subscribe(file_path.as_str(), false, move |event| {
+        if !matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Data(_))
+        ) {
+            return !event_tx.is_disconnected();
+        }
+
+        // Some programs save by writing to a temporary file and renaming it
+        // over the original, which gives the file a new inode.
+        let Some(new_id) = inode(&path) else {
+            return !event_tx.is_disconnected();
+        };
+
+        if new_id != current_id {
+            let change = fs::FileIdChange { old_id: current_id, new_id };
+            if event_tx.send(fs::FileEvent::IdChange(change)).is_err() {
+                return false;
+            }
+            current_id = new_id;
+        }
+
+        event_tx
+            .send(fs::FileEvent::Modification(fs::FileModification {
+                file_id: current_id,
+                modified_at: SystemTime::now(),
+            }))
+            .is_ok()
+    });
+
+    EventStream::new(event_rx.into_stream())
+}
+
+/// Starts watching the directory at the given path for nodes being created,
+/// deleted or renamed directly under it.
+pub(crate) fn watch_directory(
+    dir_path: &AbsPath,
+    dir_id: Inode,
+) -> EventStream<fs::DirectoryEvent<RealFs>> {
+    let Some(watcher) = WATCHER.get() else {
+        return EventStream::pending();
+    };
+
+    let (change_tx, change_rx) = flume::unbounded();
+
+    {
+        let dir_path = PathBuf::from(dir_path.as_str());
+        watcher.subscribe(dir_path.clone(), true, move |event| {
+            dir_changes(event, &dir_path)
+                .into_iter()
+                .all(|change| change_tx.send(change).is_ok())
+                && !change_tx.is_disconnected()
+        });
+    }
+
+    // The watcher only gives us the paths of deleted nodes, so we keep track
+    // of the children's inodes to be able to tell which node was deleted.
+    //
+    // The changes received while the children are being read are buffered in
+    // the channel, and applied once they're known.
+    let stream = stream::once(read_children(dir_path.to_owned())).flat_map(
+        move |mut children| {
+            change_rx.clone().into_stream().filter_map(move |change| {
+                future::ready(apply_dir_change(change, &mut children, dir_id))
+            })
+        },
+    );
+
+    EventStream::new(stream)
+}
+
+impl SharedWatcher {
+    /// Registers the given callback to be called with the events on the node
+    /// at the given path, or on its children if it's a directory.
+    fn subscribe(
+        &self,
+        path: impl AsRef<Path>,
+        is_dir: bool,
+        on_event: impl FnMut(&notify::Event) -> bool + Send + 'static,
+    ) {
+        let path = path.as_ref();
+
+        // The subscriber has to be registered before the path is watched,
+        // or we could miss the first events.
+        self.subscribers.lock().unwrap_or_else(PoisonError::into_inner).push(
+            Subscriber {
+                path: path.to_owned(),
+                is_dir,
+                on_event: Box::new(on_event),
+            },
+        );
+
+        let mut guard =
+            self.watcher.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let (watcher, roots) = &mut *guard;
+
+        if roots.iter().any(|root| path.starts_with(root)) {
+            return;
+        }
+
+        if let Err(err) = watcher.watch(path, RecursiveMode::Recursive) {
+            let _ = self.error_tx.send(err);
+            return;
+        }
+
+        // The new root replaces the ones under it.
+        roots.retain(|root| {
+            if !root.starts_with(path) {
+                return true;
+            }
+            if let Err(err) = watcher.unwatch(root) {
+                let _ = self.error_tx.send(err);
+            }
+            false
+        });
+
+        roots.push(path.to_owned());
+    }
+}
+
+impl Subscriber {
+    fn is_interested_in(&self, event: &notify::Event) -> bool {
+        event.paths.iter().any(|path| {
+            if self.is_dir {
+                path.parent() == Some(&*self.path)
+            } else {
+                *path == self.path
+            }
+        })
+    }
+}
+
+impl Stream for WatchErrors {
+    type Item = notify::Error;
+
+    #[inline]
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+/// Calls the subscribers interested in the given event, dropping the ones
+/// whose event streams have been dropped.
+fn dispatch(event: &notify::Event, subscribers: &Mutex<Vec<Subscriber>>) {
+    subscribers.lock().unwrap_or_else(PoisonError::into_inner).retain_mut(
+        |sub| !sub.is_interested_in(event) || (sub.on_event)(event),
+    );
+}
+
+/// Returns the changes directly under the directory at the given path
+/// described by the given event.
+fn dir_changes(event: &notify::Event, dir_path: &Path) -> Vec<DirChange> {
+    let is_child = |path: &PathBuf| path.parent() == Some(dir_path);
+
+    let children = || {
+        event
+            .paths
+            .iter()
+            .filter(|&path| is_child(path))
+            .filter_map(|path| AbsPathBuf::try_from(path.as_path()).ok())
+    };
+
+    let created = |path: AbsPathBuf| {
+        inode(&path).map(|node_id| DirChange::Created { path, node_id })
+    };
+
+    match event.kind {
+        EventKind::Create(_)
+        | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+            children().filter_map(created).collect()
+        },
+
+        EventKind::Remove(_)
+        | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+            children().map(|path| DirChange::Removed { path }).collect()
+        },
+
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+            let [old_path, new_path] = &*event.paths else {
+                return Vec::new();
+            };
+
+            let old_path = is_child(old_path)
+                .then(|| AbsPathBuf::try_from(old_path.as_path()).ok())
+                .flatten();
+
+            let new_path = is_child(new_path)
+                .then(|| AbsPathBuf::try_from(new_path.as_path()).ok())
+                .flatten();
+
+            // Nodes moved in or out of the directory are seen as created or
+            // removed.
+            match (old_path, new_path) {
+                (Some(old_path), Some(new_path)) => {
+                    vec![DirChange::Moved { old_path, new_path }]
+                },
+                (Some(path), None) => vec![DirChange::Removed { path }],
+                (None, Some(path)) => created(path).into_iter().collect(),
+                (None, None) => Vec::new(),
+            }
+        },
+
+        _ => Vec::new(),
+    }
+}
+
+/// Turns the given change into a [`fs::DirectoryEvent`], updating the
+/// directory's children accordingly.
+fn apply_dir_change(
+    change: DirChange,
+    children: &mut HashMap<OsString, Inode>,
+    dir_id: Inode,
+) -> Option<fs::DirectoryEvent<RealFs>> {
+    let name = |path: &AbsPathBuf| {
+        Path::new(path.as_str()).file_name().map(ToOwned::to_owned)
+    };
+
+    match change {
+        DirChange::Created { path, node_id } => {
+            children.insert(name(&path)?, node_id);
+            Some(fs::DirectoryEvent::Creation(fs::NodeCreation {
+                node_id,
+                node_path: path,
+                parent_id: dir_id,
+            }))
+        },
+        DirChange::Moved { old_path, new_path } => {
+            let node_id = children.remove(&name(&old_path)?)?;
+            children.insert(name(&new_path)?, node_id);
+            Some(fs::DirectoryEvent::Move(fs::NodeMove {
+                node_id,
+                old_path,
+                new_path,
+                move_root_id: node_id,
+            }))
+        },
+        DirChange::Removed { path } => {
+            let node_id = children.remove(&name(&path)?)?;
+            Some(fs::DirectoryEvent::Deletion(fs::NodeDeletion {
+                node_id,
+                node_path: path,
+                deletion_root_id: node_id,
+            }))
+        },
+    }
+}
+
+/// Returns the names and inodes of the nodes directly under the directory at
+/// the given path.
+#[allow(clippy::disallowed_methods)]
+async fn read_children(dir_path: AbsPathBuf) -> HashMap<OsString, Inode> {
+    let _fd_permit = FileDescriptorPermit::acquire().await;
+
+    let mut children = HashMap::new();
+
+    let Ok(mut entries) = async_fs::read_dir(dir_path.as_str()).await else {
+        return children;
+    };
+
+    while let Some(entry_res) = entries.next().await {
+        let Ok(entry) = entry_res else { continue };
+        if let Ok(metadata) = entry.metadata().await {
+            children.insert(entry.file_name(), metadata.ino());
+        }
+    }
+
+    children
+}
+
+fn inode(path: &AbsPath) -> Option<Inode> {
+    std::fs::symlink_metadata(path.as_str()).ok().map(|meta| meta.ino())
+}
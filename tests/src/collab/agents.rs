@@ -0,0 +1,102 @@
+use abs_path::path;
+use collab::PeerId;
+use collab::agents::{AgentError, AgentReplica};
+use collab_project::text::TextReplacement;
+use collab_types::Message;
+
+#[test]
+fn agent_ops_integrate_into_other_replicas() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+    };
+
+    let mut project_1 =
+        collab_project::Project::from_mock(PeerId::new(1), fs.root());
+
+    let mut agent = AgentReplica::new(&project_1, PeerId::new(2));
+
+    let replacement =
+        TextReplacement { deleted_range: 5..5, inserted_text: ",".into() };
+
+    let Message::EditedText(edit) =
+        agent.edit(path!("/foo.txt"), vec![replacement]).unwrap()
+    else {
+        panic!("expected a text edit");
+    };
+
+    project_1.integrate_text_edit(edit);
+
+    let foo = project_1
+        .node_at_path(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text();
+
+    assert_eq!(foo.contents(), "hello, world");
+
+    let [Message::CreatedCursor(creation)] =
+        &*agent.move_cursor(path!("/foo.txt"), 6).unwrap()
+    else {
+        panic!("expected a cursor creation");
+    };
+
+    let cursor =
+        project_1.integrate_cursor_creation(creation.clone()).unwrap();
+
+    // The cursor should be owned by the agent, not by the local peer.
+    assert_eq!(cursor.owner(), PeerId::new(2));
+    assert_eq!(cursor.offset(), 6);
+}
+
+#[test]
+fn agent_edits_with_invalid_ranges_are_rejected() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+    };
+
+    let project =
+        collab_project::Project::from_mock(PeerId::new(1), fs.root());
+
+    let mut agent = AgentReplica::new(&project, PeerId::new(2));
+
+    let replacements = vec![
+        TextReplacement { deleted_range: 0..5, inserted_text: "hi".into() },
+        // This was in bounds before the first replacement, but not after.
+        TextReplacement { deleted_range: 8..11, inserted_text: "".into() },
+    ];
+
+    assert_eq!(
+        agent.edit(path!("/foo.txt"), replacements).unwrap_err(),
+        AgentError::InvalidRange(path!("/foo.txt").to_owned(), 8..11),
+    );
+
+    // None of the replacements should've been applied.
+    assert_eq!(agent.read_file(path!("/foo.txt")).unwrap(), "hello world");
+}
+
+#[test]
+fn agent_replica_stays_in_sync_with_local_edits() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+    };
+
+    let mut project_1 =
+        collab_project::Project::from_mock(PeerId::new(1), fs.root());
+
+    let mut agent = AgentReplica::new(&project_1, PeerId::new(2));
+
+    let insert_comma = project_1
+        .node_at_path_mut(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text()
+        .insert(5, ",");
+
+    agent.integrate(&Message::EditedText(insert_comma));
+
+    assert_eq!(agent.read_file(path!("/foo.txt")).unwrap(), "hello, world");
+
+    // The agent's cursor should be placed on top of the new contents.
+    let messages = agent.move_cursor(path!("/foo.txt"), 12).unwrap();
+    assert!(matches!(&*messages, [Message::CreatedCursor(_)]));
+}
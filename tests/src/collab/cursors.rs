@@ -1,9 +1,10 @@
-use abs_path::{AbsPathBuf, path};
+use abs_path::path;
+use collab::PeerId;
 use collab::editors::mock::CollabMock;
-use collab::peers::RemotePeers;
-use collab::{Peer, PeerHandle, PeerId};
 use mock::{EditorExt, Mock};
 
+use crate::collab::{peer, project};
+
 #[test]
 fn remote_peer_cursor_is_present_when_opening_buffer() {
     let fs = mock::fs! {
@@ -25,24 +26,12 @@ fn remote_peer_cursor_is_present_when_opening_buffer() {
     CollabMock::new(Mock::new(fs)).block_on(async move |ctx| {
         let agent_id = ctx.new_agent_id();
 
-        let remote_peer = Peer {
-            id: project_1.peer_id(),
-            handle: PeerHandle::GitHub("peer1".parse().unwrap()),
-        };
-
-        let mut proj = collab::project::Project::<CollabMock<Mock>> {
+        let mut proj = project::<CollabMock<Mock>>(
+            project_2,
+            [peer(1)],
+            project_1.peer_id(),
             agent_id,
-            id_maps: Default::default(),
-            local_peer: Peer {
-                id: project_2.peer_id(),
-                handle: PeerHandle::GitHub("peer2".parse().unwrap()),
-            },
-            peer_cursors: Default::default(),
-            peer_selections: Default::default(),
-            remote_peers: RemotePeers::new([remote_peer], &project_2),
-            root_path: AbsPathBuf::root(),
-            inner: project_2,
-        };
+        );
 
         let foo_path = path!("/foo.txt");
 
@@ -55,7 +44,7 @@ fn remote_peer_cursor_is_present_when_opening_buffer() {
 
         // Peer 1 created a cursor at offset 5, so peer 2 should display a
         // tooltip at that offset.
-        assert_eq!(*proj.peer_cursors.get(&cursor_id).unwrap(), 5);
+        assert_eq!(proj.peer_cursors.get(&cursor_id).unwrap().offset, 5);
     });
 }
 
@@ -80,24 +69,12 @@ fn main_cursor_is_removed_when_cursor_deletion_is_received() {
     CollabMock::new(Mock::new(fs)).block_on(async move |ctx| {
         let agent_id = ctx.new_agent_id();
 
-        let remote_peer = Peer {
-            id: project_1.peer_id(),
-            handle: PeerHandle::GitHub("peer1".parse().unwrap()),
-        };
-
-        let mut proj = collab::project::Project::<CollabMock<Mock>> {
+        let mut proj = project::<CollabMock<Mock>>(
+            project_2,
+            [peer(1)],
+            project_1.peer_id(),
             agent_id,
-            id_maps: Default::default(),
-            local_peer: Peer {
-                id: project_2.peer_id(),
-                handle: PeerHandle::GitHub("peer2".parse().unwrap()),
-            },
-            peer_cursors: Default::default(),
-            peer_selections: Default::default(),
-            remote_peers: RemotePeers::new([remote_peer], &project_2),
-            root_path: AbsPathBuf::root(),
-            inner: project_2,
-        };
+        );
 
         let cursor_deletion =
             project_1.cursor_mut(cursor_id).unwrap().unwrap().delete();
@@ -137,24 +114,12 @@ fn main_cursor_is_set_to_smallest_remaining_cursor_when_deletion_is_received()
     CollabMock::new(Mock::new(fs)).block_on(async move |ctx| {
         let agent_id = ctx.new_agent_id();
 
-        let remote_peer = Peer {
-            id: project_1.peer_id(),
-            handle: PeerHandle::GitHub("peer1".parse().unwrap()),
-        };
-
-        let mut proj = collab::project::Project::<CollabMock<Mock>> {
+        let mut proj = project::<CollabMock<Mock>>(
+            project_2,
+            [peer(1)],
+            project_1.peer_id(),
             agent_id,
-            id_maps: Default::default(),
-            local_peer: Peer {
-                id: project_2.peer_id(),
-                handle: PeerHandle::GitHub("peer2".parse().unwrap()),
-            },
-            peer_cursors: Default::default(),
-            peer_selections: Default::default(),
-            remote_peers: RemotePeers::new([remote_peer], &project_2),
-            root_path: AbsPathBuf::root(),
-            inner: project_2,
-        };
+        );
 
         // Delete cursor 1.
         proj.integrate_cursor_deletion(
@@ -203,24 +168,12 @@ fn remote_peer_cursor_is_deleted_when_buffer_is_deleted() {
     CollabMock::new(Mock::new(fs)).block_on(async move |ctx| {
         let agent_id = ctx.new_agent_id();
 
-        let remote_peer = Peer {
-            id: project_1.peer_id(),
-            handle: PeerHandle::GitHub("peer1".parse().unwrap()),
-        };
-
-        let mut proj = collab::project::Project::<CollabMock<Mock>> {
+        let mut proj = project::<CollabMock<Mock>>(
+            project_2,
+            [peer(1)],
+            project_1.peer_id(),
             agent_id,
-            id_maps: Default::default(),
-            local_peer: Peer {
-                id: project_2.peer_id(),
-                handle: PeerHandle::GitHub("peer2".parse().unwrap()),
-            },
-            peer_cursors: Default::default(),
-            peer_selections: Default::default(),
-            remote_peers: RemotePeers::new([remote_peer], &project_2),
-            root_path: AbsPathBuf::root(),
-            inner: project_2,
-        };
+        );
 
         let foo_path = path!("/foo.txt");
 
@@ -238,3 +191,61 @@ fn remote_peer_cursor_is_deleted_when_buffer_is_deleted() {
         assert_eq!(proj.peer_cursors.len(), 0);
     });
 }
+
+#[test]
+fn cursor_creation_moves_file_to_front_of_open_files() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+        "bar.txt": "hello world",
+    };
+
+    let mut project_1 =
+        collab_project::Project::from_mock(PeerId::new(1), fs.root());
+
+    project_1
+        .node_at_path_mut(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text()
+        .create_cursor(5);
+
+    let project_2 = project_1.fork(PeerId::new(2));
+
+    CollabMock::new(Mock::new(fs)).block_on(async move |ctx| {
+        let agent_id = ctx.new_agent_id();
+
+        let mut proj = project::<CollabMock<Mock>>(
+            project_2,
+            [peer(1)],
+            project_1.peer_id(),
+            agent_id,
+        );
+
+        let file_id = |proj: &collab::project::Project<_>, path| {
+            proj.inner.node_at_path(path).unwrap().unwrap_file().id()
+        };
+
+        let foo_id = file_id(&proj, path!("/foo.txt"));
+        let bar_id = file_id(&proj, path!("/bar.txt"));
+
+        let open_files = |proj: &collab::project::Project<_>| {
+            let peer = proj.remote_peers.get(project_1.peer_id()).unwrap();
+            peer.open_files().to_vec()
+        };
+
+        // Peer 1 already had a cursor in foo.txt when we joined.
+        assert_eq!(open_files(&proj), [foo_id]);
+
+        let (_, creation) = project_1
+            .node_at_path_mut(path!("/bar.txt"))
+            .unwrap()
+            .unwrap_file()
+            .unwrap_text()
+            .create_cursor(0);
+
+        proj.integrate_cursor_creation(creation, ctx);
+
+        // bar.txt is now the most recently focused file.
+        assert_eq!(open_files(&proj), [bar_id, foo_id]);
+    });
+}
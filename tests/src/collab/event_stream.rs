@@ -184,10 +184,11 @@ trait EventStreamExt<Ed: CollabEditor> {
         ctx: &mut Context<Ed>,
     ) -> impl Future<Output = EventStream<Ed>> {
         async {
-            let (_, event_stream, _) =
+            let (_, event_stream, _, _) =
                 collab::start::Start::<Ed>::read_project(
                     project_root_path,
                     PeerId::new(1),
+                    &[],
                     ctx,
                 )
                 .await
@@ -0,0 +1,195 @@
+use abs_path::{AbsPathBuf, path};
+use collab::PeerId;
+use collab::editors::mock::CollabMock;
+use collab::event::{CursorEvent, CursorEventKind, Event};
+use collab_types::Message;
+use editor::{Buffer, ByteOffset, Context, Cursor};
+use mock::{EditorExt, Mock};
+
+use crate::collab::{peer, project};
+
+#[test]
+fn following_peer_jumps_to_its_cursor_when_it_moves() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+    };
+
+    let mut project_1 =
+        collab_project::Project::from_mock(PeerId::new(1), fs.root());
+
+    let (cursor_id, _) = project_1
+        .node_at_path_mut(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text()
+        .create_cursor(0);
+
+    let project_2 = project_1.fork(PeerId::new(2));
+
+    CollabMock::new(Mock::new(fs)).block_on(async move |ctx| {
+        let agent_id = ctx.new_agent_id();
+
+        let mut proj = project::<CollabMock<Mock>>(
+            project_2,
+            [peer(1)],
+            project_1.peer_id(),
+            agent_id,
+        );
+
+        let foo_path = path!("/foo.txt");
+
+        proj.synchronize_buffer_created(
+            ctx.create_buffer(foo_path, agent_id).await.unwrap(),
+            foo_path,
+            ctx,
+        );
+
+        proj.remote_peers.set_followed(Some(project_1.peer_id()));
+
+        let movement =
+            project_1.cursor_mut(cursor_id).unwrap().unwrap().r#move(6);
+
+        let _ = proj.integrate(Message::MovedCursor(movement), ctx).await;
+
+        assert_eq!(focused_cursor(ctx), Some((foo_path.to_owned(), 6)));
+    });
+}
+
+#[test]
+fn following_peer_jumps_to_other_buffer_when_it_switches_buffers() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+        "bar.txt": "hello mars",
+    };
+
+    let mut project_1 =
+        collab_project::Project::from_mock(PeerId::new(1), fs.root());
+
+    let (foo_cursor_id, _) = project_1
+        .node_at_path_mut(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text()
+        .create_cursor(0);
+
+    let project_2 = project_1.fork(PeerId::new(2));
+
+    CollabMock::new(Mock::new(fs)).block_on(async move |ctx| {
+        let agent_id = ctx.new_agent_id();
+
+        let mut proj = project::<CollabMock<Mock>>(
+            project_2,
+            [peer(1)],
+            project_1.peer_id(),
+            agent_id,
+        );
+
+        let foo_path = path!("/foo.txt");
+
+        proj.synchronize_buffer_created(
+            ctx.create_buffer(foo_path, agent_id).await.unwrap(),
+            foo_path,
+            ctx,
+        );
+
+        proj.remote_peers.set_followed(Some(project_1.peer_id()));
+
+        // Peer 1 leaves foo.txt, removing its cursor there, and opens bar.txt,
+        // creating a new cursor in it.
+        let removal =
+            project_1.cursor_mut(foo_cursor_id).unwrap().unwrap().delete();
+
+        let (_, creation) = project_1
+            .node_at_path_mut(path!("/bar.txt"))
+            .unwrap()
+            .unwrap_file()
+            .unwrap_text()
+            .create_cursor(6);
+
+        let _ = proj.integrate(Message::RemovedCursor(removal), ctx).await;
+        let _ = proj.integrate(Message::CreatedCursor(creation), ctx).await;
+
+        // bar.txt wasn't open, so jumping to it should've opened it.
+        assert_eq!(
+            focused_cursor(ctx),
+            Some((path!("/bar.txt").to_owned(), 6))
+        );
+    });
+}
+
+#[test]
+fn moving_cursor_stops_following() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+    };
+
+    let mut project_1 =
+        collab_project::Project::from_mock(PeerId::new(1), fs.root());
+
+    project_1
+        .node_at_path_mut(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text()
+        .create_cursor(0);
+
+    let project_2 = project_1.fork(PeerId::new(2));
+
+    CollabMock::new(Mock::new(fs)).block_on(async move |ctx| {
+        let agent_id = ctx.new_agent_id();
+
+        let mut proj = project::<CollabMock<Mock>>(
+            project_2,
+            [peer(1)],
+            project_1.peer_id(),
+            agent_id,
+        );
+
+        let foo_path = path!("/foo.txt");
+
+        let foo_id = ctx.create_buffer(foo_path, agent_id).await.unwrap();
+
+        proj.synchronize_buffer_created(foo_id, foo_path, ctx);
+
+        proj.remote_peers.set_followed(Some(project_1.peer_id()));
+
+        let cursor_id = ctx.with_borrowed(|ctx| {
+            ctx.buffer(foo_id).unwrap().create_cursor(0, agent_id).id()
+        });
+
+        // Events caused by the project itself shouldn't stop following.
+        let creation = Event::Cursor(CursorEvent {
+            agent_id,
+            cursor_id,
+            kind: CursorEventKind::Created(foo_id, 0),
+        });
+
+        proj.synchronize(creation, ctx).await.unwrap();
+
+        assert!(proj.remote_peers.followed().is_some());
+
+        // The user moving their cursor should.
+        let movement = Event::Cursor(CursorEvent {
+            agent_id: ctx.new_agent_id(),
+            cursor_id,
+            kind: CursorEventKind::Moved(3),
+        });
+
+        proj.synchronize(movement, ctx).await.unwrap();
+
+        assert!(proj.remote_peers.followed().is_none());
+    });
+}
+
+/// Returns the path of the focused buffer and the offset of its cursor.
+fn focused_cursor(
+    ctx: &mut Context<CollabMock<Mock>>,
+) -> Option<(AbsPathBuf, ByteOffset)> {
+    ctx.with_borrowed(|ctx| {
+        let mut buffer = ctx.current_buffer()?;
+        let path = buffer.path().into_owned();
+        let mut offset = None;
+        buffer.for_each_cursor(|cursor| offset = Some(cursor.byte_offset()));
+        Some((path, offset?))
+    })
+}
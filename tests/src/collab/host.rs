@@ -0,0 +1,120 @@
+use collab::PeerId;
+use collab::editors::mock::CollabMock;
+use mock::{EditorExt, Mock};
+
+use crate::collab::{peer, project};
+
+#[test]
+fn peer_with_smallest_id_becomes_host_when_host_leaves() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+    };
+
+    let project_1 =
+        collab_project::Project::from_mock(PeerId::new(1), fs.root());
+
+    let project_3 = project_1.fork(PeerId::new(3));
+
+    CollabMock::new(Mock::new(fs)).block_on(async move |ctx| {
+        let mut proj = project::<CollabMock<Mock>>(
+            project_3,
+            [peer(1), peer(2)],
+            PeerId::new(1),
+            ctx.new_agent_id(),
+        );
+
+        // The host leaves, so peer 2 should take its place.
+        proj.integrate_peer_left(PeerId::new(1), ctx);
+        assert_eq!(proj.host_id.copied(), PeerId::new(2));
+
+        // Then the new host also leaves, leaving the local peer as the only
+        // one in the session.
+        proj.integrate_peer_left(PeerId::new(2), ctx);
+        assert_eq!(proj.host_id.copied(), PeerId::new(3));
+    });
+}
+
+#[test]
+fn host_is_unchanged_when_non_host_peer_leaves() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+    };
+
+    let project_1 =
+        collab_project::Project::from_mock(PeerId::new(1), fs.root());
+
+    let project_2 = project_1.fork(PeerId::new(2));
+
+    CollabMock::new(Mock::new(fs)).block_on(async move |ctx| {
+        let mut proj = project::<CollabMock<Mock>>(
+            project_2,
+            [peer(1), peer(3)],
+            PeerId::new(3),
+            ctx.new_agent_id(),
+        );
+
+        proj.integrate_peer_left(PeerId::new(1), ctx);
+        assert_eq!(proj.host_id.copied(), PeerId::new(3));
+    });
+}
+
+#[test]
+fn late_joiner_elects_same_host_as_existing_peers() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+    };
+
+    let project_1 =
+        collab_project::Project::from_mock(PeerId::new(1), fs.root());
+
+    let project_3 = project_1.fork(PeerId::new(3));
+
+    CollabMock::new(Mock::new(fs)).block_on(async move |ctx| {
+        let mut proj = project::<CollabMock<Mock>>(
+            project_3,
+            [peer(1), peer(2)],
+            PeerId::new(1),
+            ctx.new_agent_id(),
+        );
+
+        proj.integrate_peer_left(PeerId::new(1), ctx);
+
+        // A peer joining now is only told about peers 2 and 3, and it should
+        // agree with them on who the host is.
+        let other_peers = [peer(3), peer(2)];
+        let elected = collab::project::elect_host(&other_peers).unwrap();
+        assert_eq!(elected.id, proj.host_id.copied());
+    });
+}
+
+#[test]
+fn reconnecting_host_agrees_with_other_peers_on_new_host() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+    };
+
+    let project_1 =
+        collab_project::Project::from_mock(PeerId::new(1), fs.root());
+
+    CollabMock::new(Mock::new(fs)).block_on(async move |ctx| {
+        let mut proj = project::<CollabMock<Mock>>(
+            project_1,
+            [peer(2), peer(3)],
+            PeerId::new(1),
+            ctx.new_agent_id(),
+        );
+
+        // The host reconnects as peer 4. The server still thinks peer 1 is
+        // the host, but peers 2 and 3 have elected peer 2 when it left.
+        let _ = proj.integrate_reconnection(
+            peer(4),
+            vec![peer(2), peer(3)],
+            PeerId::new(1),
+            ctx,
+        );
+
+        assert_eq!(proj.host_id.copied(), PeerId::new(2));
+        assert_eq!(proj.local_peer.id, PeerId::new(4));
+        assert_eq!(proj.inner.peer_id(), PeerId::new(4));
+    });
+}
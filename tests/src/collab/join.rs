@@ -49,3 +49,65 @@ fn replicate_simple_project() {
 
     future::block_on(run_peer1.or(run_peer2).or(server.run()));
 }
+
+#[test]
+fn replicate_edit_on_disk() {
+    let fs1 = mock::fs! {
+        "foo": {
+            "mars.txt": "Hello, mars!",
+        },
+    }
+    .with_home_dir(AbsPath::root());
+
+    let server = CollabServer::default();
+
+    let peer1 = CollabMock::new(Mock::new(fs1.clone())).with_server(&server);
+
+    let peer2 = CollabMock::<Mock>::default()
+        .with_default_dir_for_remote_projects(path!("/remote"))
+        .with_server(&server);
+
+    let (session_id_tx, session_id_rx) = flume::bounded(1);
+    let (joined_tx, joined_rx) = flume::bounded(1);
+
+    let run_peer1 = peer1.run_all(async move |ctx| {
+        let collab = Collab::from(&Auth::logged_in("peer1"));
+        let session_infos = collab.start(ctx).await.unwrap();
+        session_id_tx.send(session_infos.id()).unwrap();
+
+        // Wait for peer2 to join before editing the file on disk, so that
+        // the edit has to be sent as a message.
+        joined_rx.recv_async().await.unwrap();
+
+        let mut mars = fs1
+            .node_at_path(path!("/foo/mars.txt"))
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap_file();
+        mars.write("Hello, Mars!").await.unwrap();
+    });
+
+    let run_peer2 = peer2.run(async move |ctx| {
+        let collab = Collab::from(&Auth::logged_in("peer2"));
+        let session_id = session_id_rx.recv_async().await.unwrap();
+        collab.join(session_id, ctx).await.unwrap();
+        joined_tx.send(()).unwrap();
+
+        let fs2 = ctx.fs();
+        let mars_path = path!("/remote/foo/mars.txt");
+
+        for _ in 0..100 {
+            if fs2.read_file_to_string(mars_path).await.unwrap()
+                == "Hello, Mars!"
+            {
+                return;
+            }
+            future::yield_now().await;
+        }
+
+        panic!("the edit on disk was never replicated");
+    });
+
+    future::block_on(run_peer1.or(run_peer2).or(server.run()));
+}
@@ -1,7 +1,54 @@
+mod agents;
 mod cursors;
 mod event_stream;
+mod follow;
+mod host;
 mod join;
 #[cfg(feature = "neovim")]
 mod neovim;
+mod peers;
+mod query;
+mod recording;
 mod start;
+mod statusline;
 mod text_edits;
+mod undo;
+
+use abs_path::AbsPathBuf;
+use collab::peers::RemotePeers;
+use collab::project::Project;
+use collab::{CollabEditor, Peer, PeerHandle, PeerId};
+use editor::{AgentId, Shared};
+
+/// Returns a [`Project`] rooted at `/` and wrapping the given CRDT, where the
+/// local peer is the owner of `inner` and `host_id` is the session's host.
+pub(crate) fn project<Ed: CollabEditor>(
+    inner: collab_project::Project,
+    remote_peers: impl IntoIterator<Item = Peer>,
+    host_id: PeerId,
+    agent_id: AgentId,
+) -> Project<Ed> {
+    Project {
+        agent_id,
+        blames: Default::default(),
+        host_id: Shared::new(host_id),
+        id_maps: Default::default(),
+        local_peer: peer(inner.peer_id().into_u64()),
+        peer_cursors: Default::default(),
+        peer_selections: Default::default(),
+        permissions: Default::default(),
+        remote_peers: RemotePeers::new(remote_peers, &inner),
+        root_path: AbsPathBuf::root(),
+        inner,
+        session_id: None,
+        undo_history: Default::default(),
+    }
+}
+
+/// Returns a [`Peer`] with the given ID and a GitHub handle of `peer{id}`.
+pub(crate) fn peer(id: u64) -> Peer {
+    Peer {
+        id: PeerId::new(id),
+        handle: PeerHandle::GitHub(format!("peer{id}").parse().unwrap()),
+    }
+}
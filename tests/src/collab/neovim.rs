@@ -1,6 +1,7 @@
 use collab::{CollabEditor, Peer, PeerHandle, PeerId};
-use editor::Context;
+use editor::{Buffer, Context};
 use neovim::Neovim;
+use neovim::oxi::api;
 use neovim::tests::NeovimExt;
 
 #[neovim::test]
@@ -32,3 +33,29 @@ fn create_peer_tooltip_at_start_of_buffer(ctx: &mut Context<Neovim>) {
 
     <Neovim as CollabEditor>::create_peer_tooltip(peer, 0, buffer_id, ctx);
 }
+
+#[neovim::test]
+async fn read_only_buffer_receives_remote_edits(ctx: &mut Context<Neovim>) {
+    let agent_id = ctx.new_agent_id();
+
+    let buffer_id = ctx.create_and_focus_scratch_buffer();
+
+    <Neovim as CollabEditor>::set_buffer_read_only(buffer_id, true, ctx);
+
+    let insert_hello = ctx.with_borrowed(|ctx| {
+        ctx.buffer(buffer_id).unwrap().schedule_insertion(0, "Hello", agent_id)
+    });
+
+    insert_hello.await;
+
+    let opts = api::opts::OptionOpts::builder()
+        .buf(api::Buffer::from(buffer_id))
+        .build();
+
+    ctx.with_borrowed(|ctx| {
+        assert_eq!(ctx.buffer(buffer_id).unwrap().get_text(), "Hello\n");
+    });
+
+    // The buffer should still be read-only after the edit.
+    assert!(!api::get_option_value::<bool>("modifiable", &opts).unwrap());
+}
@@ -0,0 +1,97 @@
+use core::time::Duration;
+use std::time::Instant;
+
+use abs_path::path;
+use collab::PeerId;
+use collab::editors::mock::CollabMock;
+use collab::peers::PeerActivity;
+use mock::{EditorExt, Mock};
+
+use crate::collab::{peer, project};
+
+#[test]
+fn peer_activity_from_idle_time() {
+    assert_eq!(
+        PeerActivity::from_idle_time(Duration::ZERO),
+        PeerActivity::Active
+    );
+
+    assert_eq!(
+        PeerActivity::from_idle_time(PeerActivity::IDLE_AFTER),
+        PeerActivity::Idle
+    );
+
+    assert_eq!(
+        PeerActivity::from_idle_time(
+            PeerActivity::AWAY_AFTER - Duration::from_secs(1)
+        ),
+        PeerActivity::Idle
+    );
+
+    assert_eq!(
+        PeerActivity::from_idle_time(PeerActivity::AWAY_AFTER),
+        PeerActivity::Away
+    );
+}
+
+#[test]
+fn peer_tooltip_is_dimmed_as_peer_goes_idle_and_away() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+    };
+
+    let mut project_1 =
+        collab_project::Project::from_mock(PeerId::new(1), fs.root());
+
+    let (cursor_id, _) = project_1
+        .node_at_path_mut(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text()
+        .create_cursor(5);
+
+    let project_2 = project_1.fork(PeerId::new(2));
+
+    CollabMock::new(Mock::new(fs)).block_on(async move |ctx| {
+        let agent_id = ctx.new_agent_id();
+
+        let mut proj = project::<CollabMock<Mock>>(
+            project_2,
+            [peer(1)],
+            project_1.peer_id(),
+            agent_id,
+        );
+
+        // The peer was last active when it was added to `RemotePeers`.
+        let started_at = Instant::now();
+
+        let foo_path = path!("/foo.txt");
+
+        proj.synchronize_buffer_created(
+            ctx.create_buffer(foo_path, agent_id).await.unwrap(),
+            foo_path,
+            ctx,
+        );
+
+        let activity = |proj: &collab::project::Project<_>| {
+            let tooltip = proj.peer_cursors.get(&cursor_id).unwrap();
+            let peer = proj.remote_peers.get(PeerId::new(1)).unwrap();
+            assert_eq!(tooltip.activity, peer.activity());
+            tooltip.activity
+        };
+
+        assert_eq!(activity(&proj), PeerActivity::Active);
+
+        proj.update_peer_activities(
+            started_at + PeerActivity::IDLE_AFTER,
+            ctx,
+        );
+        assert_eq!(activity(&proj), PeerActivity::Idle);
+
+        proj.update_peer_activities(
+            started_at + PeerActivity::AWAY_AFTER,
+            ctx,
+        );
+        assert_eq!(activity(&proj), PeerActivity::Away);
+    });
+}
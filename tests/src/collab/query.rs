@@ -0,0 +1,95 @@
+use abs_path::{AbsPath, path};
+use auth::Auth;
+use collab::Collab;
+use collab::editors::mock::{CollabMock, CollabServer};
+use collab::query::{FileKind, PeerSummary};
+use futures_lite::future::{self, FutureExt};
+use mock::Mock;
+
+use crate::editor::ContextExt;
+
+#[test]
+fn querying_without_sessions_returns_nothing() {
+    let collab = Collab::<CollabMock<Mock>>::from(&Auth::default());
+    assert!(collab.query_sessions().is_empty());
+}
+
+#[test]
+fn querying_joined_session_returns_host_and_files() {
+    let fs1 = mock::fs! {
+        "foo": {
+            "world.txt": "Hello, world!",
+            "mars.txt": "Hello, mars!",
+        },
+    }
+    .with_home_dir(AbsPath::root());
+
+    let server = CollabServer::default();
+
+    let peer1 = CollabMock::new(Mock::new(fs1)).with_server(&server);
+
+    let peer2 = CollabMock::<Mock>::default()
+        .with_default_dir_for_remote_projects(path!("/remote"))
+        .with_server(&server);
+
+    let (session_id_tx, session_id_rx) = flume::bounded(1);
+
+    let run_peer1 = peer1.run_all(async move |ctx| {
+        let collab = Collab::from(&Auth::logged_in("peer1"));
+        let agent_id = ctx.new_agent_id();
+        ctx.create_and_focus(path!("/foo/mars.txt"), agent_id).await;
+        let session_infos = collab.start(ctx).await.unwrap();
+        session_id_tx.send(session_infos.id()).unwrap();
+    });
+
+    let run_peer2 = peer2.run(async move |ctx| {
+        let collab = Collab::from(&Auth::logged_in("peer2"));
+        let session_id = session_id_rx.recv_async().await.unwrap();
+        collab.join(session_id, ctx).await.unwrap();
+
+        // The session refreshes the peers' positions in the background, so
+        // give it a chance to run.
+        let has_position = |peers: &[PeerSummary]| {
+            peers.iter().any(|peer| peer.file.is_some())
+        };
+        let mut peers = collab.query_peers(session_id).unwrap();
+        for _ in 0..100 {
+            if has_position(&peers) {
+                break;
+            }
+            future::yield_now().await;
+            peers = collab.query_peers(session_id).unwrap();
+        }
+
+        let [host] = &*peers else {
+            panic!("expected exactly one remote peer, got {peers:?}");
+        };
+        assert_eq!(host.handle, "peer1");
+        assert!(host.is_host);
+        assert_eq!(host.file.as_deref(), Some("/remote/foo/mars.txt"));
+        assert_eq!(host.line, Some(1));
+
+        let [session] = &*collab.query_sessions() else {
+            panic!("expected exactly one session");
+        };
+        assert_eq!(session.host, "peer1");
+        assert_eq!(session.local_peer, "peer2");
+        assert_eq!(session.peers, ["peer1"]);
+
+        let mut files = collab.query_files(session_id).unwrap();
+        files.sort_by(|lhs, rhs| lhs.path.cmp(&rhs.path));
+        let files = files
+            .into_iter()
+            .map(|file| (file.path, file.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            files,
+            [
+                ("/remote/foo/mars.txt".to_owned(), FileKind::Text),
+                ("/remote/foo/world.txt".to_owned(), FileKind::Text),
+            ]
+        );
+    });
+
+    future::block_on(run_peer1.or(run_peer2).or(server.run()));
+}
@@ -0,0 +1,216 @@
+use core::time::Duration;
+
+use abs_path::path;
+use collab::PeerId;
+use collab::editors::mock::CollabMock;
+use collab::recording::{
+    self,
+    Direction,
+    Pace,
+    RecordedMessage,
+    Recording,
+    RecordingHeader,
+    ReplayError,
+};
+use collab_types::Message;
+use editor::Buffer;
+use fs::Fs;
+use mock::Mock;
+
+use crate::collab::peer;
+
+#[test]
+fn replaying_recording_integrates_recorded_messages() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+    };
+
+    let project_1 =
+        collab_project::Project::from_mock(PeerId::new(1), fs.root());
+
+    let mut project_2 = project_1.fork(PeerId::new(2));
+
+    let insert_comma = project_2
+        .node_at_path_mut(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text()
+        .insert(5, ",");
+
+    let recording = Recording {
+        header: RecordingHeader {
+            version: 2,
+            started_at: 0,
+            project_name: "foo".to_owned(),
+            local_peer: peer(1),
+            remote_peers: vec![peer(2)],
+            host_id: PeerId::new(1),
+            encoded_project: project_1.encode(),
+        },
+        messages: vec![RecordedMessage {
+            at: Duration::from_millis(42),
+            direction: Direction::Incoming,
+            message: Message::EditedText(insert_comma),
+        }],
+    };
+
+    // Round-trip through the format the recordings are saved in.
+    let recording = recording.to_string().parse::<Recording>().unwrap();
+    assert_eq!(recording.messages.len(), 1);
+    assert_eq!(recording.messages[0].at, Duration::from_millis(42));
+
+    CollabMock::<Mock>::default().block_on(async move |ctx| {
+        let project = recording::replay(
+            &recording,
+            path!("/foo").to_owned(),
+            Pace::AsFastAsPossible,
+            ctx,
+        )
+        .await
+        .unwrap();
+
+        let contents = ctx.with_borrowed(|ctx| {
+            ctx.buffer_at_path(path!("/foo/foo.txt"))
+                .unwrap()
+                .get_text()
+                .to_string()
+        });
+
+        assert_eq!(contents, "hello, world");
+
+        project.drop(ctx);
+    });
+}
+
+#[test]
+fn replaying_recording_skips_derived_messages() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+    };
+
+    let project_1 =
+        collab_project::Project::from_mock(PeerId::new(1), fs.root());
+
+    let mut project_2 = project_1.fork(PeerId::new(2));
+
+    let mut foo = project_2
+        .node_at_path_mut(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text();
+
+    let insert_comma = foo.insert(5, ",");
+    let insert_bang = foo.insert(12, "!");
+
+    let recording = Recording {
+        header: RecordingHeader {
+            version: 2,
+            started_at: 0,
+            project_name: "foo".to_owned(),
+            local_peer: peer(1),
+            remote_peers: vec![peer(2)],
+            host_id: PeerId::new(1),
+            encoded_project: project_1.encode(),
+        },
+        messages: vec![
+            RecordedMessage {
+                at: Duration::ZERO,
+                direction: Direction::Incoming,
+                message: Message::EditedText(insert_comma),
+            },
+            // Pretend this edit was produced while integrating the previous
+            // one. It's already accounted for, so it shouldn't be replayed.
+            RecordedMessage {
+                at: Duration::ZERO,
+                direction: Direction::Derived,
+                message: Message::EditedText(insert_bang),
+            },
+        ],
+    };
+
+    CollabMock::<Mock>::default().block_on(async move |ctx| {
+        let project = recording::replay(
+            &recording,
+            path!("/foo").to_owned(),
+            Pace::AsFastAsPossible,
+            ctx,
+        )
+        .await
+        .unwrap();
+
+        let contents = ctx.with_borrowed(|ctx| {
+            ctx.buffer_at_path(path!("/foo/foo.txt"))
+                .unwrap()
+                .get_text()
+                .to_string()
+        });
+
+        assert_eq!(contents, "hello, world");
+
+        project.drop(ctx);
+    });
+}
+
+#[test]
+fn replaying_recording_into_non_empty_directory_fails() {
+    let fs = mock::fs! {
+        "foo": {
+            "important.txt": "don't delete me",
+        },
+    };
+
+    let project = collab_project::Project::new(PeerId::new(1));
+
+    let recording = Recording {
+        header: RecordingHeader {
+            version: 2,
+            started_at: 0,
+            project_name: "foo".to_owned(),
+            local_peer: peer(1),
+            remote_peers: Vec::new(),
+            host_id: PeerId::new(1),
+            encoded_project: project.encode(),
+        },
+        messages: Vec::new(),
+    };
+
+    CollabMock::new(Mock::new(fs.clone())).block_on(async move |ctx| {
+        let res = recording::replay(
+            &recording,
+            path!("/foo").to_owned(),
+            Pace::AsFastAsPossible,
+            ctx,
+        )
+        .await;
+
+        assert!(matches!(res, Err(ReplayError::ProjectRootExists(_))));
+
+        assert_eq!(
+            fs.read_file_to_string(path!("/foo/important.txt")).await.unwrap(),
+            "don't delete me"
+        );
+    });
+}
+
+#[test]
+fn parsing_recording_with_unsupported_version_fails() {
+    let project = collab_project::Project::new(PeerId::new(1));
+
+    let recording = Recording {
+        header: RecordingHeader {
+            version: 0,
+            started_at: 0,
+            project_name: "foo".to_owned(),
+            local_peer: peer(1),
+            remote_peers: Vec::new(),
+            host_id: PeerId::new(1),
+            encoded_project: project.encode(),
+        },
+        messages: Vec::new(),
+    };
+
+    assert!(matches!(
+        recording.to_string().parse::<Recording>(),
+        Err(recording::ParseRecordingError::UnsupportedVersion(0))
+    ));
+}
@@ -1,8 +1,10 @@
-use abs_path::{AbsPath, path};
+use abs_path::{AbsPath, AbsPathBuf, node, path};
 use auth::Auth;
-use collab::Collab;
+use collab::config::{ProjectRoot, RootPrecedence};
 use collab::editors::mock::CollabMock;
-use collab::start::StartError;
+use collab::start::{self, Start, StartError};
+use collab::{Collab, PeerId, snapshot};
+use fs::{Directory, File, Fs};
 use mock::{EditorExt, Mock};
 
 use crate::editor::ContextExt;
@@ -42,3 +44,248 @@ fn cannot_start_session_if_project_root_is_fs_root() {
         assert_eq!(err, StartError::ProjectRootIsFsRoot);
     });
 }
+
+#[test]
+fn read_project_skips_excluded_and_nomadignored_nodes() {
+    let fs = mock::fs! {
+        ".env": "SECRET=1",
+        ".nomadignore": "fixtures/\n",
+        "fixtures": {
+            "big.json": "{}",
+        },
+        "main.rs": "fn main() {}",
+    };
+
+    CollabMock::new(Mock::new(fs)).block_on(async |ctx| {
+        let (project, _, _, _) =
+            collab::start::Start::<CollabMock<Mock>>::read_project(
+                path!("/"),
+                PeerId::new(1),
+                &[".env".to_owned()],
+                ctx,
+            )
+            .await
+            .unwrap();
+
+        assert!(project.node_at_path(path!("/main.rs")).is_some());
+        assert!(project.node_at_path(path!("/.env")).is_none());
+        assert!(project.node_at_path(path!("/fixtures")).is_none());
+    });
+}
+
+#[test]
+fn resuming_from_snapshot_keeps_ids_of_unchanged_files() {
+    let fs = mock::fs! {
+        "project": {
+            "foo.txt": "hello",
+            "bar.txt": "world",
+        },
+    };
+
+    let editor =
+        CollabMock::new(Mock::new(fs)).with_snapshots_dir("/snapshots");
+
+    editor.block_on(async |ctx| {
+        let root_path = path!("/project");
+        let local_id = PeerId::new(1);
+
+        let (project, _, _, _) = Start::<CollabMock<Mock>>::read_project(
+            root_path,
+            local_id,
+            &[],
+            ctx,
+        )
+        .await
+        .unwrap();
+
+        snapshot::save(&project, root_path, &[local_id], ctx).await.unwrap();
+
+        // Change the project on disk while no session is running.
+        let fs = ctx.fs();
+
+        fs.node_at_path(path!("/project/bar.txt"))
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap_file()
+            .write("mars")
+            .await
+            .unwrap();
+
+        fs.dir(root_path)
+            .await
+            .unwrap()
+            .create_file(node!("baz.txt"))
+            .await
+            .unwrap();
+
+        // The server gave us the same ID as in the previous session, which
+        // shouldn't stop us from resuming it.
+        let (resumed, _, id_maps, history) =
+            Start::<CollabMock<Mock>>::read_project(
+                root_path,
+                local_id,
+                &[],
+                ctx,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(history, [local_id]);
+
+        let file_id = |proj: &collab_project::Project, path: &AbsPath| {
+            proj.node_at_path(path).unwrap().unwrap_file().id()
+        };
+
+        assert_eq!(
+            file_id(&resumed, path!("/foo.txt")),
+            file_id(&project, path!("/foo.txt"))
+        );
+
+        assert_eq!(
+            resumed
+                .node_at_path(path!("/bar.txt"))
+                .unwrap()
+                .unwrap_file()
+                .unwrap_text()
+                .contents()
+                .to_string(),
+            "mars"
+        );
+
+        // The nodes on disk should map to the files in the resumed project.
+        for path_in_proj in
+            [path!("/foo.txt"), path!("/bar.txt"), path!("/baz.txt")]
+        {
+            let node_id = fs
+                .node_at_path(root_path.concat(path_in_proj))
+                .await
+                .unwrap()
+                .unwrap()
+                .id();
+
+            assert_eq!(
+                id_maps.node2file[&node_id],
+                file_id(&resumed, path_in_proj)
+            );
+        }
+    });
+}
+
+#[test]
+fn snapshot_is_ignored_if_local_id_was_another_peers() {
+    let fs = mock::fs! {
+        "project": {
+            "foo.txt": "hello",
+        },
+    };
+
+    let editor =
+        CollabMock::new(Mock::new(fs)).with_snapshots_dir("/snapshots");
+
+    editor.block_on(async |ctx| {
+        let root_path = path!("/project");
+
+        let (project, _, _, _) = Start::<CollabMock<Mock>>::read_project(
+            root_path,
+            PeerId::new(1),
+            &[],
+            ctx,
+        )
+        .await
+        .unwrap();
+
+        let history = [PeerId::new(1), PeerId::new(2)];
+
+        snapshot::save(&project, root_path, &history, ctx).await.unwrap();
+
+        // Peer 2 was a remote peer in the saved session, so resuming as peer
+        // 2 would mix our edits with theirs.
+        let (_, _, _, history) = Start::<CollabMock<Mock>>::read_project(
+            root_path,
+            PeerId::new(2),
+            &[],
+            ctx,
+        )
+        .await
+        .unwrap();
+
+        assert!(history.is_empty());
+    });
+}
+
+#[test]
+fn project_root_is_nearest_dir_with_any_marker_by_default() {
+    let root = search_monorepo_root(ProjectRoot {
+        markers: vec![".git".to_owned(), "Cargo.toml".to_owned()],
+        ..Default::default()
+    });
+
+    assert_eq!(root, path!("/monorepo/crates/foo"));
+}
+
+#[test]
+fn project_root_is_outermost_dir_with_outermost_precedence() {
+    let root = search_monorepo_root(ProjectRoot {
+        markers: vec![".git".to_owned(), "Cargo.toml".to_owned()],
+        precedence: RootPrecedence::Outermost,
+        stop_at_home: false,
+    });
+
+    assert_eq!(root, path!("/monorepo"));
+}
+
+#[test]
+fn project_root_search_stops_at_home_dir() {
+    // The home directory is /monorepo/crates, so the .git directory in
+    // /monorepo is never reached.
+    let root = search_monorepo_root(ProjectRoot {
+        markers: vec![".git".to_owned(), "Cargo.toml".to_owned()],
+        precedence: RootPrecedence::Outermost,
+        stop_at_home: true,
+    });
+
+    assert_eq!(root, path!("/monorepo/crates/foo"));
+}
+
+#[test]
+fn project_root_falls_back_to_parent_if_no_marker_matches() {
+    let root = search_monorepo_root(ProjectRoot {
+        markers: vec!["package.json".to_owned()],
+        ..Default::default()
+    });
+
+    assert_eq!(root, path!("/monorepo/crates/foo/src"));
+}
+
+/// Searches for the root of the project containing
+/// `/monorepo/crates/foo/src/lib.rs` with the given config.
+fn search_monorepo_root(config: ProjectRoot) -> AbsPathBuf {
+    let fs = mock::fs! {
+        "monorepo": {
+            ".git": {},
+            "crates": {
+                "foo": {
+                    "Cargo.toml": "",
+                    "src": {
+                        "lib.rs": "",
+                    },
+                },
+            },
+        },
+    }
+    .with_home_dir(path!("/monorepo/crates"));
+
+    CollabMock::new(Mock::new(fs)).block_on(async move |ctx| {
+        let agent_id = ctx.new_agent_id();
+
+        let buffer_id = ctx
+            .create_and_focus(
+                path!("/monorepo/crates/foo/src/lib.rs"),
+                agent_id,
+            )
+            .await;
+
+        start::search_project_root(buffer_id, config, ctx).await.unwrap()
+    })
+}
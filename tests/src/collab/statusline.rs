@@ -0,0 +1,42 @@
+use auth::Auth;
+use collab::Collab;
+use collab::editors::mock::CollabMock;
+use collab::statusline::SessionStatus;
+use mock::Mock;
+
+fn status() -> SessionStatus {
+    SessionStatus {
+        is_connected: true,
+        is_paused: false,
+        num_pending: 0,
+        num_peers: 2,
+        project_name: "foo".to_owned(),
+    }
+}
+
+#[test]
+fn statusline_shows_number_of_peers() {
+    assert_eq!(status().to_string(), "foo: 2 peers");
+
+    let status = SessionStatus { num_peers: 1, ..status() };
+    assert_eq!(status.to_string(), "foo: 1 peer");
+}
+
+#[test]
+fn statusline_shows_pending_messages_while_paused() {
+    let status = SessionStatus { is_paused: true, num_pending: 3, ..status() };
+    assert_eq!(status.to_string(), "foo: 2 peers, paused (3 pending)");
+}
+
+#[test]
+fn statusline_shows_reconnecting_sessions() {
+    let status =
+        SessionStatus { is_connected: false, is_paused: true, ..status() };
+    assert_eq!(status.to_string(), "foo: 2 peers, reconnecting");
+}
+
+#[test]
+fn statusline_is_empty_without_sessions() {
+    let collab = Collab::<CollabMock<Mock>>::from(&Auth::default());
+    assert!(collab.statusline().is_empty());
+}
@@ -1,9 +1,10 @@
-use abs_path::{AbsPathBuf, path};
+use abs_path::path;
+use collab::PeerId;
 use collab::editors::mock::CollabMock;
-use collab::peers::RemotePeers;
-use collab::{Peer, PeerHandle, PeerId};
 use mock::{EditorExt, Mock};
 
+use crate::collab::{peer, project};
+
 #[test]
 fn integrating_text_edit_moves_remote_peer_tooltip() {
     let fs = mock::fs! {
@@ -30,24 +31,12 @@ fn integrating_text_edit_moves_remote_peer_tooltip() {
     CollabMock::new(Mock::new(fs)).block_on(async move |ctx| {
         let agent_id = ctx.new_agent_id();
 
-        let remote_peer = Peer {
-            id: project_1.peer_id(),
-            handle: PeerHandle::GitHub("peer1".parse().unwrap()),
-        };
-
-        let mut proj = collab::project::Project {
+        let mut proj = project::<CollabMock<Mock>>(
+            project_2,
+            [peer(1)],
+            project_1.peer_id(),
             agent_id,
-            id_maps: Default::default(),
-            local_peer: Peer {
-                id: project_2.peer_id(),
-                handle: PeerHandle::GitHub("peer2".parse().unwrap()),
-            },
-            peer_cursors: Default::default(),
-            peer_selections: Default::default(),
-            remote_peers: RemotePeers::new([remote_peer], &project_2),
-            root_path: AbsPathBuf::root(),
-            inner: project_2,
-        };
+        );
 
         let foo_path = path!("/foo.txt");
 
@@ -61,12 +50,12 @@ fn integrating_text_edit_moves_remote_peer_tooltip() {
 
         proj.integrate_cursor_creation(cursor_creation, ctx);
         // The tooltip should be after the space.
-        assert_eq!(*proj.peer_cursors.get(&cursor_id).unwrap(), 6);
+        assert_eq!(proj.peer_cursors.get(&cursor_id).unwrap().offset, 6);
 
         proj.integrate_text_edit(insert_comma, ctx).await.unwrap();
         // After integrating the insertion, the tooltip should stay after
         // the space.
-        assert_eq!(*proj.peer_cursors.get(&cursor_id).unwrap(), 7);
+        assert_eq!(proj.peer_cursors.get(&cursor_id).unwrap().offset, 7);
     });
 }
 
@@ -98,24 +87,12 @@ fn integrating_text_edit_creates_buffer() {
     CollabMock::new(Mock::new(fs)).block_on(async move |ctx| {
         let agent_id = ctx.new_agent_id();
 
-        let remote_peer = Peer {
-            id: project_1.peer_id(),
-            handle: PeerHandle::GitHub("peer1".parse().unwrap()),
-        };
-
-        let mut proj = collab::project::Project {
+        let mut proj = project::<CollabMock<Mock>>(
+            project_2,
+            [peer(1)],
+            project_1.peer_id(),
             agent_id,
-            id_maps: Default::default(),
-            local_peer: Peer {
-                id: project_2.peer_id(),
-                handle: PeerHandle::GitHub("peer2".parse().unwrap()),
-            },
-            peer_cursors: Default::default(),
-            peer_selections: Default::default(),
-            remote_peers: RemotePeers::new([remote_peer], &project_2),
-            root_path: AbsPathBuf::root(),
-            inner: project_2,
-        };
+        );
 
         // Make sure there are no open buffers before integrating the text edit.
         assert_eq!(ctx.buffer_ids().collect::<Vec<_>>(), []);
@@ -126,6 +103,6 @@ fn integrating_text_edit_creates_buffer() {
         assert_eq!(ctx.buffer_ids().collect::<Vec<_>>(), [buffer_id]);
 
         // The buffer should display a tooltip at the end of the file.
-        assert_eq!(*proj.peer_cursors.get(&cursor_id).unwrap(), 11);
+        assert_eq!(proj.peer_cursors.get(&cursor_id).unwrap().offset, 11);
     });
 }
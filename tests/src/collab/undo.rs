@@ -0,0 +1,191 @@
+use std::time::Instant;
+
+use abs_path::path;
+use collab::undo::{GROUP_INTERVAL, MAX_STEPS, Revert, UndoHistory};
+use collab_project::text::{TextFileMut, TextReplacement};
+use collab_project::{PeerId, Project};
+
+#[test]
+fn undo_keeps_interleaved_remote_insertions() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+    };
+
+    let mut proj_1 = Project::from_mock(PeerId::new(1), fs.root());
+    let mut proj_2 = proj_1.fork(PeerId::new(2));
+    let mut history = UndoHistory::default();
+
+    let insert_there = history.edit(
+        &mut foo(&mut proj_1),
+        [insertion(5, " there")],
+        Instant::now(),
+    );
+    proj_2.integrate_text_edit(insert_there).unwrap();
+
+    // The remote peer inserts text right after ours.
+    let insert_bang = foo(&mut proj_2).insert(11, "!");
+    proj_1.integrate_text_edit(insert_bang).unwrap();
+    assert_eq!(contents(&proj_1), "hello there! world");
+
+    let (undo, _) = history
+        .revert(&mut foo(&mut proj_1), PeerId::new(1), Revert::Undo)
+        .unwrap();
+    assert_eq!(contents(&proj_1), "hello! world");
+
+    proj_2.integrate_text_edit(undo).unwrap();
+    assert_eq!(contents(&proj_2), "hello! world");
+
+    let (redo, _) = history
+        .revert(&mut foo(&mut proj_1), PeerId::new(1), Revert::Redo)
+        .unwrap();
+    assert_eq!(contents(&proj_1), "hello there! world");
+
+    proj_2.integrate_text_edit(redo).unwrap();
+    assert_eq!(contents(&proj_2), "hello there! world");
+}
+
+#[test]
+fn undo_after_concurrent_remote_edit() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+    };
+
+    let mut proj_1 = Project::from_mock(PeerId::new(1), fs.root());
+    let mut proj_2 = proj_1.fork(PeerId::new(2));
+    let mut history = UndoHistory::default();
+
+    // Both peers edit the file before seeing each other's edit.
+    let insert_bang = history.edit(
+        &mut foo(&mut proj_1),
+        [insertion(11, "!")],
+        Instant::now(),
+    );
+    let delete_hello = foo(&mut proj_2).delete(0..6);
+
+    proj_1.integrate_text_edit(delete_hello).unwrap();
+    proj_2.integrate_text_edit(insert_bang).unwrap();
+    assert_eq!(contents(&proj_1), "world!");
+
+    let (undo, _) = history
+        .revert(&mut foo(&mut proj_1), PeerId::new(1), Revert::Undo)
+        .unwrap();
+    assert_eq!(contents(&proj_1), "world");
+
+    proj_2.integrate_text_edit(undo).unwrap();
+    assert_eq!(contents(&proj_2), "world");
+}
+
+#[test]
+fn new_edit_clears_redo_history() {
+    let fs = mock::fs! {
+        "foo.txt": "hello",
+    };
+
+    let mut proj = Project::from_mock(PeerId::new(1), fs.root());
+    let mut history = UndoHistory::default();
+    let started_at = Instant::now();
+
+    history.edit(&mut foo(&mut proj), [insertion(5, "!")], started_at);
+    history.revert(&mut foo(&mut proj), PeerId::new(1), Revert::Undo).unwrap();
+
+    history.edit(
+        &mut foo(&mut proj),
+        [insertion(5, "?")],
+        started_at + GROUP_INTERVAL * 2,
+    );
+
+    assert!(
+        history
+            .revert(&mut foo(&mut proj), PeerId::new(1), Revert::Redo)
+            .is_none()
+    );
+    assert_eq!(contents(&proj), "hello?");
+}
+
+#[test]
+fn edits_less_than_a_second_apart_are_undone_together() {
+    let fs = mock::fs! {
+        "foo.txt": "",
+    };
+
+    let mut proj = Project::from_mock(PeerId::new(1), fs.root());
+    let mut history = UndoHistory::default();
+    let started_at = Instant::now();
+
+    history.edit(&mut foo(&mut proj), [insertion(0, "a")], started_at);
+    history.edit(
+        &mut foo(&mut proj),
+        [insertion(1, "b")],
+        started_at + GROUP_INTERVAL / 2,
+    );
+    history.edit(
+        &mut foo(&mut proj),
+        [insertion(2, "c")],
+        started_at + GROUP_INTERVAL * 2,
+    );
+
+    history.revert(&mut foo(&mut proj), PeerId::new(1), Revert::Undo).unwrap();
+    assert_eq!(contents(&proj), "ab");
+
+    history.revert(&mut foo(&mut proj), PeerId::new(1), Revert::Undo).unwrap();
+    assert_eq!(contents(&proj), "");
+
+    assert!(
+        history
+            .revert(&mut foo(&mut proj), PeerId::new(1), Revert::Undo)
+            .is_none()
+    );
+}
+
+#[test]
+fn oldest_steps_are_dropped_past_max_steps() {
+    let fs = mock::fs! {
+        "foo.txt": "",
+    };
+
+    let mut proj = Project::from_mock(PeerId::new(1), fs.root());
+    let mut history = UndoHistory::default();
+    let started_at = Instant::now();
+
+    for idx in 0..=MAX_STEPS {
+        let edited_at = started_at + GROUP_INTERVAL * 2 * idx as u32;
+        history.edit(&mut foo(&mut proj), [insertion(idx, "a")], edited_at);
+    }
+
+    for _ in 0..MAX_STEPS {
+        history
+            .revert(&mut foo(&mut proj), PeerId::new(1), Revert::Undo)
+            .unwrap();
+    }
+
+    // The very first edit can't be undone anymore.
+    assert!(
+        history
+            .revert(&mut foo(&mut proj), PeerId::new(1), Revert::Undo)
+            .is_none()
+    );
+    assert_eq!(contents(&proj), "a");
+}
+
+fn foo(proj: &mut Project) -> TextFileMut<'_> {
+    proj.node_at_path_mut(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text()
+}
+
+fn contents(proj: &Project) -> String {
+    proj.node_at_path(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text()
+        .contents()
+        .to_string()
+}
+
+fn insertion(offset: usize, text: &str) -> TextReplacement {
+    TextReplacement {
+        deleted_range: offset..offset,
+        inserted_text: text.into(),
+    }
+}
@@ -60,3 +60,38 @@ fn insertion_can_be_integrated_after_roundtrip() {
     assert_eq!(replacements.next().unwrap(), replacement);
     assert_eq!(replacements.next(), None);
 }
+
+#[test]
+fn authorship_after_roundtrip() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+    };
+
+    let proj_1 = Project::from_mock(PeerId::new(1), fs.root());
+
+    let mut proj_2 = proj_1.fork(PeerId::new(2));
+
+    proj_2
+        .node_at_path_mut(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text()
+        .insert(5, ",");
+
+    let proj_3 = Project::decode(&proj_2.encode(), PeerId::new(3)).unwrap();
+
+    let foo_txt = proj_3
+        .node_at_path(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text();
+
+    assert_eq!(
+        foo_txt.authorship().collect::<Vec<_>>(),
+        [
+            (0..5, PeerId::new(1)),
+            (5..6, PeerId::new(2)),
+            (6..12, PeerId::new(1)),
+        ]
+    );
+}
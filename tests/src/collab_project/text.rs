@@ -1,4 +1,5 @@
 use abs_path::path;
+use collab_project::text::AnchorBias;
 use collab_project::{PeerId, Project};
 
 #[test]
@@ -33,3 +34,206 @@ fn integrate_deletions_in_opposite_order() {
 
     assert_eq!(foo_txt_2.contents(), "hello wo");
 }
+
+#[test]
+fn authorship_tracks_local_and_remote_insertions() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+    };
+
+    let mut proj_1 = Project::from_mock(PeerId::new(1), fs.root());
+
+    let mut proj_2 = proj_1.fork(PeerId::new(2));
+
+    let insert_comma = proj_2
+        .node_at_path_mut(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text()
+        .insert(5, ",");
+
+    proj_1.integrate_text_edit(insert_comma).unwrap();
+
+    proj_1
+        .node_at_path_mut(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text()
+        .insert(12, "!");
+
+    let foo_txt = proj_1
+        .node_at_path(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text();
+
+    assert_eq!(foo_txt.contents(), "hello, world!");
+
+    // The "!" is merged with the text the file was created with, since both
+    // were written by peer 1.
+    assert_eq!(
+        foo_txt.authorship().collect::<Vec<_>>(),
+        [
+            (0..5, PeerId::new(1)),
+            (5..6, PeerId::new(2)),
+            (6..13, PeerId::new(1)),
+        ]
+    );
+}
+
+#[test]
+fn authorship_is_kept_in_sync_with_deletions() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+    };
+
+    let mut proj_1 = Project::from_mock(PeerId::new(1), fs.root());
+
+    let mut proj_2 = proj_1.fork(PeerId::new(2));
+
+    let mut foo_txt_2 = proj_2
+        .node_at_path_mut(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text();
+
+    let insert_comma = foo_txt_2.insert(5, ",");
+    let insert_bang = foo_txt_2.insert(12, "!");
+
+    proj_1.integrate_text_edit(insert_comma).unwrap();
+    proj_1.integrate_text_edit(insert_bang).unwrap();
+
+    // Delete ", world", which removes the comma and leaves "hello" right next
+    // to the "!".
+    let delete = proj_1
+        .node_at_path_mut(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text()
+        .delete(5..12);
+
+    proj_2.integrate_text_edit(delete).unwrap();
+
+    for proj in [&proj_1, &proj_2] {
+        let foo_txt = proj
+            .node_at_path(path!("/foo.txt"))
+            .unwrap()
+            .unwrap_file()
+            .unwrap_text();
+
+        assert_eq!(foo_txt.contents(), "hello!");
+
+        assert_eq!(
+            foo_txt.authorship().collect::<Vec<_>>(),
+            [(0..5, PeerId::new(1)), (5..6, PeerId::new(2))]
+        );
+    }
+}
+
+#[test]
+fn authorship_survives_many_interleaved_edits() {
+    let fs = mock::fs! {
+        "foo.txt": "a",
+    };
+
+    let mut proj_1 = Project::from_mock(PeerId::new(1), fs.root());
+
+    let mut proj_2 = proj_1.fork(PeerId::new(2));
+
+    // Peer 1 only ever writes "a"s and peer 2 only ever writes "b"s, so the
+    // authorship can be read back from the contents.
+    for idx in 0..200 {
+        let insert_a = proj_1
+            .node_at_path_mut(path!("/foo.txt"))
+            .unwrap()
+            .unwrap_file()
+            .unwrap_text()
+            .insert(2 * idx + 1, "a");
+
+        proj_2.integrate_text_edit(insert_a).unwrap();
+
+        let insert_b = proj_2
+            .node_at_path_mut(path!("/foo.txt"))
+            .unwrap()
+            .unwrap_file()
+            .unwrap_text()
+            .insert(2 * idx + 2, "b");
+
+        proj_1.integrate_text_edit(insert_b).unwrap();
+    }
+
+    for range in [50..250, 0..10, 100..101] {
+        let delete = proj_1
+            .node_at_path_mut(path!("/foo.txt"))
+            .unwrap()
+            .unwrap_file()
+            .unwrap_text()
+            .delete(range);
+
+        proj_2.integrate_text_edit(delete).unwrap();
+    }
+
+    for proj in [&proj_1, &proj_2] {
+        let foo_txt = proj
+            .node_at_path(path!("/foo.txt"))
+            .unwrap()
+            .unwrap_file()
+            .unwrap_text();
+
+        let mut expected = Vec::<(core::ops::Range<usize>, PeerId)>::new();
+
+        for (offset, byte) in foo_txt.contents().bytes().enumerate() {
+            let author = PeerId::new(if byte == b'a' { 1 } else { 2 });
+            match expected.last_mut() {
+                Some((range, last_author)) if *last_author == author => {
+                    range.end += 1;
+                },
+                _ => expected.push((offset..offset + 1, author)),
+            }
+        }
+
+        assert_eq!(foo_txt.authorship().collect::<Vec<_>>(), expected);
+    }
+}
+
+#[test]
+fn anchors_are_resolved_after_remote_edits() {
+    let fs = mock::fs! {
+        "foo.txt": "hello world",
+    };
+
+    let mut proj_1 = Project::from_mock(PeerId::new(1), fs.root());
+
+    let mut proj_2 = proj_1.fork(PeerId::new(2));
+
+    let foo_txt_1 = proj_1
+        .node_at_path(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text();
+
+    let left = foo_txt_1.create_anchor(5, AnchorBias::Left);
+    let right = foo_txt_1.create_anchor(5, AnchorBias::Right);
+
+    let mut foo_txt_2 = proj_2
+        .node_at_path_mut(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text();
+
+    let insert_comma = foo_txt_2.insert(5, ",");
+    let delete_world = foo_txt_2.delete(7..12);
+
+    proj_1.integrate_text_edit(insert_comma).unwrap();
+    proj_1.integrate_text_edit(delete_world).unwrap();
+
+    let foo_txt_1 = proj_1
+        .node_at_path(path!("/foo.txt"))
+        .unwrap()
+        .unwrap_file()
+        .unwrap_text();
+
+    assert_eq!(foo_txt_1.contents(), "hello, ");
+    assert_eq!(foo_txt_1.resolve_anchor(left), Some(5));
+    assert_eq!(foo_txt_1.resolve_anchor(right), Some(6));
+}
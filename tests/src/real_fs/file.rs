@@ -0,0 +1,13 @@
+use fs::File;
+use futures_lite::future;
+
+#[test]
+fn append_keeps_existing_contents() {
+    future::block_on(async {
+        let mut file = real_fs::RealFs::default().tempfile().await.unwrap();
+        file.write("hello").await.unwrap();
+        file.append(", ").await.unwrap();
+        file.append_chunks(["world", "!"]).await.unwrap();
+        assert_eq!(file.read().await.unwrap(), b"hello, world!");
+    });
+}
@@ -1 +1,3 @@
 mod directory;
+mod file;
+mod watcher;
@@ -0,0 +1,101 @@
+use core::time::Duration;
+use std::sync::OnceLock;
+
+use abs_path::node;
+use fs::{Directory, DirectoryEvent, File, FileEvent};
+use futures_lite::{Stream, StreamExt, future};
+
+#[test]
+fn directory_creation_and_deletion() {
+    start_watcher();
+
+    future::block_on(async {
+        let dir = real_fs::RealFs::default().tempdir().await.unwrap();
+        let mut events = dir.watch();
+
+        let file = dir.create_file(node!("foo.txt")).await.unwrap();
+        let file_id = file.id();
+
+        let DirectoryEvent::Creation(creation) = next(&mut events).await
+        else {
+            panic!("expected a creation event");
+        };
+        assert_eq!(creation.node_id, file_id);
+
+        file.delete().await.unwrap();
+
+        let DirectoryEvent::Deletion(deletion) = next(&mut events).await
+        else {
+            panic!("expected a deletion event");
+        };
+        assert_eq!(deletion.node_id, file_id);
+    });
+}
+
+#[test]
+fn file_modification() {
+    start_watcher();
+
+    future::block_on(async {
+        let mut file = real_fs::RealFs::default().tempfile().await.unwrap();
+        let mut events = file.watch();
+
+        file.write(b"hello").await.unwrap();
+
+        let FileEvent::Modification(modification) = next(&mut events).await
+        else {
+            panic!("expected a modification event");
+        };
+        assert_eq!(modification.file_id, file.id());
+    });
+}
+
+/// Watching many nodes under the same directory shouldn't use up one OS
+/// watch per node.
+#[test]
+fn many_watchers_share_one_root() {
+    start_watcher();
+
+    future::block_on(async {
+        let dir = real_fs::RealFs::default().tempdir().await.unwrap();
+        let _dir_events = dir.watch();
+
+        let mut files = Vec::new();
+        for idx in 0..256 {
+            let name = format!("{idx}.txt");
+            let file = dir
+                .create_file(<&abs_path::NodeName>::try_from(&*name).unwrap())
+                .await
+                .unwrap();
+            files.push(file);
+        }
+
+        let mut streams = files.iter().map(File::watch).collect::<Vec<_>>();
+
+        let last = files.last_mut().unwrap();
+        last.write(b"hello").await.unwrap();
+
+        let FileEvent::Modification(modification) =
+            next(streams.last_mut().unwrap()).await
+        else {
+            panic!("expected a modification event");
+        };
+        assert_eq!(modification.file_id, last.id());
+    });
+}
+
+fn start_watcher() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    STARTED.get_or_init(|| {
+        // We don't care about errors here, so we drop the stream.
+        let _ = real_fs::watcher::start().unwrap();
+    });
+}
+
+async fn next<S: Stream + Unpin>(stream: &mut S) -> S::Item {
+    future::or(async { stream.next().await.unwrap() }, async {
+        async_io::Timer::after(Duration::from_secs(5)).await;
+        panic!("timed out waiting for an event");
+    })
+    .await
+}